@@ -0,0 +1,308 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{Local, TimeZone};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::{AppHandle, State};
+
+use super::envelope::{decrypt_bytes, encrypt_bytes};
+use super::history_store::{now_millis, ClipboardHistoryStore};
+use super::image_handler::{read_image_file, store_image_files};
+use super::key_protector::HistoryKey;
+use super::retention::emit_removed;
+use super::storage::ClipboardItem;
+use crate::persistence::files::write_atomic;
+
+// Layout of a bundle file: magic | version | salt | nonce | encrypted JSON payload
+const BUNDLE_MAGIC: &[u8; 4] = b"BCXB";
+const BUNDLE_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = BUNDLE_MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+#[derive(Serialize, Deserialize)]
+struct BundlePayload {
+    exported_at: u64,
+    items: Vec<BundleItem>,
+}
+
+// A history item with its image files inlined as base64 PNGs, also used by sync
+#[derive(Serialize, Deserialize)]
+pub struct BundleItem {
+    #[serde(flatten)]
+    pub item: ClipboardItem,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_png: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail_png: Option<String>,
+}
+
+// Inline the image files of an item. The thumbnail is optional, a missing
+// original is an error.
+pub fn pack_item(app_handle: &AppHandle, item: ClipboardItem) -> Result<BundleItem, String> {
+    let read_png = |path: &str| -> Result<String, String> {
+        read_image_file(app_handle, Path::new(path)).map(|png| STANDARD.encode(png))
+    };
+
+    let (image_png, thumbnail_png) = match &item.image_data {
+        Some(image) => (
+            Some(read_png(&image.file_path)?),
+            image
+                .thumbnail_path
+                .as_deref()
+                .and_then(|path| read_png(path).ok()),
+        ),
+        None => (None, None),
+    };
+
+    Ok(BundleItem {
+        item,
+        image_png,
+        thumbnail_png,
+    })
+}
+
+// Store the inlined image files and point the item at them
+pub fn unpack_item(
+    app_handle: &AppHandle,
+    bundle_item: BundleItem,
+) -> Result<ClipboardItem, String> {
+    let decode =
+        |png: Option<String>| -> Option<Vec<u8>> { png.and_then(|png| STANDARD.decode(png).ok()) };
+
+    let mut item = bundle_item.item;
+    if let Some(image) = item.image_data.as_mut() {
+        let png = decode(bundle_item.image_png).ok_or_else(|| "Missing image data".to_string())?;
+        let thumbnail = decode(bundle_item.thumbnail_png);
+        let (file_path, thumbnail_path) =
+            store_image_files(app_handle, &image.hash, &png, thumbnail.as_deref())?;
+        image.file_path = file_path;
+        image.thumbnail_path = thumbnail_path;
+    }
+
+    Ok(item)
+}
+
+#[derive(Serialize)]
+pub struct ClipboardImportSummary {
+    pub added: usize,
+    // Items that were already in the history
+    pub merged: usize,
+    // Image items whose image couldn't be restored
+    pub skipped: usize,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum TextExportFormat {
+    Json,
+    Markdown,
+}
+
+#[derive(Serialize)]
+struct TextExportItem<'a> {
+    text: &'a str,
+    timestamp: u64,
+    last_copied: u64,
+    copy_count: u64,
+    pinned: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+}
+
+fn bundle_key(password: &str, salt: &[u8]) -> Result<HistoryKey, String> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key from password: {}", e))?;
+    Ok(key)
+}
+
+fn seal_bundle(password: &str, payload: &[u8]) -> Result<Vec<u8>, String> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let (nonce, ciphertext) = encrypt_bytes(&bundle_key(password, &salt)?, payload)?;
+
+    let mut bundle = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    bundle.extend_from_slice(BUNDLE_MAGIC);
+    bundle.push(BUNDLE_VERSION);
+    bundle.extend_from_slice(&salt);
+    bundle.extend_from_slice(&nonce);
+    bundle.extend_from_slice(&ciphertext);
+    Ok(bundle)
+}
+
+fn open_bundle(password: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.len() < HEADER_LEN || !data.starts_with(BUNDLE_MAGIC) {
+        return Err("Not a clipboard history bundle".to_string());
+    }
+    let version = data[BUNDLE_MAGIC.len()];
+    if version > BUNDLE_VERSION {
+        return Err(format!(
+            "Bundle uses unsupported format version {}",
+            version
+        ));
+    }
+
+    let salt_start = BUNDLE_MAGIC.len() + 1;
+    let salt = &data[salt_start..salt_start + SALT_LEN];
+    let nonce = &data[salt_start + SALT_LEN..HEADER_LEN];
+
+    decrypt_bytes(&bundle_key(password, salt)?, nonce, &data[HEADER_LEN..])
+        .map_err(|_| "Wrong password or damaged bundle".to_string())
+}
+
+// Items that are about to expire are left out, they were never meant to be kept
+fn exportable_items(history_store: &ClipboardHistoryStore) -> Result<Vec<ClipboardItem>, String> {
+    history_store.with_items(|items| {
+        items
+            .iter()
+            .filter(|item| item.expires_at.is_none())
+            .cloned()
+            .collect()
+    })
+}
+
+// Write the history and its images to a password protected bundle. Returns
+// the number of exported items.
+#[tauri::command]
+pub fn export_clipboard_bundle(
+    path: String,
+    password: String,
+    app_handle: AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<usize, String> {
+    if password.is_empty() {
+        return Err("A password is required to export the clipboard history".to_string());
+    }
+
+    let mut items = Vec::new();
+    for item in exportable_items(&history_store)? {
+        let item_id = item.id;
+        match pack_item(&app_handle, item) {
+            Ok(bundle_item) => items.push(bundle_item),
+            Err(e) => eprintln!("Skipping clipboard item {} in export: {}", item_id, e),
+        }
+    }
+
+    let count = items.len();
+    let payload = serde_json::to_vec(&BundlePayload {
+        exported_at: now_millis(),
+        items,
+    })
+    .map_err(|e| format!("Failed to serialize clipboard history: {}", e))?;
+
+    write_atomic(Path::new(&path), &seal_bundle(&password, &payload)?)
+        .map_err(|e| format!("Failed to write clipboard bundle: {}", e))?;
+
+    Ok(count)
+}
+
+// Merge a bundle into the history. Content that is already stored keeps its
+// pinned state and the higher copy count of both.
+#[tauri::command]
+pub fn import_clipboard_bundle(
+    path: String,
+    password: String,
+    app_handle: AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardImportSummary, String> {
+    let data = fs::read(&path).map_err(|e| format!("Failed to read clipboard bundle: {}", e))?;
+    let payload: BundlePayload = serde_json::from_slice(&open_bundle(&password, &data)?)
+        .map_err(|e| format!("Failed to parse clipboard bundle: {}", e))?;
+
+    let mut items = Vec::with_capacity(payload.items.len());
+    let mut skipped = 0;
+
+    for bundle_item in payload.items {
+        match unpack_item(&app_handle, bundle_item) {
+            Ok(item) => items.push(item),
+            Err(e) => {
+                eprintln!("Skipping imported clipboard item: {}", e);
+                skipped += 1;
+            }
+        }
+    }
+
+    let merged = history_store.merge_items(items)?;
+    emit_removed(&app_handle, &merged.removed);
+
+    Ok(ClipboardImportSummary {
+        added: merged.added,
+        merged: merged.merged,
+        skipped,
+    })
+}
+
+fn format_timestamp(timestamp: u64) -> String {
+    Local
+        .timestamp_millis_opt(timestamp as i64)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+// Fence that doesn't occur in the text, so code blocks can't end early
+fn markdown_fence(text: &str) -> String {
+    let mut fence = "```".to_string();
+    while text.contains(&fence) {
+        fence.push('`');
+    }
+    fence
+}
+
+// Write the text items as plain JSON or Markdown. Sensitive items are left
+// out, the file is not encrypted. Returns the number of exported items.
+#[tauri::command]
+pub fn export_clipboard_text(
+    path: String,
+    format: TextExportFormat,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<usize, String> {
+    let items = exportable_items(&history_store)?;
+    let text_items: Vec<TextExportItem> = items
+        .iter()
+        .filter(|item| item.content_type == "text" && item.sensitive.is_none())
+        .filter_map(|item| {
+            Some(TextExportItem {
+                text: item.text.as_deref()?,
+                timestamp: item.timestamp,
+                last_copied: item.last_copied,
+                copy_count: item.copy_count,
+                pinned: item.pinned,
+                source: item.source.as_ref().map(|source| source.name.as_str()),
+            })
+        })
+        .collect();
+
+    let contents = match format {
+        TextExportFormat::Json => serde_json::to_string_pretty(&text_items)
+            .map_err(|e| format!("Failed to serialize clipboard history: {}", e))?,
+        TextExportFormat::Markdown => {
+            let mut markdown = String::from("# Clipboard history\n");
+            for item in &text_items {
+                let mut heading = format_timestamp(item.timestamp);
+                if let Some(source) = item.source {
+                    heading.push_str(&format!(" · {}", source));
+                }
+                if item.pinned {
+                    heading.push_str(" · pinned");
+                }
+
+                let fence = markdown_fence(item.text);
+                markdown.push_str(&format!(
+                    "\n## {}\n\n{}\n{}\n{}\n",
+                    heading, fence, item.text, fence
+                ));
+            }
+            markdown
+        }
+    };
+
+    write_atomic(Path::new(&path), contents.as_bytes())
+        .map_err(|e| format!("Failed to write clipboard export: {}", e))?;
+
+    Ok(text_items.len())
+}
@@ -1,38 +1,60 @@
+use tauri::State;
+
+use super::content_hash::clipboard_holds;
+use super::history_commands::restore_item;
+use super::history_store::ClipboardHistoryStore;
+use super::storage::ClipboardItem;
+
+pub fn clear_clipboard() -> Result<(), String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to create clipboard: {}", e))?;
+    clipboard
+        .clear()
+        .map_err(|e| format!("Failed to clear clipboard: {}", e))
+}
+
+#[tauri::command]
+pub fn clear_system_clipboard() -> Result<(), String> {
+    clear_clipboard()
+}
+
+// If the system clipboard holds the item, put the replacement item there
+// instead or clear it. Works for every kind of item, including images and
+// files. Returns whether the clipboard was changed.
 #[tauri::command]
-pub fn clear_system_clipboard(_window: tauri::Window) -> Result<(), String> {
-    match arboard::Clipboard::new() {
-        Ok(mut clipboard) => {
-            // Set clipboard to empty string to clear it
-            match clipboard.set_text("") {
-                Ok(_) => Ok(()),
-                Err(e) => Err(format!("Failed to clear clipboard: {}", e)),
-            }
+pub fn delete_from_clipboard(
+    item_id: u64,
+    replacement_item_id: Option<u64>,
+    app_handle: tauri::AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<bool, String> {
+    if !clipboard_holds(&history_store.get(item_id)?)? {
+        return Ok(false);
+    }
+
+    match replacement_item_id {
+        Some(replacement_id) => {
+            restore_item(&app_handle, &history_store, replacement_id, false)?;
         }
-        Err(e) => Err(format!("Failed to create clipboard: {}", e)),
+        None => clear_clipboard()?,
     }
+    Ok(true)
 }
 
+// Delete a history item and clear the system clipboard if it still holds it
 #[tauri::command]
-pub fn delete_from_clipboard(
-    _window: tauri::Window,
-    current_text: String,
-    replacement_text: Option<String>,
-) -> Result<(), String> {
-    match arboard::Clipboard::new() {
-        Ok(mut clipboard) => match clipboard.get_text() {
-            Ok(text) => {
-                if text == current_text {
-                    let new_text = replacement_text.unwrap_or_else(|| String::from(""));
-                    match clipboard.set_text(new_text) {
-                        Ok(_) => Ok(()),
-                        Err(e) => Err(format!("Failed to update clipboard: {}", e)),
-                    }
-                } else {
-                    Ok(())
-                }
-            }
-            Err(e) => Err(format!("Failed to get clipboard text: {}", e)),
-        },
-        Err(e) => Err(format!("Failed to create clipboard: {}", e)),
+pub fn delete_and_clear_clipboard_item(
+    item_id: u64,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardItem, String> {
+    let held = clipboard_holds(&history_store.get(item_id)?).unwrap_or_else(|e| {
+        eprintln!("Failed to compare clipboard content: {}", e);
+        false
+    });
+
+    let item = history_store.delete(item_id)?;
+    if held {
+        clear_clipboard()?;
     }
+    Ok(item)
 }
@@ -0,0 +1,105 @@
+use sha2::{Digest, Sha256};
+
+use super::storage::{ClipboardItem, ImageData};
+
+// The content a hash covers is part of it, so text that looks like a path
+// never matches a copied file
+enum ContentDomain {
+    Text,
+    Files,
+    Image,
+}
+
+fn domain_hash(domain: ContentDomain, content: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(match domain {
+        ContentDomain::Text => b"text".as_slice(),
+        ContentDomain::Files => b"files".as_slice(),
+        ContentDomain::Image => b"image".as_slice(),
+    });
+    hasher.update([0]);
+    hasher.update(content);
+    format!("{:x}", hasher.finalize())
+}
+
+// Line endings and trailing NULs depend on the platform and the app that
+// copied the text, not on its content
+pub fn text_content_hash(text: &str) -> String {
+    let text = text.trim_end_matches('\0').replace("\r\n", "\n");
+    domain_hash(ContentDomain::Text, text.as_bytes())
+}
+
+pub fn files_content_hash<'a>(paths: impl IntoIterator<Item = &'a str>) -> String {
+    let paths: Vec<&str> = paths.into_iter().collect();
+    domain_hash(ContentDomain::Files, paths.join("\n").as_bytes())
+}
+
+// Images are identified by the hash of their pixels, see `store_clipboard_image`
+pub fn image_content_hash(pixel_hash: &str) -> String {
+    domain_hash(ContentDomain::Image, pixel_hash.as_bytes())
+}
+
+// Identity of the content, the same on every device. Duplicates, deletions
+// and sync all compare content by it.
+pub fn content_hash(
+    content_type: &str,
+    text: Option<&str>,
+    image_data: Option<&ImageData>,
+) -> Option<String> {
+    match (image_data, text) {
+        (Some(image), _) => Some(image_content_hash(&image.hash)),
+        (None, Some(text)) if content_type == "files" => Some(files_content_hash(text.lines())),
+        (None, Some(text)) => Some(text_content_hash(text)),
+        (None, None) => None,
+    }
+}
+
+pub fn item_content_hash(item: &ClipboardItem) -> Option<String> {
+    content_hash(
+        &item.content_type,
+        item.text.as_deref(),
+        item.image_data.as_ref(),
+    )
+}
+
+// Hash of what the system clipboard holds right now. Formats are checked in
+// the same order as the clipboard monitor records them.
+pub fn current_clipboard_hash() -> Result<Option<String>, String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to create clipboard: {}", e))?;
+
+    if let Ok(files) = clipboard.get().file_list() {
+        if !files.is_empty() {
+            let paths: Vec<String> = files
+                .iter()
+                .map(|path| path.to_string_lossy().to_string())
+                .collect();
+            return Ok(Some(files_content_hash(paths.iter().map(String::as_str))));
+        }
+    }
+
+    if let Ok(text) = clipboard.get_text() {
+        if !text.is_empty() {
+            return Ok(Some(text_content_hash(&text)));
+        }
+    }
+
+    if let Ok(image) = clipboard.get_image() {
+        let mut hasher = Sha256::new();
+        hasher.update(&image.bytes);
+        return Ok(Some(image_content_hash(&format!(
+            "{:x}",
+            hasher.finalize()
+        ))));
+    }
+
+    Ok(None)
+}
+
+// True if the system clipboard still holds the content of the history item
+pub fn clipboard_holds(item: &ClipboardItem) -> Result<bool, String> {
+    match item_content_hash(item) {
+        Some(hash) => Ok(current_clipboard_hash()? == Some(hash)),
+        None => Ok(false),
+    }
+}
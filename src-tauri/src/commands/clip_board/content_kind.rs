@@ -0,0 +1,799 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tauri::{api::shell, AppHandle, Manager, State};
+
+use super::history_store::ClipboardHistoryStore;
+
+// Detection is skipped for very large texts, they are never a single value
+const MAX_DETECT_LEN: usize = 1024 * 1024;
+// Phone numbers have at most 15 digits (E.164), short codes are just numbers
+const MIN_PHONE_DIGITS: usize = 7;
+const MAX_PHONE_DIGITS: usize = 15;
+// Keyword hits needed before text counts as code
+const MIN_CODE_SCORE: u32 = 3;
+
+// What a text item contains, decides which actions the webview offers
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentKind {
+    Url,
+    Email,
+    FilePath,
+    Color { format: ColorFormat },
+    Json,
+    Code { language: Option<String> },
+    Phone,
+    Number,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorFormat {
+    Hex,
+    Rgb,
+    Hsl,
+}
+
+// Classify a text item. Plain prose has no kind.
+pub fn detect_content_kind(text: &str) -> Option<ContentKind> {
+    let text = text.trim();
+    if text.is_empty() || text.len() > MAX_DETECT_LEN {
+        return None;
+    }
+
+    if !text.contains('\n') {
+        if is_url(text) {
+            return Some(ContentKind::Url);
+        }
+        if is_email(text) {
+            return Some(ContentKind::Email);
+        }
+        if let Some((format, _)) = parse_color(text) {
+            return Some(ContentKind::Color { format });
+        }
+        if is_number(text) {
+            return Some(ContentKind::Number);
+        }
+        if is_phone(text) {
+            return Some(ContentKind::Phone);
+        }
+        if is_file_path(text) {
+            return Some(ContentKind::FilePath);
+        }
+    }
+
+    if (text.starts_with('{') || text.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    {
+        return Some(ContentKind::Json);
+    }
+
+    guess_language(text).map(|language| ContentKind::Code { language })
+}
+
+fn is_url(text: &str) -> bool {
+    if text.chars().any(char::is_whitespace) {
+        return false;
+    }
+
+    let lower = text.to_ascii_lowercase();
+    if lower.starts_with("www.") {
+        return url_target(text).is_some();
+    }
+    (lower.starts_with("http://") || lower.starts_with("https://")) && url_target(text).is_some()
+}
+
+// Address to open for a URL item, bare www. addresses get a scheme
+fn url_target(text: &str) -> Option<String> {
+    let text = text.trim();
+    let target = if text.to_ascii_lowercase().starts_with("www.") {
+        format!("https://{}", text)
+    } else {
+        text.to_string()
+    };
+
+    let url = url::Url::parse(&target).ok()?;
+    let host = url.host_str()?;
+    (host.contains('.') || host == "localhost").then_some(target)
+}
+
+fn is_email(text: &str) -> bool {
+    let address = text.strip_prefix("mailto:").unwrap_or(text);
+    let (local, domain) = match address.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+
+    !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_alphanumeric() || "._%+-'".contains(c))
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && domain
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+}
+
+fn is_number(text: &str) -> bool {
+    // Thousands separators have to group by three, "1,2" is a list
+    let integer = text.split('.').next().unwrap_or(text);
+    if integer.contains(',') {
+        let mut groups = integer.trim_start_matches(['-', '+']).split(',');
+        let first = groups.next().unwrap_or("");
+        if first.is_empty() || first.len() > 3 || !groups.all(|group| group.len() == 3) {
+            return false;
+        }
+    }
+    let compact: String = text.chars().filter(|c| *c != ',' && *c != '_').collect();
+    // Long digit runs are ids or phone numbers more often than amounts
+    let digits = compact.chars().filter(char::is_ascii_digit).count();
+    if compact.chars().all(|c| c.is_ascii_digit()) && digits > MAX_PHONE_DIGITS {
+        return false;
+    }
+    compact.parse::<f64>().is_ok_and(f64::is_finite) && compact.chars().any(|c| c.is_ascii_digit())
+}
+
+fn is_phone(text: &str) -> bool {
+    if !text
+        .chars()
+        .all(|c| c.is_ascii_digit() || " +-().".contains(c))
+    {
+        return false;
+    }
+    if !text.starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '(') {
+        return false;
+    }
+    // Dates and version numbers look alike
+    if text.contains('.') && !text.contains(' ') {
+        return false;
+    }
+    let groups: Vec<&str> = text.split('-').collect();
+    if groups.len() == 3 && groups[0].len() == 4 {
+        return false;
+    }
+
+    let digits = text.chars().filter(char::is_ascii_digit).count();
+    (MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS).contains(&digits)
+}
+
+fn is_file_path(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let windows_drive = bytes.len() > 2
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/');
+    let unc = text.starts_with("\\\\") && text.len() > 2;
+    let unix = (text.starts_with('/') && text.len() > 1 && !text.starts_with("//"))
+        || text.starts_with("~/")
+        || text.starts_with("./")
+        || text.starts_with("../");
+
+    (windows_drive || unc || unix) && !text.contains(['<', '>', '"', '|', '*', '?'])
+}
+
+// Parsed color as red, green, blue and alpha (0.0 - 1.0)
+pub type Rgba = (u8, u8, u8, f64);
+
+pub fn parse_color(text: &str) -> Option<(ColorFormat, Rgba)> {
+    let text = text.trim();
+    if let Some(hex) = text.strip_prefix('#') {
+        return parse_hex(hex).map(|color| (ColorFormat::Hex, color));
+    }
+
+    let lower = text.to_ascii_lowercase();
+    let (name, args) = lower.strip_suffix(')')?.split_once('(')?;
+    let args: Vec<&str> = args
+        .split([',', ' ', '/'])
+        .map(str::trim)
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let alpha = match args.get(3) {
+        Some(alpha) => parse_alpha(alpha)?,
+        None => 1.0,
+    };
+
+    match name.trim() {
+        "rgb" | "rgba" => {
+            let channel = |arg: &str| -> Option<u8> {
+                let value = match arg.strip_suffix('%') {
+                    Some(percent) => percent.parse::<f64>().ok()? * 2.55,
+                    None => arg.parse::<f64>().ok()?,
+                };
+                (0.0..=255.0).contains(&value).then(|| value.round() as u8)
+            };
+            Some((
+                ColorFormat::Rgb,
+                (
+                    channel(args[0])?,
+                    channel(args[1])?,
+                    channel(args[2])?,
+                    alpha,
+                ),
+            ))
+        }
+        "hsl" | "hsla" => {
+            let hue = args[0].trim_end_matches("deg").parse::<f64>().ok()?;
+            let percent = |arg: &str| -> Option<f64> {
+                let value = arg.strip_suffix('%')?.parse::<f64>().ok()?;
+                (0.0..=100.0).contains(&value).then_some(value / 100.0)
+            };
+            let (red, green, blue) = hsl_to_rgb(hue, percent(args[1])?, percent(args[2])?);
+            Some((ColorFormat::Hsl, (red, green, blue, alpha)))
+        }
+        _ => None,
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<Rgba> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digits: Vec<u8> = match hex.len() {
+        // Short forms repeat every digit, #abc is #aabbcc
+        3 | 4 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&format!("{}{}", c, c), 16).ok())
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+
+    let alpha = digits.get(3).map_or(1.0, |alpha| *alpha as f64 / 255.0);
+    Some((digits[0], digits[1], digits[2], alpha))
+}
+
+fn parse_alpha(arg: &str) -> Option<f64> {
+    let alpha = match arg.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+        None => arg.parse::<f64>().ok()?,
+    };
+    (0.0..=1.0).contains(&alpha).then_some(alpha)
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64) -> (u8, u8, u8) {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (red, green, blue) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let channel = |value: f64| ((value + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    (channel(red), channel(green), channel(blue))
+}
+
+fn rgb_to_hsl(red: u8, green: u8, blue: u8) -> (f64, f64, f64) {
+    let (red, green, blue) = (
+        red as f64 / 255.0,
+        green as f64 / 255.0,
+        blue as f64 / 255.0,
+    );
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, lightness * 100.0);
+    }
+
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == red {
+        60.0 * ((green - blue) / delta).rem_euclid(6.0)
+    } else if max == green {
+        60.0 * ((blue - red) / delta + 2.0)
+    } else {
+        60.0 * ((red - green) / delta + 4.0)
+    };
+    (hue, saturation * 100.0, lightness * 100.0)
+}
+
+// Write a color in another notation, keeping its alpha
+pub fn convert_color(text: &str, format: ColorFormat) -> Result<String, String> {
+    let (_, (red, green, blue, alpha)) =
+        parse_color(text).ok_or_else(|| "Text is not a color".to_string())?;
+    let has_alpha = alpha < 1.0;
+    let alpha_text = format!("{:.2}", alpha)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string();
+
+    Ok(match format {
+        ColorFormat::Hex if has_alpha => format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            red,
+            green,
+            blue,
+            (alpha * 255.0).round() as u8
+        ),
+        ColorFormat::Hex => format!("#{:02x}{:02x}{:02x}", red, green, blue),
+        ColorFormat::Rgb if has_alpha => {
+            format!("rgba({}, {}, {}, {})", red, green, blue, alpha_text)
+        }
+        ColorFormat::Rgb => format!("rgb({}, {}, {})", red, green, blue),
+        ColorFormat::Hsl => {
+            let (hue, saturation, lightness) = rgb_to_hsl(red, green, blue);
+            if has_alpha {
+                format!(
+                    "hsla({:.0}, {:.0}%, {:.0}%, {})",
+                    hue, saturation, lightness, alpha_text
+                )
+            } else {
+                format!("hsl({:.0}, {:.0}%, {:.0}%)", hue, saturation, lightness)
+            }
+        }
+    })
+}
+
+// Markers of a language and how strongly they point at it
+const LANGUAGE_MARKERS: &[(&str, &[(&str, u32)])] = &[
+    (
+        "rust",
+        &[
+            ("fn ", 2),
+            ("let mut ", 3),
+            ("impl ", 2),
+            ("pub fn", 3),
+            ("::", 1),
+            ("-> ", 1),
+            ("&self", 3),
+            ("match ", 1),
+            ("use std", 3),
+        ],
+    ),
+    (
+        "python",
+        &[
+            ("def ", 2),
+            ("import ", 1),
+            ("self.", 1),
+            ("elif ", 3),
+            ("print(", 1),
+            ("None", 1),
+            ("__init__", 3),
+            ("):\n", 2),
+        ],
+    ),
+    (
+        "javascript",
+        &[
+            ("const ", 1),
+            ("function ", 2),
+            ("=> ", 1),
+            ("console.log", 3),
+            ("require(", 2),
+            ("document.", 2),
+            ("let ", 1),
+            ("===", 2),
+        ],
+    ),
+    (
+        "typescript",
+        &[
+            ("interface ", 2),
+            (": string", 3),
+            (": number", 3),
+            ("export ", 1),
+            ("import {", 1),
+            ("=> ", 1),
+            ("<T>", 2),
+            ("const ", 1),
+        ],
+    ),
+    (
+        "java",
+        &[
+            ("public class", 3),
+            ("public static void", 3),
+            ("private ", 1),
+            ("System.out", 3),
+            ("import java", 3),
+            ("@Override", 3),
+            ("new ", 1),
+        ],
+    ),
+    (
+        "csharp",
+        &[
+            ("using System", 3),
+            ("namespace ", 2),
+            ("public class", 1),
+            ("Console.Write", 3),
+            ("{ get;", 3),
+            ("var ", 1),
+            ("async Task", 3),
+        ],
+    ),
+    (
+        "cpp",
+        &[
+            ("#include", 3),
+            ("std::", 2),
+            ("int main(", 3),
+            ("cout", 2),
+            ("nullptr", 3),
+            ("->", 1),
+            ("template<", 3),
+        ],
+    ),
+    (
+        "go",
+        &[
+            ("package ", 3),
+            ("func ", 2),
+            (":= ", 2),
+            ("fmt.", 3),
+            ("import (", 3),
+            ("go func", 3),
+            ("err != nil", 3),
+        ],
+    ),
+    (
+        "php",
+        &[
+            ("<?php", 5),
+            ("$this->", 3),
+            ("function ", 1),
+            ("echo ", 2),
+            ("=> ", 1),
+            ("namespace ", 1),
+        ],
+    ),
+    (
+        "ruby",
+        &[
+            ("def ", 1),
+            ("end\n", 2),
+            ("puts ", 3),
+            ("require '", 3),
+            ("attr_", 3),
+            ("do |", 3),
+        ],
+    ),
+    (
+        "sql",
+        &[
+            ("SELECT ", 2),
+            (" FROM ", 2),
+            (" WHERE ", 2),
+            ("INSERT INTO", 3),
+            ("CREATE TABLE", 3),
+            ("JOIN ", 2),
+            ("UPDATE ", 1),
+            ("GROUP BY", 2),
+        ],
+    ),
+    (
+        "html",
+        &[
+            ("<!DOCTYPE", 5),
+            ("<html", 3),
+            ("<div", 2),
+            ("</", 1),
+            ("<span", 2),
+            ("class=\"", 2),
+            ("<script", 2),
+        ],
+    ),
+    (
+        "css",
+        &[
+            ("{\n", 1),
+            ("px;", 2),
+            ("color:", 2),
+            ("margin:", 2),
+            ("padding:", 2),
+            ("display:", 2),
+            ("@media", 3),
+        ],
+    ),
+    (
+        "shell",
+        &[
+            ("#!/bin/", 5),
+            ("sudo ", 2),
+            ("echo ", 1),
+            ("export ", 1),
+            (" | grep", 3),
+            ("fi\n", 3),
+            ("$(", 2),
+            ("apt ", 2),
+        ],
+    ),
+];
+
+// Text counts as code when it scores enough on some language and has code
+// punctuation. The language is only named when it clearly wins.
+fn guess_language(text: &str) -> Option<Option<String>> {
+    let scores: Vec<(&str, u32)> = LANGUAGE_MARKERS
+        .iter()
+        .map(|(language, markers)| {
+            let score = markers
+                .iter()
+                .filter(|(marker, _)| text.contains(marker))
+                .map(|(_, weight)| weight)
+                .sum();
+            (*language, score)
+        })
+        .collect();
+
+    let (best, best_score) = scores.iter().max_by_key(|(_, score)| *score).copied()?;
+    let has_punctuation = text.contains(['{', '}', ';', '(', '<', '=']);
+    if best_score < MIN_CODE_SCORE || !has_punctuation {
+        return None;
+    }
+
+    let tied = scores
+        .iter()
+        .filter(|(_, score)| *score == best_score)
+        .count();
+    Some((tied == 1).then(|| best.to_string()))
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir()
+            .map(|home| home.join(rest))
+            .unwrap_or_else(|| PathBuf::from(path)),
+        None => PathBuf::from(path),
+    }
+}
+
+// Extensions the system runs instead of opening in a viewer
+const LAUNCHABLE_EXTENSIONS: &[&str] = &[
+    "exe", "com", "bat", "cmd", "ps1", "vbs", "vbe", "js", "jse", "wsf", "wsh", "msi", "msc",
+    "scr", "pif", "cpl", "hta", "lnk", "url", "reg", "jar", "desktop", "sh", "bash", "zsh",
+    "command", "app", "appimage", "run", "bin",
+];
+
+// Whether opening the path would start a program rather than show a document
+fn is_launchable(path: &Path) -> bool {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    if extension.is_some_and(|extension| LAUNCHABLE_EXTENSIONS.contains(&extension.as_str())) {
+        return true;
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if let Ok(metadata) = std::fs::metadata(path) {
+            return metadata.is_file() && metadata.permissions().mode() & 0o111 != 0;
+        }
+    }
+    false
+}
+
+// Open a file with its default app, or show it selected in the file manager.
+// Goes around the shell scope, which only allows web, mail and phone links.
+// Only absolute paths are opened, and programs are only ever revealed.
+fn open_path(path: &Path, reveal: bool) -> Result<(), String> {
+    if !path.is_absolute() {
+        return Err(format!("{} is not an absolute path", path.display()));
+    }
+    // A link to a program is a program as well
+    let target = path
+        .canonicalize()
+        .map_err(|_| format!("{} does not exist", path.display()))?;
+    let reveal = reveal || is_launchable(path) || is_launchable(&target);
+
+    #[cfg(target_os = "windows")]
+    let spawned = if reveal {
+        Command::new("explorer")
+            .arg(format!("/select,{}", path.display()))
+            .spawn()
+    } else {
+        Command::new("explorer").arg(path).spawn()
+    };
+    #[cfg(target_os = "macos")]
+    let spawned = if reveal {
+        Command::new("open").arg("-R").arg(path).spawn()
+    } else {
+        Command::new("open").arg(path).spawn()
+    };
+    // Most Linux file managers can't select a file, show its folder instead
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let spawned = Command::new("xdg-open")
+        .arg(match path.parent() {
+            Some(parent) if reveal && !path.is_dir() => parent,
+            _ => path,
+        })
+        .spawn();
+
+    spawned
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+// Open a URL, email address, phone number or file path item with the system
+// default handler. With `reveal` a path is shown in the file manager instead.
+#[tauri::command]
+pub fn open_clipboard_item(
+    item_id: u64,
+    reveal: Option<bool>,
+    app_handle: AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<(), String> {
+    let item = history_store.get(item_id)?;
+    let text = item
+        .text
+        .as_deref()
+        .map(str::trim)
+        .ok_or_else(|| "Clipboard item has no text".to_string())?;
+
+    let target = match item.kind {
+        Some(ContentKind::Url) => {
+            url_target(text).ok_or_else(|| "Clipboard item is not a valid URL".to_string())?
+        }
+        Some(ContentKind::Email) if text.starts_with("mailto:") => text.to_string(),
+        Some(ContentKind::Email) => format!("mailto:{}", text),
+        Some(ContentKind::Phone) => {
+            let number: String = text
+                .chars()
+                .filter(|c| c.is_ascii_digit() || *c == '+')
+                .collect();
+            format!("tel:{}", number)
+        }
+        Some(ContentKind::FilePath) => {
+            return open_path(&expand_home(text), reveal.unwrap_or(false));
+        }
+        _ => return Err("Clipboard item can't be opened".to_string()),
+    };
+
+    shell::open(&app_handle.shell_scope(), &target, None)
+        .map_err(|e| format!("Failed to open {}: {}", target, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(language: Option<&str>) -> Option<ContentKind> {
+        Some(ContentKind::Code {
+            language: language.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn single_values_are_detected() {
+        let color = |format| Some(ContentKind::Color { format });
+        let cases = [
+            ("https://example.com/path?q=1", Some(ContentKind::Url)),
+            ("  www.example.com  ", Some(ContentKind::Url)),
+            ("http://localhost:8080", Some(ContentKind::Url)),
+            ("jane.doe+tag@example.co.uk", Some(ContentKind::Email)),
+            ("mailto:jane@example.com", Some(ContentKind::Email)),
+            ("#1e90ff", color(ColorFormat::Hex)),
+            ("rgba(30, 144, 255, 0.5)", color(ColorFormat::Rgb)),
+            ("hsl(210deg 100% 56%)", color(ColorFormat::Hsl)),
+            ("1,234,567.89", Some(ContentKind::Number)),
+            ("-42", Some(ContentKind::Number)),
+            ("+1 (555) 123-4567", Some(ContentKind::Phone)),
+            ("C:\\Users\\me\\notes.txt", Some(ContentKind::FilePath)),
+            ("~/projects/app", Some(ContentKind::FilePath)),
+            ("/usr/local/bin", Some(ContentKind::FilePath)),
+            (r#"{"a": [1, 2]}"#, Some(ContentKind::Json)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(detect_content_kind(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn look_alikes_are_not_detected() {
+        let cases = [
+            "",
+            "Just a sentence about http://example.com",
+            "https://nodot",
+            "user@localhost",
+            "#12345",
+            "rgb(300, 0, 0)",
+            "1,2",
+            "12345678901234567890",
+            "2024-06-01",
+            "1.2.3.4",
+            "//network/share",
+            "C:\\bad|name",
+            "{not json",
+            "The meeting is at noon; bring snacks",
+        ];
+        for text in cases {
+            assert_eq!(detect_content_kind(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn code_names_its_language_only_when_it_clearly_wins() {
+        let cases = [
+            ("pub fn main() {\n    let mut x = 1;\n}", code(Some("rust"))),
+            (
+                "def f(self):\n    if x:\n        pass\n    elif y:\n        print(1)",
+                code(Some("python")),
+            ),
+            ("SELECT id FROM users WHERE id = 1;", code(Some("sql"))),
+            (
+                "package main\n\nfunc main() {\n\tfmt.Println(1)\n}",
+                code(Some("go")),
+            ),
+            ("<div class=\"a\"><span>x</span></div>", code(Some("html"))),
+            // PHP and JavaScript score the same, it's code in an unclear language
+            ("function f() { echo $x; let y = 1; }", code(None)),
+            // Keywords without code punctuation are prose
+            ("import the function and let it run", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(detect_content_kind(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn colors_parse_in_every_notation() {
+        let cases = [
+            ("#abc", (0xaa, 0xbb, 0xcc, 1.0)),
+            ("#1e90ff80", (30, 144, 255, 128.0 / 255.0)),
+            ("rgb(100%, 0%, 40%)", (255, 0, 102, 1.0)),
+            ("rgba(0 0 0 / 25%)", (0, 0, 0, 0.25)),
+            ("hsl(0, 100%, 50%)", (255, 0, 0, 1.0)),
+            ("hsla(-120, 100%, 50%, 0.5)", (0, 0, 255, 0.5)),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                parse_color(text).map(|(_, rgba)| rgba),
+                Some(expected),
+                "{}",
+                text
+            );
+        }
+        for text in [
+            "#ggg",
+            "rgb(1, 2)",
+            "hsl(0, 100, 50)",
+            "rgba(0, 0, 0, 2)",
+            "cmyk(0, 0, 0, 0)",
+        ] {
+            assert_eq!(parse_color(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn colors_convert_between_notations_keeping_alpha() {
+        let cases = [
+            ("#1e90ff", ColorFormat::Rgb, "rgb(30, 144, 255)"),
+            ("#1e90ff", ColorFormat::Hsl, "hsl(210, 100%, 56%)"),
+            ("rgb(255, 0, 0)", ColorFormat::Hex, "#ff0000"),
+            ("hsl(120, 100%, 25%)", ColorFormat::Hex, "#008000"),
+            ("#808080", ColorFormat::Hsl, "hsl(0, 0%, 50%)"),
+            ("rgba(255, 0, 0, 0.5)", ColorFormat::Hex, "#ff000080"),
+            ("#ff000080", ColorFormat::Rgb, "rgba(255, 0, 0, 0.5)"),
+            (
+                "rgba(0, 0, 255, 25%)",
+                ColorFormat::Hsl,
+                "hsla(240, 100%, 50%, 0.25)",
+            ),
+        ];
+        for (text, format, expected) in cases {
+            assert_eq!(
+                convert_color(text, format).as_deref(),
+                Ok(expected),
+                "{}",
+                text
+            );
+        }
+        assert!(convert_color("blue", ColorFormat::Hex).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::key_protector::HistoryKey;
+
+// Version 1 was a bare base64 string of nonce ‖ ciphertext without a key id
+pub const ENVELOPE_VERSION: u32 = 2;
+pub const NONCE_LEN: usize = 12;
+
+// On-disk format of encrypted clipboard data
+#[derive(Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u32,
+    // Identifies the key that sealed the data, empty for version 1
+    pub key_id: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+// Short public fingerprint of a key, so a wrong key can be told apart from corrupt data
+pub fn key_id(key: &HistoryKey) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(b"blazecast-history-key");
+    hasher.update(key);
+    hasher.finalize()[..8]
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub fn encrypt_bytes(
+    key: &HistoryKey,
+    plaintext: &[u8],
+) -> Result<([u8; NONCE_LEN], Vec<u8>), String> {
+    // Generate a random nonce
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let ciphertext = encrypt_bytes_with_nonce(key, &nonce_bytes, plaintext)?;
+    Ok((nonce_bytes, ciphertext))
+}
+
+// The caller makes sure a nonce is never used twice with the same key
+pub fn encrypt_bytes_with_nonce(
+    key: &HistoryKey,
+    nonce: &[u8; NONCE_LEN],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, String> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .encrypt(Nonce::from_slice(nonce), plaintext)
+        .map_err(|e| format!("Encryption failed: {:?}", e))
+}
+
+pub fn decrypt_bytes(key: &HistoryKey, nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    if nonce.len() != NONCE_LEN {
+        return Err("Invalid encrypted data format".to_string());
+    }
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|e| format!("Decryption failed: {:?}", e))
+}
+
+pub fn seal(key: &HistoryKey, plaintext: &[u8]) -> Result<String, String> {
+    let (nonce, ciphertext) = encrypt_bytes(key, plaintext)?;
+
+    let envelope = Envelope {
+        version: ENVELOPE_VERSION,
+        key_id: key_id(key),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    };
+
+    serde_json::to_string(&envelope).map_err(|e| format!("Failed to serialize envelope: {}", e))
+}
+
+// Parse either envelope version
+pub fn parse(data: &str) -> Result<Envelope, String> {
+    let data = data.trim();
+
+    if data.starts_with('{') {
+        let envelope: Envelope = serde_json::from_str(data)
+            .map_err(|e| format!("Invalid encrypted data format: {}", e))?;
+        if envelope.version > ENVELOPE_VERSION {
+            return Err(format!(
+                "Encrypted data uses unsupported format version {}",
+                envelope.version
+            ));
+        }
+        return Ok(envelope);
+    }
+
+    let combined = STANDARD
+        .decode(data)
+        .map_err(|e| format!("Base64 decoding failed: {}", e))?;
+    if combined.len() < NONCE_LEN {
+        return Err("Invalid encrypted data format".to_string());
+    }
+
+    Ok(Envelope {
+        version: 1,
+        key_id: String::new(),
+        nonce: STANDARD.encode(&combined[..NONCE_LEN]),
+        ciphertext: STANDARD.encode(&combined[NONCE_LEN..]),
+    })
+}
+
+pub fn open(key: &HistoryKey, data: &str) -> Result<Vec<u8>, String> {
+    let envelope = parse(data)?;
+
+    if !envelope.key_id.is_empty() && envelope.key_id != key_id(key) {
+        return Err(format!(
+            "Data was encrypted with a different key ({}, current key is {})",
+            envelope.key_id,
+            key_id(key)
+        ));
+    }
+
+    let nonce = STANDARD
+        .decode(&envelope.nonce)
+        .map_err(|e| format!("Base64 decoding failed: {}", e))?;
+    let ciphertext = STANDARD
+        .decode(&envelope.ciphertext)
+        .map_err(|e| format!("Base64 decoding failed: {}", e))?;
+
+    decrypt_bytes(key, &nonce, &ciphertext)
+}
+
+// Binary variant for image files: magic | key id | nonce | ciphertext
+const BINARY_MAGIC: &[u8; 4] = b"BCE2";
+const KEY_ID_LEN: usize = 16;
+
+pub fn is_sealed_bytes(data: &[u8]) -> bool {
+    data.starts_with(BINARY_MAGIC)
+}
+
+pub fn seal_bytes(key: &HistoryKey, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let (nonce, ciphertext) = encrypt_bytes(key, plaintext)?;
+
+    let mut sealed =
+        Vec::with_capacity(BINARY_MAGIC.len() + KEY_ID_LEN + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(BINARY_MAGIC);
+    sealed.extend_from_slice(key_id(key).as_bytes());
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub fn open_bytes(key: &HistoryKey, data: &[u8]) -> Result<Vec<u8>, String> {
+    let header_len = BINARY_MAGIC.len() + KEY_ID_LEN + NONCE_LEN;
+    if !is_sealed_bytes(data) || data.len() < header_len {
+        return Err("Invalid encrypted data format".to_string());
+    }
+
+    let stored_key_id =
+        String::from_utf8_lossy(&data[BINARY_MAGIC.len()..BINARY_MAGIC.len() + KEY_ID_LEN]);
+    if stored_key_id != key_id(key) {
+        return Err(format!(
+            "Data was encrypted with a different key ({}, current key is {})",
+            stored_key_id,
+            key_id(key)
+        ));
+    }
+
+    let nonce = &data[BINARY_MAGIC.len() + KEY_ID_LEN..header_len];
+    decrypt_bytes(key, nonce, &data[header_len..])
+}
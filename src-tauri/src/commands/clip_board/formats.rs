@@ -0,0 +1,542 @@
+use serde::{Deserialize, Serialize};
+
+// Formats captured next to the plain text / image of a history item
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct ClipboardFormats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rtf: Option<String>,
+    // Absolute paths of copied files
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<Vec<String>>,
+    // Page the content was copied from, if the source app tells us
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+}
+
+impl ClipboardFormats {
+    pub fn is_empty(&self) -> bool {
+        self.html.is_none()
+            && self.rtf.is_none()
+            && self.files.is_none()
+            && self.source_url.is_none()
+    }
+}
+
+// Read every format we know about besides plain text and images
+pub fn capture_formats(clipboard: &mut arboard::Clipboard) -> ClipboardFormats {
+    let mut formats = ClipboardFormats {
+        html: clipboard.get().html().ok().filter(|html| !html.is_empty()),
+        files: clipboard
+            .get()
+            .file_list()
+            .ok()
+            .filter(|files| !files.is_empty())
+            .map(|files| {
+                files
+                    .iter()
+                    .map(|path| path.to_string_lossy().to_string())
+                    .collect()
+            }),
+        ..Default::default()
+    };
+
+    let native = native::read_formats();
+    formats.rtf = native.rtf;
+    formats.source_url = native.source_url;
+
+    formats
+}
+
+// True when the app that owns the clipboard asked history tools to ignore it
+pub fn has_exclusion_hint() -> bool {
+    native::has_exclusion_hint()
+}
+
+// Put an entry back on the clipboard with all of its formats
+pub fn restore_formats(text: Option<&str>, formats: &ClipboardFormats) -> Result<(), String> {
+    native::write_formats(text, formats)
+}
+
+// Put an image back together with the formats copied next to it, e.g. the
+// markup and page URL of an image copied from a browser
+pub fn restore_image_formats(
+    image: arboard::ImageData,
+    formats: &ClipboardFormats,
+) -> Result<(), String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to create clipboard: {}", e))?;
+    clipboard
+        .set_image(image)
+        .map_err(|e| format!("Failed to set clipboard image: {}", e))?;
+
+    native::add_formats(formats)
+}
+
+// Put only the plain text on the clipboard
+pub fn restore_plain_text(text: &str) -> Result<(), String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to create clipboard: {}", e))?;
+    clipboard
+        .set_text(text)
+        .map_err(|e| format!("Failed to set clipboard text: {}", e))
+}
+
+// Formats that arboard can't read for us
+#[derive(Default)]
+struct NativeFormats {
+    rtf: Option<String>,
+    source_url: Option<String>,
+}
+
+// Clipboard text is usually UTF-8, but some apps hand out UTF-16 with a trailing NUL
+fn decode_text(bytes: &[u8]) -> Option<String> {
+    let looks_utf16 = bytes.len() >= 2 && bytes[1] == 0;
+    let text = if looks_utf16 {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(bytes).to_string()
+    };
+
+    let text = text.trim_end_matches('\0').to_string();
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod native {
+    use std::ptr;
+    use std::thread;
+    use std::time::Duration;
+    use winapi::shared::minwindef::UINT;
+    use winapi::um::winbase::{
+        GlobalAlloc, GlobalFree, GlobalLock, GlobalSize, GlobalUnlock, GMEM_MOVEABLE,
+    };
+    use winapi::um::winuser::{
+        CloseClipboard, EmptyClipboard, GetClipboardData, IsClipboardFormatAvailable,
+        OpenClipboard, RegisterClipboardFormatW, SetClipboardData, CF_HDROP, CF_UNICODETEXT,
+    };
+
+    use super::{decode_text, ClipboardFormats, NativeFormats};
+
+    // Closes the clipboard again when dropped
+    struct OpenedClipboard;
+
+    impl OpenedClipboard {
+        fn open() -> Result<Self, String> {
+            // Another app may hold the clipboard for a moment right after a copy
+            for _ in 0..10 {
+                if unsafe { OpenClipboard(ptr::null_mut()) } != 0 {
+                    return Ok(OpenedClipboard);
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err("Failed to open clipboard".to_string())
+        }
+    }
+
+    impl Drop for OpenedClipboard {
+        fn drop(&mut self) {
+            unsafe {
+                CloseClipboard();
+            }
+        }
+    }
+
+    fn register_format(name: &str) -> UINT {
+        let wide: Vec<u16> = name.encode_utf16().chain(Some(0)).collect();
+        unsafe { RegisterClipboardFormatW(wide.as_ptr()) }
+    }
+
+    fn read_data(format: UINT) -> Option<Vec<u8>> {
+        unsafe {
+            let handle = GetClipboardData(format);
+            if handle.is_null() {
+                return None;
+            }
+
+            let data = GlobalLock(handle) as *const u8;
+            if data.is_null() {
+                return None;
+            }
+            let bytes = std::slice::from_raw_parts(data, GlobalSize(handle)).to_vec();
+            GlobalUnlock(handle);
+
+            Some(bytes)
+        }
+    }
+
+    fn write_data(format: UINT, bytes: &[u8]) -> Result<(), String> {
+        unsafe {
+            let handle = GlobalAlloc(GMEM_MOVEABLE, bytes.len());
+            if handle.is_null() {
+                return Err("Failed to allocate clipboard memory".to_string());
+            }
+
+            let data = GlobalLock(handle) as *mut u8;
+            if data.is_null() {
+                GlobalFree(handle);
+                return Err("Failed to lock clipboard memory".to_string());
+            }
+            ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+            GlobalUnlock(handle);
+
+            // The clipboard owns the memory once this succeeds
+            if SetClipboardData(format, handle).is_null() {
+                GlobalFree(handle);
+                return Err("Failed to set clipboard data".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // CF_HTML carries its metadata as "Key:Value" lines in front of the markup
+    fn html_source_url(cf_html: &str) -> Option<String> {
+        cf_html
+            .lines()
+            .take_while(|line| !line.starts_with('<'))
+            .find_map(|line| line.strip_prefix("SourceURL:"))
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())
+    }
+
+    // Wrap a fragment in the CF_HTML header with byte offsets
+    fn build_cf_html(html: &str, source_url: Option<&str>) -> Vec<u8> {
+        const HEADER: &str = "Version:0.9\r\nStartHTML:{start_html}\r\nEndHTML:{end_html}\r\nStartFragment:{start_fragment}\r\nEndFragment:{end_fragment}\r\n";
+        const PREFIX: &str = "<html><body>\r\n<!--StartFragment-->";
+        const SUFFIX: &str = "<!--EndFragment-->\r\n</body></html>";
+
+        let source_line = source_url
+            .map(|url| format!("SourceURL:{}\r\n", url))
+            .unwrap_or_default();
+        // Every placeholder becomes a 10 digit number
+        let header_len = HEADER.len()
+            - "{start_html}{end_html}{start_fragment}{end_fragment}".len()
+            + 4 * 10
+            + source_line.len();
+
+        let start_html = header_len;
+        let start_fragment = start_html + PREFIX.len();
+        let end_fragment = start_fragment + html.len();
+        let end_html = end_fragment + SUFFIX.len();
+
+        let mut cf_html = HEADER
+            .replace("{start_html}", &format!("{:010}", start_html))
+            .replace("{end_html}", &format!("{:010}", end_html))
+            .replace("{start_fragment}", &format!("{:010}", start_fragment))
+            .replace("{end_fragment}", &format!("{:010}", end_fragment));
+        cf_html.push_str(&source_line);
+        cf_html.push_str(PREFIX);
+        cf_html.push_str(html);
+        cf_html.push_str(SUFFIX);
+
+        let mut bytes = cf_html.into_bytes();
+        bytes.push(0);
+        bytes
+    }
+
+    // DROPFILES header followed by NUL separated UTF-16 paths and a final NUL
+    fn build_drop_files(files: &[String]) -> Vec<u8> {
+        const HEADER_LEN: u32 = 20;
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&HEADER_LEN.to_le_bytes()); // pFiles
+        bytes.extend_from_slice(&0i32.to_le_bytes()); // pt.x
+        bytes.extend_from_slice(&0i32.to_le_bytes()); // pt.y
+        bytes.extend_from_slice(&0i32.to_le_bytes()); // fNC
+        bytes.extend_from_slice(&1i32.to_le_bytes()); // fWide
+
+        for file in files {
+            for unit in file.encode_utf16().chain(Some(0)) {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+        bytes
+    }
+
+    pub(super) fn read_formats() -> NativeFormats {
+        let _clipboard = match OpenedClipboard::open() {
+            Ok(clipboard) => clipboard,
+            Err(_) => return NativeFormats::default(),
+        };
+
+        NativeFormats {
+            rtf: read_data(register_format("Rich Text Format"))
+                .and_then(|bytes| decode_text(&bytes)),
+            source_url: read_data(register_format("HTML Format"))
+                .and_then(|bytes| decode_text(&bytes))
+                .and_then(|cf_html| html_source_url(&cf_html)),
+        }
+    }
+
+    // Password managers mark their entries so clipboard history tools leave them alone
+    pub(super) fn has_exclusion_hint() -> bool {
+        let _clipboard = match OpenedClipboard::open() {
+            Ok(clipboard) => clipboard,
+            Err(_) => return false,
+        };
+
+        let excluded = unsafe {
+            IsClipboardFormatAvailable(register_format(
+                "ExcludeClipboardContentFromMonitorProcessing",
+            ))
+        };
+        if excluded != 0 {
+            return true;
+        }
+
+        // A DWORD that is zero when the content must stay out of the history
+        read_data(register_format("CanIncludeInClipboardHistory"))
+            .filter(|bytes| bytes.len() >= 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) == 0)
+            .unwrap_or(false)
+    }
+
+    pub(super) fn write_formats(
+        text: Option<&str>,
+        formats: &ClipboardFormats,
+    ) -> Result<(), String> {
+        let _clipboard = OpenedClipboard::open()?;
+        if unsafe { EmptyClipboard() } == 0 {
+            return Err("Failed to empty clipboard".to_string());
+        }
+
+        if let Some(text) = text {
+            let wide: Vec<u8> = text
+                .encode_utf16()
+                .chain(Some(0))
+                .flat_map(|unit| unit.to_le_bytes())
+                .collect();
+            write_data(CF_UNICODETEXT, &wide)?;
+        }
+        if let Some(html) = &formats.html {
+            write_data(
+                register_format("HTML Format"),
+                &build_cf_html(html, formats.source_url.as_deref()),
+            )?;
+        }
+        if let Some(rtf) = &formats.rtf {
+            let mut bytes = rtf.as_bytes().to_vec();
+            bytes.push(0);
+            write_data(register_format("Rich Text Format"), &bytes)?;
+        }
+        if let Some(files) = &formats.files {
+            write_data(CF_HDROP, &build_drop_files(files))?;
+        }
+
+        Ok(())
+    }
+
+    // Add HTML and RTF next to what is already on the clipboard
+    pub(super) fn add_formats(formats: &ClipboardFormats) -> Result<(), String> {
+        if formats.html.is_none() && formats.rtf.is_none() {
+            return Ok(());
+        }
+
+        let _clipboard = OpenedClipboard::open()?;
+        if let Some(html) = &formats.html {
+            write_data(
+                register_format("HTML Format"),
+                &build_cf_html(html, formats.source_url.as_deref()),
+            )?;
+        }
+        if let Some(rtf) = &formats.rtf {
+            let mut bytes = rtf.as_bytes().to_vec();
+            bytes.push(0);
+            write_data(register_format("Rich Text Format"), &bytes)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, WindowClass,
+    };
+    use x11rb::protocol::Event;
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::CURRENT_TIME;
+
+    use super::{decode_text, ClipboardFormats, NativeFormats};
+
+    // Owners that don't answer within this are skipped
+    const CONVERT_TIMEOUT: Duration = Duration::from_millis(200);
+
+    fn intern(conn: &RustConnection, name: &str) -> Option<Atom> {
+        Some(
+            conn.intern_atom(false, name.as_bytes())
+                .ok()?
+                .reply()
+                .ok()?
+                .atom,
+        )
+    }
+
+    // Ask the CLIPBOARD owner to convert its content to `target` and read the result.
+    // Large transfers (INCR) are not supported, they are skipped.
+    fn read_target(
+        conn: &RustConnection,
+        window: u32,
+        clipboard: Atom,
+        property: Atom,
+        target: &str,
+    ) -> Option<Vec<u8>> {
+        let target = intern(conn, target)?;
+        conn.convert_selection(window, clipboard, target, property, CURRENT_TIME)
+            .ok()?;
+        conn.flush().ok()?;
+
+        let deadline = Instant::now() + CONVERT_TIMEOUT;
+        loop {
+            match conn.poll_for_event().ok()? {
+                Some(Event::SelectionNotify(notify)) if notify.target == target => {
+                    if notify.property == x11rb::NONE {
+                        return None;
+                    }
+                    break;
+                }
+                Some(_) => {}
+                None if Instant::now() >= deadline => return None,
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+
+        let reply = conn
+            .get_property(true, window, property, AtomEnum::ANY, 0, u32::MAX / 4)
+            .ok()?
+            .reply()
+            .ok()?;
+        if reply.type_ == intern(conn, "INCR")? {
+            return None;
+        }
+        Some(reply.value)
+    }
+
+    pub(super) fn read_formats() -> NativeFormats {
+        with_clipboard_reader(|read| NativeFormats {
+            rtf: read("text/rtf"),
+            // Chromium and Firefox publish the page URL under their own targets
+            source_url: read("chromium/x-source-url")
+                .or_else(|| read("text/x-moz-url-priv"))
+                .map(|url| url.lines().next().unwrap_or_default().trim().to_string())
+                .filter(|url| !url.is_empty()),
+        })
+        .unwrap_or_default()
+    }
+
+    // KeePassXC and other password managers mark their entries for KDE's Klipper
+    pub(super) fn has_exclusion_hint() -> bool {
+        with_clipboard_reader(|read| read("x-kde-passwordManagerHint"))
+            .flatten()
+            .map(|hint| hint.trim() == "secret")
+            .unwrap_or(false)
+    }
+
+    // Run `f` with a function that reads one CLIPBOARD target as text
+    fn with_clipboard_reader<T>(f: impl FnOnce(&dyn Fn(&str) -> Option<String>) -> T) -> Option<T> {
+        let (conn, screen_num) = x11rb::connect(None).ok()?;
+        let root = conn.setup().roots[screen_num].root;
+
+        let window = conn.generate_id().ok()?;
+        conn.create_window(
+            0,
+            window,
+            root,
+            0,
+            0,
+            1,
+            1,
+            0,
+            WindowClass::INPUT_ONLY,
+            0,
+            &CreateWindowAux::new(),
+        )
+        .ok()?;
+
+        let clipboard = intern(&conn, "CLIPBOARD")?;
+        let property = intern(&conn, "BLAZECAST_CLIPBOARD")?;
+        let read = |target: &str| {
+            read_target(&conn, window, clipboard, property, target)
+                .and_then(|bytes| decode_text(&bytes))
+        };
+
+        let result = f(&read);
+
+        let _ = conn.destroy_window(window);
+        let _ = conn.flush();
+        Some(result)
+    }
+
+    // arboard can only offer text and HTML together, so RTF is dropped here and
+    // files are restored as their paths
+    pub(super) fn write_formats(
+        text: Option<&str>,
+        formats: &ClipboardFormats,
+    ) -> Result<(), String> {
+        super::write_with_arboard(text, formats)
+    }
+
+    // arboard replaces the whole selection on every write, the image wins
+    pub(super) fn add_formats(_formats: &ClipboardFormats) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod native {
+    use super::{ClipboardFormats, NativeFormats};
+
+    pub(super) fn read_formats() -> NativeFormats {
+        NativeFormats::default()
+    }
+
+    pub(super) fn has_exclusion_hint() -> bool {
+        false
+    }
+
+    pub(super) fn write_formats(
+        text: Option<&str>,
+        formats: &ClipboardFormats,
+    ) -> Result<(), String> {
+        super::write_with_arboard(text, formats)
+    }
+
+    pub(super) fn add_formats(_formats: &ClipboardFormats) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn write_with_arboard(text: Option<&str>, formats: &ClipboardFormats) -> Result<(), String> {
+    let mut clipboard =
+        arboard::Clipboard::new().map_err(|e| format!("Failed to create clipboard: {}", e))?;
+
+    let plain = text
+        .map(str::to_string)
+        .or_else(|| formats.files.as_ref().map(|files| files.join("\n")));
+
+    match (&formats.html, plain) {
+        (Some(html), plain) => clipboard
+            .set_html(html.as_str(), plain.as_deref())
+            .map_err(|e| format!("Failed to set clipboard html: {}", e)),
+        (None, Some(plain)) => clipboard
+            .set_text(plain)
+            .map_err(|e| format!("Failed to set clipboard text: {}", e)),
+        (None, None) => Err("Nothing to put on the clipboard".to_string()),
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::Path;
+use tauri::State;
+
+use super::formats::{restore_formats, restore_image_formats, restore_plain_text};
+use super::history_store::{
+    ClipboardHistoryStore, ClipboardItemUpdate, ClipboardPage, NewClipboardItem,
+};
+use super::image_handler::{load_clipboard_image, write_clipboard_image};
+use super::recovery::RecoveryNotice;
+use super::retention::emit_removed;
+use super::search::{search_items, ClipboardSearchRequest, ClipboardSearchResults};
+use super::settings::load_clipboard_settings;
+use super::storage::ClipboardItem;
+
+// Page size used when the webview doesn't ask for one
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+// Returns None if the item is already stored or the sensitive content rules skip it
+#[tauri::command]
+pub fn add_clipboard_item(
+    mut item: NewClipboardItem,
+    app_handle: tauri::AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<Option<ClipboardItem>, String> {
+    if !load_clipboard_settings(&app_handle)
+        .sensitive
+        .apply(&mut item, false)
+    {
+        return Ok(None);
+    }
+
+    Ok(history_store.add(item)?.map(|added| {
+        emit_removed(&app_handle, &added.removed);
+        added.item
+    }))
+}
+
+#[tauri::command]
+pub fn update_clipboard_item(
+    item_id: u64,
+    mut changes: ClipboardItemUpdate,
+    app_handle: tauri::AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardItem, String> {
+    // Edited text goes through the same checks as newly copied text
+    if let Some(text) = &changes.text {
+        changes.sensitive = load_clipboard_settings(&app_handle)
+            .sensitive
+            .classify_edit(text)?;
+    }
+    history_store.update(item_id, changes)
+}
+
+#[tauri::command]
+pub fn mark_clipboard_item_copied(
+    item_id: u64,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardItem, String> {
+    history_store.mark_copied(item_id)
+}
+
+// Put a history entry back on the system clipboard. By default every captured
+// format is restored, with `plain_text` only the text is.
+#[tauri::command]
+pub fn restore_clipboard_item(
+    item_id: u64,
+    plain_text: Option<bool>,
+    app_handle: tauri::AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardItem, String> {
+    restore_item(
+        &app_handle,
+        &history_store,
+        item_id,
+        plain_text.unwrap_or(false),
+    )
+}
+
+pub fn restore_item(
+    app_handle: &tauri::AppHandle,
+    history_store: &ClipboardHistoryStore,
+    item_id: u64,
+    plain_text: bool,
+) -> Result<ClipboardItem, String> {
+    let item = history_store.get(item_id)?;
+
+    match (&item.image_data, &item.text) {
+        (Some(image_data), _) if item.formats.is_empty() => {
+            write_clipboard_image(app_handle, Path::new(&image_data.file_path))?
+        }
+        // Images copied from a browser come with their markup and page URL
+        (Some(image_data), _) => restore_image_formats(
+            load_clipboard_image(app_handle, Path::new(&image_data.file_path))?,
+            &item.formats,
+        )?,
+        (None, Some(text)) if plain_text => restore_plain_text(text)?,
+        (None, text) => {
+            // Files are put back as files, their joined paths are only the display text
+            let text = if item.content_type == "files" {
+                None
+            } else {
+                text.as_deref()
+            };
+            restore_formats(text, &item.formats)?
+        }
+    }
+
+    history_store.mark_copied(item_id)
+}
+
+#[tauri::command]
+pub fn delete_clipboard_item(
+    item_id: u64,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardItem, String> {
+    history_store.delete(item_id)
+}
+
+// Toggle the pinned flag of a single item
+#[tauri::command]
+pub fn pin_clipboard_item(
+    item_id: u64,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardItem, String> {
+    history_store.toggle_pin(item_id)
+}
+
+// `source_app` matches an app id, executable or app name, see `ClipboardSource::matches`
+#[tauri::command]
+pub fn list_clipboard_items(
+    offset: Option<usize>,
+    limit: Option<usize>,
+    source_app: Option<String>,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardPage, String> {
+    history_store.list(
+        offset.unwrap_or(0),
+        limit.unwrap_or(DEFAULT_PAGE_SIZE),
+        source_app.as_deref(),
+    )
+}
+
+// Ranked search with filters and highlights, see `ClipboardSearchRequest`
+#[tauri::command]
+pub fn search_clipboard_items(
+    request: ClipboardSearchRequest,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<ClipboardSearchResults, String> {
+    history_store.with_items(|items| search_items(items, &request))
+}
+
+// Remove every item together with its image files
+#[tauri::command]
+pub fn clear_clipboard_history(
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<usize, String> {
+    history_store.clear()
+}
+
+// Details about a history that had to be moved aside at startup, reported once
+#[tauri::command]
+pub fn take_clipboard_recovery_notice(
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Option<RecoveryNotice> {
+    history_store.take_recovery_notice()
+}
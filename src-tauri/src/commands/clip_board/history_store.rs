@@ -0,0 +1,846 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::content_hash::{content_hash, item_content_hash};
+use super::formats::ClipboardFormats;
+use super::image_handler::{encrypt_plaintext_images, remove_migrated_images};
+use super::recovery::{
+    finish_interrupted_rotation, recover_history, HistoryRecovery, RecoveryNotice,
+};
+use super::retention::{image_file_sizes, ImageSizes, RetentionPolicy};
+use super::sensitive::{SensitiveKind, SensitiveMark};
+use super::settings::load_clipboard_settings;
+use super::source_app::ClipboardSource;
+use super::storage::{
+    read_history_file, write_history_file, ClipboardHistoryFile, ClipboardItem, DeletedContent,
+    ImageData,
+};
+use crate::persistence::background_writer::{BackgroundWriter, DebounceTiming};
+
+// Deleted content is remembered this long, a device that was offline for
+// longer may bring it back
+const DELETED_CONTENT_LIFETIME_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+
+// Content for a new history entry, the store fills in id, timestamps and counters
+#[derive(Deserialize, Clone)]
+pub struct NewClipboardItem {
+    #[serde(rename = "type")]
+    pub content_type: String,
+    pub text: Option<String>,
+    #[serde(rename = "imageData")]
+    pub image_data: Option<ImageData>,
+    #[serde(default)]
+    pub formats: ClipboardFormats,
+    // Filled in by the sensitive content rules, never by the webview
+    #[serde(skip)]
+    pub sensitive: Option<SensitiveKind>,
+    #[serde(skip)]
+    pub expires_at: Option<u64>,
+    #[serde(skip)]
+    pub source: Option<ClipboardSource>,
+}
+
+impl NewClipboardItem {
+    pub fn content_hash(&self) -> Option<String> {
+        content_hash(
+            &self.content_type,
+            self.text.as_deref(),
+            self.image_data.as_ref(),
+        )
+    }
+}
+
+// Partial update of an existing entry, missing fields stay untouched
+#[derive(Deserialize, Default)]
+pub struct ClipboardItemUpdate {
+    pub text: Option<String>,
+    pub pinned: Option<bool>,
+    pub last_copied: Option<u64>,
+    pub copy_count: Option<u64>,
+    // Set by the backend once the new text is classified
+    #[serde(skip)]
+    pub sensitive: SensitiveMark,
+}
+
+// Outcome of merging imported items into the history
+pub struct MergedItems {
+    pub added: usize,
+    // Imported items that were already in the history
+    pub merged: usize,
+    // Items the retention limits removed afterwards
+    pub removed: Vec<ClipboardItem>,
+}
+
+// A newly stored item and the items the retention limits removed to make room
+pub struct AddedItem {
+    pub item: ClipboardItem,
+    pub removed: Vec<ClipboardItem>,
+}
+
+#[derive(Serialize)]
+pub struct ClipboardPage {
+    pub items: Vec<ClipboardItem>,
+    // Number of matching items across all pages
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+// Backend-owned clipboard history. Commands work on the in-memory copy and the
+// encrypted file is rewritten in the background once changes settle.
+#[derive(Clone)]
+pub struct ClipboardHistoryStore {
+    history: Arc<RwLock<ClipboardHistoryFile>>,
+    // False while the file on disk couldn't be read, e.g. a locked passphrase
+    loaded: Arc<AtomicBool>,
+    // Set when an unreadable history was moved aside at startup
+    recovery_notice: Arc<Mutex<Option<RecoveryNotice>>>,
+    retention: Arc<RwLock<RetentionPolicy>>,
+    writer: BackgroundWriter<()>,
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl ClipboardHistoryStore {
+    pub fn load(app_handle: &tauri::AppHandle) -> Self {
+        finish_interrupted_rotation(app_handle);
+
+        let (history, loaded, recovery_notice) = match read_history_file(app_handle) {
+            Ok(history) => (history, true, None),
+            Err(e) => {
+                eprintln!("Failed to load clipboard history: {}", e);
+                match recover_history(app_handle, &e) {
+                    HistoryRecovery::Restored(history, notice) => (history, true, Some(notice)),
+                    HistoryRecovery::KeyUnavailable(notice) => {
+                        (ClipboardHistoryFile::default(), false, Some(notice))
+                    }
+                    HistoryRecovery::Failed => (ClipboardHistoryFile::default(), false, None),
+                }
+            }
+        };
+        let recovered = loaded && recovery_notice.is_some();
+
+        let history = Arc::new(RwLock::new(history));
+        let loaded = Arc::new(AtomicBool::new(loaded));
+        let persisted_history = history.clone();
+        let persisted_loaded = loaded.clone();
+        let persist_handle = app_handle.clone();
+
+        let writer = BackgroundWriter::spawn(
+            "clipboard history",
+            DebounceTiming {
+                quiet: Duration::from_millis(500),
+                max_delay: Duration::from_secs(5),
+            },
+            |_| {},
+            move || {
+                // Never replace a file we couldn't read with the partial in-memory history
+                if !persisted_loaded.load(Ordering::SeqCst) {
+                    return Err("History file could not be loaded, not overwriting it".to_string());
+                }
+
+                let history = persisted_history
+                    .read()
+                    .map_err(|_| "Clipboard history lock poisoned".to_string())?;
+                write_history_file(&history, &persist_handle)
+            },
+        );
+
+        let store = Self {
+            history,
+            loaded,
+            recovery_notice: Arc::new(Mutex::new(recovery_notice)),
+            retention: Arc::new(RwLock::new(load_clipboard_settings(app_handle).retention)),
+            writer,
+        };
+
+        // Write the restored history right away instead of on the next change
+        if recovered {
+            store.changed();
+        }
+        store
+    }
+
+    pub fn take_recovery_notice(&self) -> Option<RecoveryNotice> {
+        self.recovery_notice
+            .lock()
+            .ok()
+            .and_then(|mut notice| notice.take())
+    }
+
+    // Encrypt images saved in plain PNG files by earlier versions and point
+    // the history at the encrypted copies
+    pub fn encrypt_legacy_images(&self, app_handle: &tauri::AppHandle) -> Result<(), String> {
+        if !self.loaded.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let migrated = encrypt_plaintext_images(app_handle)?;
+        if migrated.is_empty() {
+            return Ok(());
+        }
+
+        {
+            let mut history = self.write()?;
+            for image in history
+                .items
+                .iter_mut()
+                .filter_map(|item| item.image_data.as_mut())
+            {
+                if let Some((_, encrypted_path)) = migrated
+                    .iter()
+                    .find(|(plain_path, _)| *plain_path == image.file_path)
+                {
+                    image.file_path = encrypted_path.clone();
+                }
+            }
+        }
+
+        // The plain files can only go once the new paths are on disk
+        self.changed();
+        self.flush()?;
+        remove_migrated_images(&migrated);
+
+        println!("Encrypted {} clipboard images", migrated.len());
+        Ok(())
+    }
+
+    // Run `f` while no other change or background write can happen
+    pub fn with_history_locked<T>(
+        &self,
+        f: impl FnOnce(&ClipboardHistoryFile) -> Result<T, String>,
+    ) -> Result<T, String> {
+        if !self.loaded.load(Ordering::SeqCst) {
+            return Err("Clipboard history is not loaded".to_string());
+        }
+
+        let history = self.write()?;
+        f(&history)
+    }
+
+    // Read the file again, e.g. once the key became available. Items added in
+    // the meantime are kept on top.
+    pub fn reload(&self, app_handle: &tauri::AppHandle) -> Result<(), String> {
+        let stored = read_history_file(app_handle)?;
+        {
+            let mut history = self.write()?;
+            let added = std::mem::take(&mut history.items);
+            let mut items = added;
+            for item in stored.items {
+                if !items.iter().any(|existing| existing.id == item.id) {
+                    items.push(item);
+                }
+            }
+            history.items = items;
+            for deleted in stored.deleted {
+                note_deleted(&mut history, deleted);
+            }
+        }
+
+        self.loaded.store(true, Ordering::SeqCst);
+        self.changed();
+        Ok(())
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, ClipboardHistoryFile>, String> {
+        self.history
+            .read()
+            .map_err(|_| "Failed to lock clipboard history".to_string())
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, ClipboardHistoryFile>, String> {
+        self.history
+            .write()
+            .map_err(|_| "Failed to lock clipboard history".to_string())
+    }
+
+    fn changed(&self) {
+        self.writer.send(());
+    }
+
+    pub fn flush(&self) -> Result<(), String> {
+        self.writer.flush()
+    }
+
+    pub fn snapshot(&self) -> Result<ClipboardHistoryFile, String> {
+        Ok(self.read()?.clone())
+    }
+
+    // Add new content at the top of the history. Returns None if the same text
+    // or image is already stored, or the content is over the size limit.
+    pub fn add(&self, new_item: NewClipboardItem) -> Result<Option<AddedItem>, String> {
+        let image_sizes = self.image_sizes(new_item.image_data.iter())?;
+        let removed;
+        let mut item;
+        {
+            let mut history = self.write()?;
+
+            let duplicate = new_item
+                .content_hash()
+                .is_some_and(|hash| find_content(&history.items, &hash).is_some());
+            if duplicate {
+                return Ok(None);
+            }
+
+            // Ids are creation times in milliseconds, bumped if two items share one
+            let now = now_millis();
+            let id = history
+                .items
+                .iter()
+                .map(|existing| existing.id + 1)
+                .max()
+                .unwrap_or(0)
+                .max(now);
+
+            item = ClipboardItem {
+                id,
+                content_type: new_item.content_type,
+                text: new_item.text,
+                image_data: new_item.image_data,
+                timestamp: now,
+                pinned: false,
+                last_copied: now,
+                copy_count: 1,
+                formats: new_item.formats,
+                sensitive: new_item.sensitive,
+                expires_at: new_item.expires_at,
+                source: new_item.source,
+                kind: None,
+            };
+            item.detect_kind();
+            let retention = self.retention();
+            if !retention.allows_item_size(&item, &image_sizes) {
+                drop(history);
+                eprintln!("Clipboard item is over the size limit, not recorded");
+                self.remove_unused_images(std::slice::from_ref(&item));
+                return Ok(None);
+            }
+
+            // Copying deleted content again brings it back on every device
+            if let Some(hash) = item_content_hash(&item) {
+                history.deleted.retain(|deleted| deleted.hash != hash);
+            }
+            history.items.insert(0, item.clone());
+            removed = retention.apply(&mut history.items, now, &image_sizes);
+            remember_deleted(&mut history, &removed, false, now);
+        }
+
+        self.remove_unused_images(&removed);
+        self.changed();
+        Ok(Some(AddedItem { item, removed }))
+    }
+
+    // Id of the stored item with the same text or image, if there is one
+    pub fn find_same(&self, new_item: &NewClipboardItem) -> Result<Option<u64>, String> {
+        let hash = match new_item.content_hash() {
+            Some(hash) => hash,
+            None => return Ok(None),
+        };
+        Ok(find_content(&self.read()?.items, &hash).map(|existing| existing.id))
+    }
+
+    pub fn update(
+        &self,
+        item_id: u64,
+        update: ClipboardItemUpdate,
+    ) -> Result<ClipboardItem, String> {
+        self.modify(item_id, |item| {
+            if let Some(text) = update.text {
+                if item.content_type == "text" {
+                    item.text = Some(text);
+                    // Rich formats would still paste the old content
+                    item.formats.html = None;
+                    item.formats.rtf = None;
+                    item.detect_kind();
+                    item.sensitive = update.sensitive.kind;
+                    // Pinned items are kept, like in `toggle_pin`
+                    item.expires_at = update.sensitive.expires_at.filter(|_| !item.pinned);
+                }
+            }
+            if let Some(pinned) = update.pinned {
+                item.pinned = pinned;
+                if pinned {
+                    item.expires_at = None;
+                }
+            }
+            if let Some(last_copied) = update.last_copied {
+                item.last_copied = last_copied;
+            }
+            if let Some(copy_count) = update.copy_count {
+                item.copy_count = copy_count;
+            }
+        })
+    }
+
+    pub fn get(&self, item_id: u64) -> Result<ClipboardItem, String> {
+        self.read()?
+            .items
+            .iter()
+            .find(|item| item.id == item_id)
+            .cloned()
+            .ok_or_else(|| format!("Clipboard item {} not found", item_id))
+    }
+
+    pub fn toggle_pin(&self, item_id: u64) -> Result<ClipboardItem, String> {
+        self.modify(item_id, |item| {
+            item.pinned = !item.pinned;
+            // Pinning a sensitive item means the user wants to keep it
+            if item.pinned {
+                item.expires_at = None;
+            }
+        })
+    }
+
+    pub fn mark_copied(&self, item_id: u64) -> Result<ClipboardItem, String> {
+        self.modify(item_id, |item| {
+            item.copy_count += 1;
+            item.last_copied = now_millis();
+        })
+    }
+
+    fn modify(
+        &self,
+        item_id: u64,
+        change: impl FnOnce(&mut ClipboardItem),
+    ) -> Result<ClipboardItem, String> {
+        let item = {
+            let mut history = self.write()?;
+            let item = history
+                .items
+                .iter_mut()
+                .find(|item| item.id == item_id)
+                .ok_or_else(|| format!("Clipboard item {} not found", item_id))?;
+            change(item);
+            item.clone()
+        };
+
+        self.changed();
+        Ok(item)
+    }
+
+    // Remove an item and its image file, unless another item still uses it
+    pub fn delete(&self, item_id: u64) -> Result<ClipboardItem, String> {
+        let item = {
+            let mut history = self.write()?;
+            let index = history
+                .items
+                .iter()
+                .position(|item| item.id == item_id)
+                .ok_or_else(|| format!("Clipboard item {} not found", item_id))?;
+            let item = history.items.remove(index);
+            remember_deleted(
+                &mut history,
+                std::slice::from_ref(&item),
+                true,
+                now_millis(),
+            );
+            item
+        };
+
+        self.remove_unused_images(std::slice::from_ref(&item));
+        self.changed();
+        Ok(item)
+    }
+
+    fn retention(&self) -> RetentionPolicy {
+        self.retention
+            .read()
+            .map(|retention| retention.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_retention(&self, policy: RetentionPolicy) {
+        if let Ok(mut retention) = self.retention.write() {
+            *retention = policy;
+        }
+    }
+
+    // Sizes of the stored images plus `extra`, read before taking the write lock.
+    // Only the image limit needs all of them.
+    fn image_sizes<'a>(
+        &self,
+        extra: impl Iterator<Item = &'a ImageData>,
+    ) -> Result<ImageSizes, String> {
+        let mut image_sizes = image_file_sizes(extra);
+        if self.retention().max_image_bytes.is_some() {
+            let stored: Vec<ImageData> = self
+                .read()?
+                .items
+                .iter()
+                .filter_map(|item| item.image_data.clone())
+                .collect();
+            image_sizes.extend(image_file_sizes(stored.iter()));
+        }
+        Ok(image_sizes)
+    }
+
+    // Remove what is over the retention limits, returns the removed items
+    pub fn enforce_retention(&self, now: u64) -> Result<Vec<ClipboardItem>, String> {
+        let retention = self.retention();
+        let image_sizes = self.image_sizes(std::iter::empty())?;
+        let removed = {
+            let mut history = self.write()?;
+            let removed = retention.apply(&mut history.items, now, &image_sizes);
+            remember_deleted(&mut history, &removed, false, now);
+            removed
+        };
+        if removed.is_empty() {
+            return Ok(removed);
+        }
+
+        self.remove_unused_images(&removed);
+        self.changed();
+        Ok(removed)
+    }
+
+    // Merge items from another history. Content that is already stored keeps
+    // the higher copy count and stays pinned if either copy is, everything else
+    // is added with a new id. Image files must already be in place.
+    pub fn merge_items(&self, imported: Vec<ClipboardItem>) -> Result<MergedItems, String> {
+        let image_sizes =
+            self.image_sizes(imported.iter().filter_map(|item| item.image_data.as_ref()))?;
+        let (added, merged, removed) = {
+            let mut history = self.write()?;
+            // Importing content on purpose takes back its deletion
+            let imported_hashes: HashSet<String> =
+                imported.iter().filter_map(item_content_hash).collect();
+            history
+                .deleted
+                .retain(|deleted| !imported_hashes.contains(&deleted.hash));
+
+            let (added, merged) = merge_into(&mut history.items, imported);
+            let now = now_millis();
+            let removed = self
+                .retention()
+                .apply(&mut history.items, now, &image_sizes);
+            remember_deleted(&mut history, &removed, false, now);
+            (added, merged, removed)
+        };
+
+        self.remove_unused_images(&removed);
+        self.changed();
+        Ok(MergedItems {
+            added,
+            merged,
+            removed,
+        })
+    }
+
+    // Merge items received from a paired device and remove what it deleted.
+    // Content deleted after it was last copied isn't added back.
+    pub fn merge_synced(
+        &self,
+        received: Vec<ClipboardItem>,
+        remote_deleted: Vec<DeletedContent>,
+    ) -> Result<MergedItems, String> {
+        let image_sizes =
+            self.image_sizes(received.iter().filter_map(|item| item.image_data.as_ref()))?;
+        let now = now_millis();
+        let (added, merged, removed) = {
+            let mut history = self.write()?;
+            for deleted in remote_deleted {
+                if deleted.shared && !is_stale(&deleted, now) {
+                    note_deleted(&mut history, deleted);
+                }
+            }
+
+            let deleted = std::mem::take(&mut history.deleted);
+            let (mut removed, kept): (Vec<ClipboardItem>, Vec<ClipboardItem>) =
+                std::mem::take(&mut history.items)
+                    .into_iter()
+                    .partition(|item| is_deleted(&deleted, item));
+            let received = received
+                .into_iter()
+                .filter(|item| !is_deleted(&deleted, item))
+                .collect();
+            history.items = kept;
+            history.deleted = deleted;
+
+            let (added, merged) = merge_into(&mut history.items, received);
+            let pruned = self
+                .retention()
+                .apply(&mut history.items, now, &image_sizes);
+            remember_deleted(&mut history, &pruned, false, now);
+            removed.extend(pruned);
+            (added, merged, removed)
+        };
+
+        self.remove_unused_images(&removed);
+        self.changed();
+        Ok(MergedItems {
+            added,
+            merged,
+            removed,
+        })
+    }
+
+    // Content the user deleted here, for paired devices to remove as well
+    pub fn shared_deletions(&self) -> Result<Vec<DeletedContent>, String> {
+        let now = now_millis();
+        Ok(self
+            .read()?
+            .deleted
+            .iter()
+            .filter(|deleted| deleted.shared && !is_stale(deleted, now))
+            .cloned()
+            .collect())
+    }
+
+    // Run `f` over the items, newest first, without copying the history
+    pub fn with_items<T>(&self, f: impl FnOnce(&[ClipboardItem]) -> T) -> Result<T, String> {
+        Ok(f(&self.read()?.items))
+    }
+
+    // Image files the history points at, used to find orphaned files
+    pub fn referenced_image_paths(&self) -> Result<HashSet<String>, String> {
+        if !self.loaded.load(Ordering::SeqCst) {
+            return Err("Clipboard history is not loaded".to_string());
+        }
+
+        Ok(self
+            .read()?
+            .items
+            .iter()
+            .filter_map(|item| item.image_data.as_ref())
+            .flat_map(|image| image.file_paths().cloned())
+            .collect())
+    }
+
+    // Drop items whose expiry time has passed, returns what was removed
+    pub fn remove_expired(&self, now: u64) -> Result<Vec<ClipboardItem>, String> {
+        let is_expired =
+            |item: &ClipboardItem| item.expires_at.is_some_and(|expires_at| expires_at <= now);
+
+        if !self.read()?.items.iter().any(is_expired) {
+            return Ok(Vec::new());
+        }
+
+        let removed = {
+            let mut history = self.write()?;
+            let (removed, kept): (Vec<ClipboardItem>, Vec<ClipboardItem>) =
+                std::mem::take(&mut history.items)
+                    .into_iter()
+                    .partition(is_expired);
+            history.items = kept;
+            remember_deleted(&mut history, &removed, false, now);
+            removed
+        };
+
+        self.remove_unused_images(&removed);
+        self.changed();
+        Ok(removed)
+    }
+
+    pub fn clear(&self) -> Result<usize, String> {
+        let removed = {
+            let mut history = self.write()?;
+            let removed = std::mem::take(&mut history.items);
+            remember_deleted(&mut history, &removed, true, now_millis());
+            removed
+        };
+
+        self.remove_unused_images(&removed);
+        self.changed();
+        Ok(removed.len())
+    }
+
+    // `source_app` limits the page to items copied from a matching app
+    pub fn list(
+        &self,
+        offset: usize,
+        limit: usize,
+        source_app: Option<&str>,
+    ) -> Result<ClipboardPage, String> {
+        let history = self.read()?;
+        let matches = history
+            .items
+            .iter()
+            .filter(|item| from_source_app(item, source_app));
+
+        Ok(paginate(matches, offset, limit))
+    }
+
+    fn remove_unused_images(&self, removed: &[ClipboardItem]) {
+        let history = match self.read() {
+            Ok(history) => history,
+            Err(e) => {
+                eprintln!("{}", e);
+                return;
+            }
+        };
+
+        let removed_paths = removed
+            .iter()
+            .filter_map(|item| item.image_data.as_ref())
+            .flat_map(|image| image.file_paths());
+        for path in removed_paths {
+            let still_used = history
+                .items
+                .iter()
+                .filter_map(|item| item.image_data.as_ref())
+                .any(|other| other.file_paths().any(|other_path| other_path == path));
+
+            if !still_used && Path::new(path).exists() {
+                if let Err(e) = std::fs::remove_file(path) {
+                    eprintln!("Failed to delete image file {}: {}", path, e);
+                }
+            }
+        }
+    }
+}
+
+// Adds imported items to `items`, returns how many were added and merged
+fn merge_into(items: &mut Vec<ClipboardItem>, imported: Vec<ClipboardItem>) -> (usize, usize) {
+    let mut added = 0;
+    let mut merged = 0;
+    let mut next_id = items
+        .iter()
+        .map(|existing| existing.id + 1)
+        .max()
+        .unwrap_or(0);
+
+    // Hash every stored item once, not once per imported item. Reversed so the
+    // first of two items with the same content wins, like a search would.
+    let mut index_by_hash: HashMap<String, usize> = items
+        .iter()
+        .enumerate()
+        .rev()
+        .filter_map(|(index, existing)| item_content_hash(existing).map(|hash| (hash, index)))
+        .collect();
+
+    for mut item in imported {
+        let hash = item_content_hash(&item);
+        let existing = hash
+            .as_ref()
+            .and_then(|hash| index_by_hash.get(hash))
+            .map(|&index| &mut items[index]);
+        match existing {
+            Some(existing) => {
+                existing.pinned |= item.pinned;
+                existing.copy_count = existing.copy_count.max(item.copy_count);
+                existing.last_copied = existing.last_copied.max(item.last_copied);
+                existing.timestamp = existing.timestamp.min(item.timestamp);
+                merged += 1;
+            }
+            None => {
+                item.id = next_id;
+                next_id += 1;
+                // Don't trust kinds from other versions or devices
+                item.detect_kind();
+                if let Some(hash) = hash {
+                    index_by_hash.insert(hash, items.len());
+                }
+                items.push(item);
+                added += 1;
+            }
+        }
+    }
+
+    // Keep the history newest first
+    items.sort_by_key(|item| Reverse(item.timestamp));
+    (added, merged)
+}
+
+fn is_stale(deleted: &DeletedContent, now: u64) -> bool {
+    deleted
+        .deleted_at
+        .saturating_add(DELETED_CONTENT_LIFETIME_MS)
+        <= now
+}
+
+fn note_deleted(history: &mut ClipboardHistoryFile, deleted: DeletedContent) {
+    match history
+        .deleted
+        .iter_mut()
+        .find(|existing| existing.hash == deleted.hash)
+    {
+        Some(existing) => {
+            existing.deleted_at = existing.deleted_at.max(deleted.deleted_at);
+            existing.shared |= deleted.shared;
+        }
+        None => history.deleted.push(deleted),
+    }
+}
+
+fn remember_deleted(
+    history: &mut ClipboardHistoryFile,
+    removed: &[ClipboardItem],
+    shared: bool,
+    now: u64,
+) {
+    for item in removed {
+        if let Some(hash) = item_content_hash(item) {
+            note_deleted(
+                history,
+                DeletedContent {
+                    hash,
+                    deleted_at: now,
+                    // Sensitive content never leaves the device, not even its hash
+                    shared: shared && item.sensitive.is_none(),
+                },
+            );
+        }
+    }
+    history.deleted.retain(|deleted| !is_stale(deleted, now));
+}
+
+// Deleted after it was last copied, on this device or a paired one
+fn is_deleted(deleted: &[DeletedContent], item: &ClipboardItem) -> bool {
+    let hash = match item_content_hash(item) {
+        Some(hash) => hash,
+        None => return false,
+    };
+    let last_used = item.timestamp.max(item.last_copied);
+    deleted
+        .iter()
+        .any(|deleted| deleted.hash == hash && deleted.deleted_at >= last_used)
+}
+
+fn find_content<'a>(items: &'a [ClipboardItem], hash: &str) -> Option<&'a ClipboardItem> {
+    items
+        .iter()
+        .find(|existing| item_content_hash(existing).as_deref() == Some(hash))
+}
+
+fn from_source_app(item: &ClipboardItem, source_app: Option<&str>) -> bool {
+    match source_app {
+        Some(source_app) => item
+            .source
+            .as_ref()
+            .is_some_and(|source| source.matches(source_app)),
+        None => true,
+    }
+}
+
+fn paginate<'a>(
+    items: impl Iterator<Item = &'a ClipboardItem>,
+    offset: usize,
+    limit: usize,
+) -> ClipboardPage {
+    let mut total = 0;
+    let mut page = Vec::new();
+
+    for item in items {
+        if total >= offset && page.len() < limit {
+            page.push(item.clone());
+        }
+        total += 1;
+    }
+
+    ClipboardPage {
+        items: page,
+        total,
+        offset,
+        limit,
+    }
+}
@@ -3,6 +3,21 @@ use image::{DynamicImage, GenericImageView};
 use serde::Serialize;
 use sha2::{Digest, Sha256};
 use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+use super::envelope::{is_sealed_bytes, open_bytes, seal_bytes};
+use super::history_store::ClipboardHistoryStore;
+use super::key_protector::{get_encryption_key, HistoryKey};
+use crate::persistence::files::write_atomic;
+
+// Images are stored as encrypted PNGs with this extension
+const ENCRYPTED_IMAGE_EXTENSION: &str = "png.enc";
+
+// Thumbnails are `<hash>.thumb.png.enc` and fit in a square of this size
+const THUMBNAIL_SUFFIX: &str = "thumb";
+const THUMBNAIL_SIZE: u32 = 256;
 
 // Function to get image from clipboard
 #[tauri::command]
@@ -13,54 +28,7 @@ pub fn get_clipboard_image(
     match arboard::Clipboard::new() {
         Ok(mut clipboard) => {
             match clipboard.get_image() {
-                Ok(image_data) => {
-                    // Process the image
-                    let width = image_data.width as u32;
-                    let height = image_data.height as u32;
-
-                    // Generate a simple hash of the image data
-                    let mut hasher = Sha256::new();
-                    hasher.update(&image_data.bytes);
-                    let hash = format!("{:x}", hasher.finalize());
-
-                    // Save the image to a file in the app's data directory
-                    let app_data_dir = app_handle
-                        .path_resolver()
-                        .app_data_dir()
-                        .ok_or_else(|| "Failed to get app data directory".to_string())?;
-
-                    let images_dir = app_data_dir.join("clipboard_images");
-                    if !images_dir.exists() {
-                        std::fs::create_dir_all(&images_dir)
-                            .map_err(|e| format!("Failed to create images directory: {}", e))?;
-                    }
-
-                    let image_path = images_dir.join(format!("{}.png", hash));
-                    let image_path_str = image_path.to_string_lossy().to_string();
-
-                    // Create the DynamicImage and save it
-                    let img = DynamicImage::ImageRgba8(
-                        image::RgbaImage::from_raw(width, height, image_data.bytes.to_vec())
-                            .ok_or_else(|| "Fto_vec to create image from raw data".to_string())?,
-                    );
-
-                    // Resize large images to reduce memory usage
-                    let img = if width > 1200 || height > 1200 {
-                        img.resize(1200, 1200, image::imageops::FilterType::Lanczos3)
-                    } else {
-                        img
-                    };
-
-                    img.save(&image_path)
-                        .map_err(|e| format!("Failed to save image: {}", e))?;
-
-                    Ok(Some(ImageResponse {
-                        width,
-                        height,
-                        hash,
-                        file_path: image_path_str,
-                    }))
-                }
+                Ok(image_data) => store_clipboard_image(&app_handle, image_data).map(Some),
                 Err(_) => {
                     // No image in clipboard, return None instead of an error
                     Ok(None)
@@ -71,21 +39,174 @@ pub fn get_clipboard_image(
     }
 }
 
-// Function to set image to clipboard
-#[tauri::command]
-pub fn set_clipboard_image(file_path: String, _window: tauri::Window) -> Result<(), String> {
-    let img = image::open(&file_path).map_err(|e| format!("Failed to open image file: {}", e))?;
+// Hash of the raw pixels, identical images share their files
+pub fn pixel_hash(image_data: &ArboardImageData) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(&image_data.bytes);
+    format!("{:x}", hasher.finalize())
+}
 
-    let (width, height) = img.dimensions();
-    let rgba_image = img.to_rgba8();
-    let bytes = rgba_image.into_raw();
+// Save a clipboard image into the app's data directory, named by its hash. The
+// original is kept at full size, the list view gets a separate thumbnail.
+pub fn store_clipboard_image(
+    app_handle: &tauri::AppHandle,
+    image_data: ArboardImageData,
+) -> Result<ImageResponse, String> {
+    let hash = pixel_hash(&image_data);
+    store_hashed_clipboard_image(app_handle, image_data, hash)
+}
 
-    let image_data = ArboardImageData {
-        width: width as usize,
-        height: height as usize,
-        bytes: bytes.into(),
+// Same as `store_clipboard_image` for a caller that already has the pixel hash
+pub fn store_hashed_clipboard_image(
+    app_handle: &tauri::AppHandle,
+    image_data: ArboardImageData,
+    hash: String,
+) -> Result<ImageResponse, String> {
+    let width = image_data.width as u32;
+    let height = image_data.height as u32;
+
+    let images_dir = get_images_dir(app_handle)?;
+    if !images_dir.exists() {
+        std::fs::create_dir_all(&images_dir)
+            .map_err(|e| format!("Failed to create images directory: {}", e))?;
+    }
+
+    let image_path = images_dir.join(format!("{}.{}", hash, ENCRYPTED_IMAGE_EXTENSION));
+    let thumbnail_path = images_dir.join(format!(
+        "{}.{}.{}",
+        hash, THUMBNAIL_SUFFIX, ENCRYPTED_IMAGE_EXTENSION
+    ));
+    let response = ImageResponse {
+        width,
+        height,
+        hash,
+        file_path: image_path.to_string_lossy().to_string(),
+        thumbnail_path: thumbnail_path.to_string_lossy().to_string(),
     };
 
+    // Files written before thumbnails existed may hold a downscaled copy, so
+    // only skip the work when both are there
+    if image_path.exists() && thumbnail_path.exists() {
+        return Ok(response);
+    }
+
+    let img = DynamicImage::ImageRgba8(
+        image::RgbaImage::from_raw(width, height, image_data.bytes.into_owned())
+            .ok_or_else(|| "Failed to create image from raw data".to_string())?,
+    );
+    let thumbnail = if width > THUMBNAIL_SIZE || height > THUMBNAIL_SIZE {
+        img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+    } else {
+        img.clone()
+    };
+
+    write_sealed_files(
+        app_handle,
+        &[
+            (&image_path, &encode_png(&img)?),
+            (&thumbnail_path, &encode_png(&thumbnail)?),
+        ],
+    )?;
+
+    Ok(response)
+}
+
+// Store already encoded PNGs under their hash, e.g. from an imported bundle.
+// Existing files are kept. Returns the image and thumbnail paths.
+pub fn store_image_files(
+    app_handle: &tauri::AppHandle,
+    hash: &str,
+    png_bytes: &[u8],
+    thumbnail_png_bytes: Option<&[u8]>,
+) -> Result<(String, Option<String>), String> {
+    if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("Invalid image hash: {}", hash));
+    }
+
+    let images_dir = get_images_dir(app_handle)?;
+    fs::create_dir_all(&images_dir)
+        .map_err(|e| format!("Failed to create images directory: {}", e))?;
+
+    let image_path = images_dir.join(format!("{}.{}", hash, ENCRYPTED_IMAGE_EXTENSION));
+    let thumbnail_path = thumbnail_png_bytes.map(|thumbnail_png_bytes| {
+        let thumbnail_path = images_dir.join(format!(
+            "{}.{}.{}",
+            hash, THUMBNAIL_SUFFIX, ENCRYPTED_IMAGE_EXTENSION
+        ));
+        (thumbnail_path, thumbnail_png_bytes)
+    });
+
+    let mut files: Vec<(&Path, &[u8])> = Vec::new();
+    if !image_path.exists() {
+        files.push((&image_path, png_bytes));
+    }
+    if let Some((thumbnail_path, thumbnail_png_bytes)) = &thumbnail_path {
+        if !thumbnail_path.exists() {
+            files.push((thumbnail_path, thumbnail_png_bytes));
+        }
+    }
+    write_sealed_files(app_handle, &files)?;
+
+    Ok((
+        image_path.to_string_lossy().to_string(),
+        thumbnail_path.map(|(thumbnail_path, _)| thumbnail_path.to_string_lossy().to_string()),
+    ))
+}
+
+// Encode in memory so the plain PNG never touches the disk
+fn encode_png(img: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut png_bytes = Vec::new();
+    img.write_to(
+        &mut Cursor::new(&mut png_bytes),
+        image::ImageOutputFormat::Png,
+    )
+    .map_err(|e| format!("Failed to encode image: {}", e))?;
+    Ok(png_bytes)
+}
+
+// Seal and write under the history lock, the same one `rotate_history_key`
+// holds. Otherwise a rotation could re-encrypt the existing images and swap
+// the key while these are still being written with the old one.
+fn write_sealed_files(
+    app_handle: &tauri::AppHandle,
+    files: &[(&Path, &[u8])],
+) -> Result<(), String> {
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let write = || {
+        let key = get_encryption_key(app_handle)?;
+        for (path, bytes) in files {
+            write_atomic(path, &seal_bytes(&key, bytes)?)
+                .map_err(|e| format!("Failed to save image: {}", e))?;
+        }
+        Ok(())
+    };
+
+    match app_handle.try_state::<ClipboardHistoryStore>() {
+        Some(history_store) => history_store.with_history_locked(|_| write()),
+        None => write(),
+    }
+}
+
+// Function to set image to clipboard
+#[tauri::command]
+pub fn set_clipboard_image(
+    file_path: String,
+    _window: tauri::Window,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    write_clipboard_image(&app_handle, Path::new(&file_path))
+}
+
+// Decode a stored image and put it on the system clipboard
+pub fn write_clipboard_image(
+    app_handle: &tauri::AppHandle,
+    file_path: &Path,
+) -> Result<(), String> {
+    let image_data = load_clipboard_image(app_handle, file_path)?;
+
     match arboard::Clipboard::new() {
         Ok(mut clipboard) => match clipboard.set_image(image_data) {
             Ok(_) => Ok(()),
@@ -95,9 +216,32 @@ pub fn set_clipboard_image(file_path: String, _window: tauri::Window) -> Result<
     }
 }
 
+// Decode a stored image into the pixels the clipboard takes
+pub fn load_clipboard_image(
+    app_handle: &tauri::AppHandle,
+    file_path: &Path,
+) -> Result<ArboardImageData<'static>, String> {
+    let png_bytes = read_image_file(app_handle, file_path)?;
+    let img = image::load_from_memory(&png_bytes)
+        .map_err(|e| format!("Failed to open image file: {}", e))?;
+
+    let (width, height) = img.dimensions();
+    let rgba_image = img.to_rgba8();
+    let bytes = rgba_image.into_raw();
+
+    Ok(ArboardImageData {
+        width: width as usize,
+        height: height as usize,
+        bytes: bytes.into(),
+    })
+}
+
 #[tauri::command]
-pub fn load_clipboard_image_bytes(file_path: String) -> Result<Vec<u8>, String> {
-    fs::read(file_path).map_err(|e| format!("Failed to read image file: {}", e))
+pub fn load_clipboard_image_bytes(
+    file_path: String,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<u8>, String> {
+    read_image_file(&app_handle, Path::new(&file_path))
 }
 
 #[tauri::command]
@@ -110,10 +254,169 @@ pub fn delete_clipboard_image_file(file_path: String) -> Result<(), String> {
 }
 
 // Response structure for image data
-#[derive(Serialize)]
+#[derive(Serialize, Clone)]
 pub struct ImageResponse {
     pub width: u32,
     pub height: u32,
     pub hash: String,
     pub file_path: String,
+    pub thumbnail_path: String,
+}
+
+pub fn get_images_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+
+    Ok(app_data_dir.join("clipboard_images"))
+}
+
+// PNG bytes of a stored image, decrypted if needed
+pub fn read_image_file(app_handle: &tauri::AppHandle, path: &Path) -> Result<Vec<u8>, String> {
+    let data = fs::read(path).map_err(|e| format!("Failed to read image file: {}", e))?;
+
+    // Files from before encryption are plain PNGs until they are migrated
+    if !is_sealed_bytes(&data) {
+        return Ok(data);
+    }
+
+    open_bytes(&get_encryption_key(app_handle)?, &data)
+}
+
+// Files in the images directory whose name ends with `suffix`
+pub fn list_image_files(
+    app_handle: &tauri::AppHandle,
+    suffix: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let images_dir = get_images_dir(app_handle)?;
+    if !images_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(fs::read_dir(&images_dir)
+        .map_err(|e| format!("Failed to read images directory: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.to_string_lossy().ends_with(suffix))
+        .collect())
+}
+
+// Encrypt plain PNGs written by earlier versions. Returns (old path, new path)
+// pairs, the plain files are only removed by `remove_migrated_images` once the
+// history points at the new ones.
+pub fn encrypt_plaintext_images(
+    app_handle: &tauri::AppHandle,
+) -> Result<Vec<(String, String)>, String> {
+    let key = get_encryption_key(app_handle)?;
+    let mut migrated = Vec::new();
+
+    for plain_path in list_image_files(app_handle, ".png")? {
+        let encrypted_path = plain_path.with_extension(ENCRYPTED_IMAGE_EXTENSION);
+
+        let result = fs::read(&plain_path)
+            .map_err(|e| format!("Failed to read image file: {}", e))
+            .and_then(|png_bytes| seal_bytes(&key, &png_bytes))
+            .and_then(|sealed| write_atomic(&encrypted_path, &sealed));
+
+        match result {
+            Ok(()) => migrated.push((
+                plain_path.to_string_lossy().to_string(),
+                encrypted_path.to_string_lossy().to_string(),
+            )),
+            Err(e) => eprintln!("Failed to encrypt {}: {}", plain_path.display(), e),
+        }
+    }
+
+    Ok(migrated)
+}
+
+pub fn remove_migrated_images(migrated: &[(String, String)]) {
+    for (plain_path, _) in migrated {
+        if let Err(e) = fs::remove_file(plain_path) {
+            eprintln!("Failed to remove plain image {}: {}", plain_path, e);
+        }
+    }
+}
+
+// First half of a key rotation: write every image re-encrypted with the new key
+// next to the original. Returns the written files.
+pub fn reencrypt_images(
+    app_handle: &tauri::AppHandle,
+    old_key: &HistoryKey,
+    new_key: &HistoryKey,
+) -> Result<Vec<PathBuf>, String> {
+    let mut rotating = Vec::new();
+
+    for image_path in list_image_files(app_handle, &format!(".{}", ENCRYPTED_IMAGE_EXTENSION))? {
+        let rotating_path = rotating_image_path(&image_path);
+
+        let result = fs::read(&image_path)
+            .map_err(|e| format!("Failed to read image file: {}", e))
+            .and_then(|data| open_bytes(old_key, &data))
+            .and_then(|png_bytes| seal_bytes(new_key, &png_bytes))
+            .and_then(|sealed| write_atomic(&rotating_path, &sealed));
+
+        if let Err(e) = result {
+            discard_rotated_images(&rotating);
+            return Err(format!(
+                "Failed to re-encrypt {}: {}",
+                image_path.display(),
+                e
+            ));
+        }
+        rotating.push(rotating_path);
+    }
+
+    Ok(rotating)
+}
+
+fn rotating_image_path(image_path: &Path) -> PathBuf {
+    let mut rotating_path = image_path.as_os_str().to_owned();
+    rotating_path.push(".rotating");
+    PathBuf::from(rotating_path)
+}
+
+// Second half of a key rotation, once the new key is stored
+pub fn commit_rotated_images(rotating: &[PathBuf]) {
+    for rotating_path in rotating {
+        if let Err(e) = fs::rename(rotating_path, rotating_path.with_extension("")) {
+            eprintln!("Failed to replace {}: {}", rotating_path.display(), e);
+        }
+    }
+}
+
+pub fn discard_rotated_images(rotating: &[PathBuf]) {
+    for rotating_path in rotating {
+        let _ = fs::remove_file(rotating_path);
+    }
+}
+
+pub fn has_rotating_images(app_handle: &tauri::AppHandle) -> bool {
+    list_image_files(app_handle, ".rotating")
+        .map(|rotating| !rotating.is_empty())
+        .unwrap_or(false)
+}
+
+// Finish or undo an interrupted rotation, depending on which key made it to disk
+pub fn finish_image_rotation(app_handle: &tauri::AppHandle, key: &HistoryKey) {
+    let rotating = match list_image_files(app_handle, ".rotating") {
+        Ok(rotating) => rotating,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    for rotating_path in rotating {
+        let readable = fs::read(&rotating_path)
+            .map(|data| open_bytes(key, &data).is_ok())
+            .unwrap_or(false);
+
+        if readable {
+            commit_rotated_images(std::slice::from_ref(&rotating_path));
+        } else {
+            discard_rotated_images(std::slice::from_ref(&rotating_path));
+        }
+    }
 }
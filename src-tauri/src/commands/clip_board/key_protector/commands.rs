@@ -0,0 +1,64 @@
+use tauri::State;
+
+use super::{get_encryption_key, key_state, migrate_key, set_session_passphrase, KeyProtectorKind};
+use crate::commands::clip_board::history_store::ClipboardHistoryStore;
+use crate::commands::clip_board::recovery::{reset_history_key, rotate_history_key};
+use crate::commands::clip_board::settings::load_clipboard_settings;
+
+#[tauri::command]
+pub fn get_clipboard_key_protector(app_handle: tauri::AppHandle) -> KeyProtectorKind {
+    load_clipboard_settings(&app_handle).key_protector
+}
+
+// Why the history key is unavailable, None while it loads fine
+#[tauri::command]
+pub fn get_clipboard_key_error(app_handle: tauri::AppHandle) -> Option<String> {
+    key_state(&app_handle).ok().and_then(|state| state.error())
+}
+
+// Move the history key to another protector, or change the passphrase
+#[tauri::command]
+pub fn set_clipboard_key_protector(
+    protector: KeyProtectorKind,
+    passphrase: Option<String>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    if protector == KeyProtectorKind::Passphrase
+        && passphrase.as_ref().map(|p| p.is_empty()).unwrap_or(false)
+    {
+        return Err("Passphrase must not be empty".to_string());
+    }
+
+    migrate_key(&app_handle, protector, passphrase)
+}
+
+// Provide the passphrase for a passphrase protected history and load it
+#[tauri::command]
+pub fn unlock_clipboard_history(
+    passphrase: String,
+    app_handle: tauri::AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<(), String> {
+    set_session_passphrase(&app_handle, passphrase)?;
+    get_encryption_key(&app_handle)?;
+    history_store.reload(&app_handle)
+}
+
+// Re-encrypt the history with a brand new key, returns the new key id
+#[tauri::command]
+pub fn rotate_clipboard_key(
+    app_handle: tauri::AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<String, String> {
+    rotate_history_key(&app_handle, &history_store)
+}
+
+// Give up on the data sealed with a key that can't be found anymore and start
+// over with a new one
+#[tauri::command]
+pub fn reset_clipboard_key(
+    app_handle: tauri::AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<(), String> {
+    reset_history_key(&app_handle, &history_store)
+}
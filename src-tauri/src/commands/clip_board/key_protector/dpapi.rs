@@ -0,0 +1,125 @@
+use std::fs;
+use std::path::PathBuf;
+use std::ptr;
+use winapi::um::dpapi::{CryptProtectData, CryptUnprotectData};
+use winapi::um::wincrypt::DATA_BLOB;
+
+use super::{key_from_bytes, HistoryKey, KeyProtector};
+
+// Key encrypted with DPAPI for the current Windows user, kept in protected_key.bin
+pub struct DpapiProtector {
+    key_path: PathBuf,
+}
+
+impl DpapiProtector {
+    pub fn new(key_path: PathBuf) -> Self {
+        Self { key_path }
+    }
+}
+
+impl KeyProtector for DpapiProtector {
+    fn load_key(&self) -> Result<Option<HistoryKey>, String> {
+        if !self.key_path.exists() {
+            return Ok(None);
+        }
+
+        // Read existing protected key
+        let protected_key_data = fs::read(&self.key_path)
+            .map_err(|e| format!("Failed to read protected encryption key: {}", e))?;
+
+        // Decrypt key using DPAPI
+        let key_data = dpapi_decrypt(&protected_key_data)?;
+        key_from_bytes(&key_data).map(Some)
+    }
+
+    fn store_key(&self, key: &HistoryKey) -> Result<(), String> {
+        // Encrypt key using DPAPI
+        let protected_key = dpapi_encrypt(key)?;
+
+        // Save protected key to file
+        fs::write(&self.key_path, &protected_key)
+            .map_err(|e| format!("Failed to save protected encryption key: {}", e))
+    }
+
+    fn delete_key(&self) -> Result<(), String> {
+        if self.key_path.exists() {
+            fs::remove_file(&self.key_path)
+                .map_err(|e| format!("Failed to delete protected encryption key: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+// Windows DPAPI encrypt function
+fn dpapi_encrypt(data: &[u8]) -> Result<Vec<u8>, String> {
+    unsafe {
+        let mut in_blob = DATA_BLOB {
+            cbData: data.len() as u32,
+            pbData: data.as_ptr() as *mut _,
+        };
+
+        let mut out_blob = DATA_BLOB {
+            cbData: 0,
+            pbData: ptr::null_mut(),
+        };
+
+        let result = CryptProtectData(
+            &mut in_blob,
+            ptr::null(),     // description
+            ptr::null_mut(), // entropy
+            ptr::null_mut(), // reserved
+            ptr::null_mut(), // prompt struct
+            0,               // flags
+            &mut out_blob,
+        );
+
+        if result == 0 {
+            return Err("DPAPI encryption failed".to_string());
+        }
+
+        let encrypted_data =
+            std::slice::from_raw_parts(out_blob.pbData, out_blob.cbData as usize).to_vec();
+
+        // Free the memory allocated by CryptProtectData
+        winapi::um::winbase::LocalFree(out_blob.pbData as *mut _);
+
+        Ok(encrypted_data)
+    }
+}
+
+// Windows DPAPI decrypt function
+fn dpapi_decrypt(encrypted_data: &[u8]) -> Result<Vec<u8>, String> {
+    unsafe {
+        let mut in_blob = DATA_BLOB {
+            cbData: encrypted_data.len() as u32,
+            pbData: encrypted_data.as_ptr() as *mut _,
+        };
+
+        let mut out_blob = DATA_BLOB {
+            cbData: 0,
+            pbData: ptr::null_mut(),
+        };
+
+        let result = CryptUnprotectData(
+            &mut in_blob,
+            ptr::null_mut(), // description
+            ptr::null_mut(), // entropy
+            ptr::null_mut(), // reserved
+            ptr::null_mut(), // prompt struct
+            0,               // flags
+            &mut out_blob,
+        );
+
+        if result == 0 {
+            return Err("DPAPI decryption failed".to_string());
+        }
+
+        let decrypted_data =
+            std::slice::from_raw_parts(out_blob.pbData, out_blob.cbData as usize).to_vec();
+
+        // Free the memory allocated by CryptUnprotectData
+        winapi::um::winbase::LocalFree(out_blob.pbData as *mut _);
+
+        Ok(decrypted_data)
+    }
+}
@@ -0,0 +1,415 @@
+mod commands;
+#[cfg(target_os = "windows")]
+mod dpapi;
+mod passphrase;
+#[cfg(target_os = "linux")]
+mod system_keyring;
+
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::Manager;
+
+use super::settings::{load_clipboard_settings, save_clipboard_settings};
+use super::storage::get_history_file_path;
+use super::sync::get_peers_path;
+use crate::commands::snippets::get_snippets_path;
+
+pub use commands::{
+    get_clipboard_key_error, get_clipboard_key_protector, reset_clipboard_key,
+    rotate_clipboard_key, set_clipboard_key_protector, unlock_clipboard_history,
+};
+
+// Sent with the error message when the history key can't be loaded
+pub const CLIPBOARD_KEY_UNAVAILABLE_EVENT: &str = "clipboard-key-unavailable";
+
+// Environment variable that can supply the passphrase for unattended setups
+pub const PASSPHRASE_ENV_VAR: &str = "BLAZECAST_CLIPBOARD_PASSPHRASE";
+
+pub type HistoryKey = [u8; 32];
+
+// Keeps the history encryption key out of plain sight on disk
+pub trait KeyProtector {
+    // The stored key, or None if this protector doesn't hold one yet
+    fn load_key(&self) -> Result<Option<HistoryKey>, String>;
+    fn store_key(&self, key: &HistoryKey) -> Result<(), String>;
+    fn delete_key(&self) -> Result<(), String>;
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyProtectorKind {
+    // Windows Data Protection API, tied to the user account
+    Dpapi,
+    // Secret Service (Linux)
+    SystemKeyring,
+    // Key wrapped with an Argon2-derived key from a user passphrase
+    Passphrase,
+}
+
+impl KeyProtectorKind {
+    pub const ALL: [KeyProtectorKind; 3] = [
+        KeyProtectorKind::Dpapi,
+        KeyProtectorKind::SystemKeyring,
+        KeyProtectorKind::Passphrase,
+    ];
+
+    pub fn platform_default() -> Self {
+        if cfg!(target_os = "windows") {
+            KeyProtectorKind::Dpapi
+        } else if cfg!(target_os = "linux") {
+            KeyProtectorKind::SystemKeyring
+        } else {
+            KeyProtectorKind::Passphrase
+        }
+    }
+}
+
+// Unlocked key and session passphrase, so the protector isn't asked on every read
+pub struct ClipboardKeyState {
+    key: Mutex<Option<HistoryKey>>,
+    // Held while the key is loaded or created, two callers at startup must
+    // not each create and store a key of their own
+    loading: Mutex<()>,
+    passphrase: Mutex<Option<String>>,
+    // Why the key couldn't be loaded the last time, None once it could
+    error: Mutex<Option<String>>,
+}
+
+impl ClipboardKeyState {
+    pub fn new() -> Self {
+        Self {
+            key: Mutex::new(None),
+            loading: Mutex::new(()),
+            passphrase: Mutex::new(std::env::var(PASSPHRASE_ENV_VAR).ok()),
+            error: Mutex::new(None),
+        }
+    }
+
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().ok().and_then(|error| error.clone())
+    }
+
+    // Returns true if the error differs from the one reported before
+    fn set_error(&self, error: Option<String>) -> bool {
+        match self.error.lock() {
+            Ok(mut current) if *current != error => {
+                *current = error;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn passphrase(&self) -> Option<String> {
+        self.passphrase
+            .lock()
+            .ok()
+            .and_then(|passphrase| passphrase.clone())
+    }
+
+    fn set_passphrase(&self, passphrase: Option<String>) {
+        if let Ok(mut current) = self.passphrase.lock() {
+            *current = passphrase;
+        }
+    }
+
+    fn cached_key(&self) -> Option<HistoryKey> {
+        self.key.lock().ok().and_then(|key| *key)
+    }
+
+    fn cache_key(&self, key: Option<HistoryKey>) {
+        if let Ok(mut cached) = self.key.lock() {
+            *cached = key;
+        }
+    }
+}
+
+fn get_app_data_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())
+}
+
+fn key_from_bytes(bytes: &[u8]) -> Result<HistoryKey, String> {
+    if bytes.len() != 32 {
+        return Err("Invalid encryption key length after decryption".to_string());
+    }
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(bytes);
+    Ok(key)
+}
+
+fn create_protector(
+    kind: KeyProtectorKind,
+    app_handle: &tauri::AppHandle,
+    passphrase: Option<String>,
+) -> Result<Box<dyn KeyProtector>, String> {
+    match kind {
+        #[cfg(target_os = "windows")]
+        KeyProtectorKind::Dpapi => Ok(Box::new(dpapi::DpapiProtector::new(
+            get_app_data_dir(app_handle)?.join("protected_key.bin"),
+        ))),
+        #[cfg(not(target_os = "windows"))]
+        KeyProtectorKind::Dpapi => Err("DPAPI is only available on Windows".to_string()),
+
+        #[cfg(target_os = "linux")]
+        KeyProtectorKind::SystemKeyring => Ok(Box::new(system_keyring::SystemKeyringProtector)),
+        #[cfg(not(target_os = "linux"))]
+        KeyProtectorKind::SystemKeyring => {
+            Err("The system keyring is only supported on Linux".to_string())
+        }
+
+        KeyProtectorKind::Passphrase => Ok(Box::new(passphrase::PassphraseProtector::new(
+            get_app_data_dir(app_handle)?.join("passphrase_key.bin"),
+            passphrase,
+        ))),
+    }
+}
+
+fn key_state(app_handle: &tauri::AppHandle) -> Result<tauri::State<'_, ClipboardKeyState>, String> {
+    app_handle
+        .try_state::<ClipboardKeyState>()
+        .ok_or_else(|| "Clipboard key state is not initialized".to_string())
+}
+
+// Generate or retrieve the history encryption key through the configured protector.
+// Failures are reported to the UI once, not on every read.
+pub fn get_encryption_key(app_handle: &tauri::AppHandle) -> Result<HistoryKey, String> {
+    let key_state = key_state(app_handle)?;
+    if let Some(key) = key_state.cached_key() {
+        return Ok(key);
+    }
+
+    let _loading = key_state
+        .loading
+        .lock()
+        .map_err(|_| "Clipboard key lock poisoned".to_string())?;
+    // Someone else may have loaded it while we waited
+    if let Some(key) = key_state.cached_key() {
+        return Ok(key);
+    }
+
+    match load_or_create_key(app_handle, &key_state) {
+        Ok(key) => {
+            key_state.cache_key(Some(key));
+            key_state.set_error(None);
+            Ok(key)
+        }
+        Err(e) => {
+            if key_state.set_error(Some(e.clone())) {
+                eprintln!("{}", e);
+                if let Err(emit_error) = app_handle.emit_all(CLIPBOARD_KEY_UNAVAILABLE_EVENT, &e) {
+                    eprintln!("Failed to emit clipboard key error: {}", emit_error);
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+fn load_or_create_key(
+    app_handle: &tauri::AppHandle,
+    key_state: &ClipboardKeyState,
+) -> Result<HistoryKey, String> {
+    let kind = load_clipboard_settings(app_handle).key_protector;
+    let passphrase = key_state.passphrase();
+    let protector = create_protector(kind, app_handle, passphrase.clone())?;
+
+    let stored = protector
+        .load_key()
+        .map_err(|e| format!("Clipboard key store {:?} failed: {}", kind, e))?;
+    if let Some(key) = stored {
+        return Ok(key);
+    }
+    if let Some(key) = adopt_existing_key(kind, protector.as_ref(), app_handle, passphrase)? {
+        return Ok(key);
+    }
+
+    // A fresh key would make anything sealed with the lost one unreadable for good
+    let sealed = sealed_files(app_handle)?;
+    if !sealed.is_empty() {
+        let names: Vec<String> = sealed
+            .iter()
+            .filter_map(|path| path.file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .collect();
+        return Err(format!(
+            "No clipboard key is stored but {} was encrypted with one",
+            names.join(", ")
+        ));
+    }
+
+    // Nothing stored anywhere yet, start with a fresh key
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    protector.store_key(&key)?;
+    Ok(key)
+}
+
+// Fails unless every key store answers and none of them holds a key
+pub(crate) fn ensure_no_key_stored(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let key_state = key_state(app_handle)?;
+    let kind = load_clipboard_settings(app_handle).key_protector;
+
+    for other_kind in KeyProtectorKind::ALL {
+        let protector = match create_protector(other_kind, app_handle, key_state.passphrase()) {
+            Ok(protector) => protector,
+            Err(e) if other_kind == kind => return Err(e),
+            Err(_) => continue,
+        };
+        match protector.load_key() {
+            Ok(None) => {}
+            Ok(Some(_)) => return Err(format!("A clipboard key is stored in {:?}", other_kind)),
+            Err(e) => {
+                return Err(format!(
+                    "Clipboard key store {:?} failed: {}",
+                    other_kind, e
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+// Files on disk that only the history key can open
+pub(crate) fn sealed_files(app_handle: &tauri::AppHandle) -> Result<Vec<PathBuf>, String> {
+    Ok([
+        get_history_file_path(app_handle)?,
+        get_snippets_path(app_handle)?,
+        get_peers_path(app_handle)?,
+    ]
+    .into_iter()
+    .filter(|path| path.exists())
+    .collect())
+}
+
+// The configured protector is empty. If another protector still holds a key
+// (e.g. the setting was changed by hand), move that key over instead of
+// generating a new one that can't read the existing history. A protector that
+// fails to answer might hold the key, so that is an error rather than "no key".
+fn adopt_existing_key(
+    kind: KeyProtectorKind,
+    protector: &dyn KeyProtector,
+    app_handle: &tauri::AppHandle,
+    passphrase: Option<String>,
+) -> Result<Option<HistoryKey>, String> {
+    let mut failures = Vec::new();
+    for other_kind in KeyProtectorKind::ALL
+        .into_iter()
+        .filter(|other| *other != kind)
+    {
+        let other = match create_protector(other_kind, app_handle, passphrase.clone()) {
+            Ok(other) => other,
+            Err(_) => continue,
+        };
+
+        let key = match other.load_key() {
+            Ok(Some(key)) => key,
+            Ok(None) => continue,
+            Err(e) => {
+                failures.push(format!("{:?}: {}", other_kind, e));
+                continue;
+            }
+        };
+
+        println!("Moving clipboard key from {:?} to {:?}", other_kind, kind);
+        protector.store_key(&key)?;
+        if let Err(e) = other.delete_key() {
+            eprintln!(
+                "Failed to remove old clipboard key from {:?}: {}",
+                other_kind, e
+            );
+        }
+        return Ok(Some(key));
+    }
+
+    if !failures.is_empty() {
+        return Err(format!(
+            "No clipboard key in {:?} and other key stores failed: {}",
+            kind,
+            failures.join("; ")
+        ));
+    }
+    Ok(None)
+}
+
+// Move the current key to another protector and make it the configured one.
+// Also used to change the passphrase of the passphrase protector.
+pub fn migrate_key(
+    app_handle: &tauri::AppHandle,
+    target: KeyProtectorKind,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    let key = get_encryption_key(app_handle)?;
+    let key_state = key_state(app_handle)?;
+
+    let mut settings = load_clipboard_settings(app_handle);
+    let source = settings.key_protector;
+
+    let target_passphrase = passphrase.or_else(|| key_state.passphrase());
+    let target_protector = create_protector(target, app_handle, target_passphrase.clone())?;
+    target_protector.store_key(&key)?;
+
+    // Make sure the key can be read back before dropping the old copy
+    if target_protector.load_key()? != Some(key) {
+        return Err("Key could not be read back from the new protector".to_string());
+    }
+
+    settings.key_protector = target;
+    save_clipboard_settings(app_handle, &settings)?;
+
+    if source != target {
+        match create_protector(source, app_handle, key_state.passphrase()) {
+            Ok(source_protector) => {
+                if let Err(e) = source_protector.delete_key() {
+                    eprintln!(
+                        "Failed to remove old clipboard key from {:?}: {}",
+                        source, e
+                    );
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+
+    if target == KeyProtectorKind::Passphrase {
+        key_state.set_passphrase(target_passphrase);
+    }
+
+    println!("Clipboard key is now protected by {:?}", target);
+    Ok(())
+}
+
+// Store a new key in the configured protector, used by key rotation
+pub fn replace_key(app_handle: &tauri::AppHandle, key: HistoryKey) -> Result<(), String> {
+    let key_state = key_state(app_handle)?;
+    let _loading = key_state
+        .loading
+        .lock()
+        .map_err(|_| "Clipboard key lock poisoned".to_string())?;
+    let kind = load_clipboard_settings(app_handle).key_protector;
+
+    let protector = create_protector(kind, app_handle, key_state.passphrase())?;
+    protector.store_key(&key)?;
+    if protector.load_key()? != Some(key) {
+        return Err("New key could not be read back from the protector".to_string());
+    }
+
+    key_state.cache_key(Some(key));
+    Ok(())
+}
+
+// Use a passphrase for this session and drop any cached key so it is checked
+pub fn set_session_passphrase(
+    app_handle: &tauri::AppHandle,
+    passphrase: String,
+) -> Result<(), String> {
+    let key_state = key_state(app_handle)?;
+    key_state.set_passphrase(Some(passphrase));
+    key_state.cache_key(None);
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, Key, KeyInit, Nonce};
+use argon2::Argon2;
+use rand::{rngs::OsRng, RngCore};
+use std::fs;
+use std::path::PathBuf;
+
+use super::{key_from_bytes, HistoryKey, KeyProtector};
+use crate::persistence::files::write_atomic;
+
+// Layout of passphrase_key.bin: version | salt | nonce | encrypted key
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+// Fallback for systems without DPAPI or a keyring: the history key is wrapped
+// with a key derived from the user's passphrase
+pub struct PassphraseProtector {
+    key_path: PathBuf,
+    passphrase: Option<String>,
+}
+
+impl PassphraseProtector {
+    pub fn new(key_path: PathBuf, passphrase: Option<String>) -> Self {
+        Self {
+            key_path,
+            passphrase,
+        }
+    }
+
+    fn cipher(&self, salt: &[u8]) -> Result<Aes256Gcm, String> {
+        let passphrase = self
+            .passphrase
+            .as_ref()
+            .ok_or_else(|| "Clipboard history is locked, a passphrase is required".to_string())?;
+
+        let mut wrapping_key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut wrapping_key)
+            .map_err(|e| format!("Failed to derive key from passphrase: {}", e))?;
+
+        Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&wrapping_key)))
+    }
+}
+
+impl KeyProtector for PassphraseProtector {
+    fn load_key(&self) -> Result<Option<HistoryKey>, String> {
+        if !self.key_path.exists() {
+            return Ok(None);
+        }
+
+        let data = fs::read(&self.key_path)
+            .map_err(|e| format!("Failed to read passphrase protected key: {}", e))?;
+
+        if data.len() <= 1 + SALT_LEN + NONCE_LEN || data[0] != FORMAT_VERSION {
+            return Err("Invalid passphrase protected key file".to_string());
+        }
+
+        let salt = &data[1..1 + SALT_LEN];
+        let nonce = Nonce::from_slice(&data[1 + SALT_LEN..1 + SALT_LEN + NONCE_LEN]);
+        let ciphertext = &data[1 + SALT_LEN + NONCE_LEN..];
+
+        let key_data = self
+            .cipher(salt)?
+            .decrypt(nonce, ciphertext)
+            .map_err(|_| "Wrong passphrase for clipboard history".to_string())?;
+
+        key_from_bytes(&key_data).map(Some)
+    }
+
+    fn store_key(&self, key: &HistoryKey) -> Result<(), String> {
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce_bytes);
+
+        let ciphertext = self
+            .cipher(&salt)?
+            .encrypt(Nonce::from_slice(&nonce_bytes), key.as_slice())
+            .map_err(|e| format!("Encryption failed: {:?}", e))?;
+
+        let mut data = vec![FORMAT_VERSION];
+        data.extend_from_slice(&salt);
+        data.extend_from_slice(&nonce_bytes);
+        data.extend_from_slice(&ciphertext);
+
+        write_atomic(&self.key_path, &data)
+    }
+
+    fn delete_key(&self) -> Result<(), String> {
+        if self.key_path.exists() {
+            fs::remove_file(&self.key_path)
+                .map_err(|e| format!("Failed to delete passphrase protected key: {}", e))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use keyring::secret_service::SsCredential;
+use keyring::Entry;
+
+use super::{key_from_bytes, HistoryKey, KeyProtector};
+
+const SERVICE: &str = "blazecast";
+const USER: &str = "clipboard-history-key";
+
+// Key kept in the desktop Secret Service (GNOME Keyring, KWallet, ...). The
+// kernel keyring is no fallback, it forgets the key on reboot. Without a
+// Secret Service the passphrase protector has to be used.
+pub struct SystemKeyringProtector;
+
+fn secret_service_entry() -> Result<Entry, String> {
+    SsCredential::new_with_target(None, SERVICE, USER)
+        .map(|credential| Entry::new_with_credential(Box::new(credential)))
+        .map_err(|e| format!("Failed to open Secret Service entry: {}", e))
+}
+
+fn read_entry(entry: &Entry) -> Result<Option<HistoryKey>, String> {
+    match entry.get_password() {
+        Ok(encoded) => {
+            let key_data = STANDARD
+                .decode(encoded)
+                .map_err(|e| format!("Base64 decoding failed: {}", e))?;
+            key_from_bytes(&key_data).map(Some)
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read key from keyring: {}", e)),
+    }
+}
+
+impl KeyProtector for SystemKeyringProtector {
+    fn load_key(&self) -> Result<Option<HistoryKey>, String> {
+        read_entry(&secret_service_entry()?)
+    }
+
+    fn store_key(&self, key: &HistoryKey) -> Result<(), String> {
+        secret_service_entry()?
+            .set_password(&STANDARD.encode(key))
+            .map_err(|e| {
+                format!(
+                    "Failed to store key in Secret Service, protect it with a passphrase instead: {}",
+                    e
+                )
+            })
+    }
+
+    fn delete_key(&self) -> Result<(), String> {
+        match secret_service_entry()?.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("Failed to delete key from keyring: {}", e)),
+        }
+    }
+}
@@ -1,11 +1,67 @@
+pub mod bundle;
 pub mod clear_clipboard;
+pub mod content_hash;
+pub mod content_kind;
+pub mod envelope;
+pub mod formats;
+pub mod history_commands;
+pub mod history_store;
 pub mod image_handler;
+pub mod key_protector;
+pub mod monitor;
+pub mod paste;
+pub mod paste_stack;
 pub mod read_clipboard;
+pub mod recovery;
+pub mod retention;
+pub mod search;
+pub mod sensitive;
+pub mod settings;
+pub mod source_app;
 pub mod storage;
+pub mod sync;
+pub mod transforms;
 pub mod write_clipboard;
 
-pub use clear_clipboard::{clear_system_clipboard, delete_from_clipboard};
-pub use image_handler::{get_clipboard_image, load_clipboard_image_bytes, set_clipboard_image, delete_clipboard_image_file};
+pub use bundle::{export_clipboard_bundle, export_clipboard_text, import_clipboard_bundle};
+pub use clear_clipboard::{
+    clear_system_clipboard, delete_and_clear_clipboard_item, delete_from_clipboard,
+};
+pub use content_kind::open_clipboard_item;
+pub use history_commands::{
+    add_clipboard_item, clear_clipboard_history, delete_clipboard_item, list_clipboard_items,
+    mark_clipboard_item_copied, pin_clipboard_item, restore_clipboard_item, search_clipboard_items,
+    take_clipboard_recovery_notice, update_clipboard_item,
+};
+pub use history_store::ClipboardHistoryStore;
+pub use image_handler::{
+    delete_clipboard_image_file, get_clipboard_image, load_clipboard_image_bytes,
+    set_clipboard_image,
+};
+pub use key_protector::{
+    get_clipboard_key_error, get_clipboard_key_protector, reset_clipboard_key,
+    rotate_clipboard_key, set_clipboard_key_protector, unlock_clipboard_history, ClipboardKeyState,
+};
+pub use monitor::{start_clipboard_monitor, OwnClipboardWrites};
+pub use paste::{
+    get_clipboard_typed_paste_apps, paste_clipboard_item, remember_paste_target,
+    set_clipboard_typed_paste_apps, PasteTargetState,
+};
+pub use paste_stack::{
+    get_paste_stack, paste_next_from_stack, start_paste_stack, stop_paste_stack,
+    toggle_paste_stack, PasteStackState,
+};
 pub use read_clipboard::get_clipboard;
-pub use storage::{load_clipboard_history, pin_clipboard_item, save_clipboard_history};
+pub use retention::{get_clipboard_retention, set_clipboard_retention, start_history_maintenance};
+pub use sensitive::{
+    get_clipboard_sensitive_rules, set_clipboard_sensitive_rules, start_expiry_sweeper,
+};
+pub use source_app::{get_clipboard_excluded_apps, set_clipboard_excluded_apps};
+pub use storage::load_clipboard_history;
+pub use sync::{
+    get_clipboard_sync_status, pair_clipboard_sync_device, remove_clipboard_sync_device,
+    set_clipboard_sync, start_clipboard_sync, start_clipboard_sync_pairing, sync_clipboard_now,
+    ClipboardSyncState,
+};
+pub use transforms::transform_clipboard_text;
 pub use write_clipboard::set_clipboard;
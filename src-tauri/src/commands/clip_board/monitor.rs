@@ -0,0 +1,518 @@
+use arboard::ImageData as ArboardImageData;
+use sha2::{Digest, Sha256};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use super::content_hash::{files_content_hash, image_content_hash, text_content_hash};
+use super::formats::{capture_formats, has_exclusion_hint, ClipboardFormats};
+use super::history_store::{ClipboardHistoryStore, NewClipboardItem};
+use super::image_handler::{pixel_hash, store_hashed_clipboard_image, ImageResponse};
+use super::paste_stack::{push_to_paste_stack, PasteStackState};
+use super::retention::emit_removed;
+use super::settings::load_clipboard_settings;
+use super::source_app::current_source_app;
+use super::storage::ImageData;
+use super::sync::request_clipboard_sync;
+
+pub const CLIPBOARD_CHANGED_EVENT: &str = "clipboard-changed";
+
+// Only used when the OS can't notify us about clipboard changes
+const POLL_INTERVAL: Duration = Duration::from_millis(750);
+
+// Own writes the monitor never saw, e.g. because polling missed them, are
+// forgotten after this long so they don't hide a later copy
+const OWN_WRITE_LIFETIME: Duration = Duration::from_secs(5);
+
+// New content seen on the system clipboard
+pub enum ClipboardChange {
+    Text {
+        text: String,
+        formats: ClipboardFormats,
+    },
+    Files {
+        paths: Vec<String>,
+        formats: ClipboardFormats,
+    },
+    // Not stored yet, that only happens once the exclusion checks passed
+    Image {
+        image_data: ArboardImageData<'static>,
+        hash: String,
+        formats: ClipboardFormats,
+    },
+}
+
+// Content this app puts on the clipboard only to paste it, like an expanded
+// snippet, and what was there before it. None of it is recorded as a copy.
+#[derive(Default)]
+pub struct OwnClipboardWrites {
+    expected: Mutex<Vec<(String, Instant)>>,
+}
+
+impl OwnClipboardWrites {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `hash` as computed by `content_hash`
+    pub fn expect(&self, hash: String) {
+        if let Ok(mut expected) = self.expected.lock() {
+            expected.retain(|(_, written_at)| written_at.elapsed() < OWN_WRITE_LIFETIME);
+            expected.push((hash, Instant::now()));
+        }
+    }
+
+    fn take(&self, hash: &str) -> bool {
+        let mut expected = match self.expected.lock() {
+            Ok(expected) => expected,
+            Err(_) => return false,
+        };
+        expected.retain(|(_, written_at)| written_at.elapsed() < OWN_WRITE_LIFETIME);
+        match expected.iter().position(|(expected, _)| expected == hash) {
+            Some(index) => {
+                expected.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Remembers what was reported last so repeated notifications don't produce duplicates
+#[derive(Default)]
+struct ChangeTracker {
+    clipboard: Option<arboard::Clipboard>,
+    last_hash: Option<String>,
+    last_image_fingerprint: Option<u64>,
+}
+
+impl ChangeTracker {
+    // Read the clipboard and return its content if it differs from the last report.
+    // With `sampled` set, images are compared by a cheap fingerprint first so
+    // blind polling doesn't hash the full image every time.
+    fn check(&mut self, sampled: bool) -> Option<ClipboardChange> {
+        if self.clipboard.is_none() {
+            match arboard::Clipboard::new() {
+                Ok(clipboard) => self.clipboard = Some(clipboard),
+                Err(e) => {
+                    eprintln!("Failed to create clipboard: {}", e);
+                    return None;
+                }
+            }
+        }
+        let clipboard = self.clipboard.as_mut()?;
+        let mut formats = capture_formats(clipboard);
+
+        // File managers also offer the paths as text, so files are checked first
+        if let Some(paths) = formats.files.clone() {
+            let hash = hash_bytes(paths.join("\n").as_bytes());
+            if self.last_hash.as_deref() == Some(hash.as_str()) {
+                return None;
+            }
+
+            self.last_hash = Some(hash);
+            self.last_image_fingerprint = None;
+            return Some(ClipboardChange::Files { paths, formats });
+        }
+
+        if let Ok(text) = clipboard.get_text() {
+            if !text.is_empty() {
+                let hash = hash_bytes(text.as_bytes());
+                if self.last_hash.as_deref() == Some(hash.as_str()) {
+                    return None;
+                }
+
+                self.last_hash = Some(hash);
+                self.last_image_fingerprint = None;
+                return Some(ClipboardChange::Text { text, formats });
+            }
+        }
+
+        if let Ok(image_data) = clipboard.get_image() {
+            if sampled {
+                let fingerprint = image_fingerprint(&image_data);
+                if self.last_image_fingerprint == Some(fingerprint) {
+                    return None;
+                }
+                self.last_image_fingerprint = Some(fingerprint);
+            }
+
+            // Hashing the pixels is cheap next to encoding and encrypting the
+            // image, so an unchanged image stops here
+            let hash = pixel_hash(&image_data);
+            if self.last_hash.as_deref() == Some(hash.as_str()) {
+                return None;
+            }
+
+            self.last_hash = Some(hash.clone());
+            // Images copied from a browser keep their markup and page URL, RTF is dropped
+            formats.rtf = None;
+            return Some(ClipboardChange::Image {
+                image_data,
+                hash,
+                formats,
+            });
+        }
+
+        // Emptied, e.g. when a deleted item was cleared from it. Copying the
+        // same content again has to be recorded again.
+        self.last_hash = None;
+        self.last_image_fingerprint = None;
+        None
+    }
+}
+
+impl ClipboardChange {
+    fn content_hash(&self) -> String {
+        match self {
+            ClipboardChange::Text { text, .. } => text_content_hash(text),
+            ClipboardChange::Files { paths, .. } => {
+                files_content_hash(paths.iter().map(String::as_str))
+            }
+            ClipboardChange::Image { hash, .. } => image_content_hash(hash),
+        }
+    }
+
+    // The item to add, plus the image still to be stored for image changes
+    fn into_new_item(
+        self,
+    ) -> (
+        NewClipboardItem,
+        Option<(ArboardImageData<'static>, String)>,
+    ) {
+        match self {
+            ClipboardChange::Text { text, formats } => (
+                NewClipboardItem {
+                    content_type: "text".to_string(),
+                    text: Some(text),
+                    image_data: None,
+                    formats,
+                },
+                None,
+            ),
+            // The paths double as the searchable text of the entry
+            ClipboardChange::Files { paths, formats } => (
+                NewClipboardItem {
+                    content_type: "files".to_string(),
+                    text: Some(paths.join("\n")),
+                    image_data: None,
+                    formats,
+                },
+                None,
+            ),
+            ClipboardChange::Image {
+                image_data,
+                hash,
+                formats,
+            } => (
+                NewClipboardItem {
+                    content_type: "image".to_string(),
+                    text: None,
+                    image_data: None,
+                    formats,
+                },
+                Some((image_data, hash)),
+            ),
+        }
+    }
+}
+
+fn stored_image_data(image: ImageResponse) -> ImageData {
+    ImageData {
+        width: image.width,
+        height: image.height,
+        hash: image.hash,
+        file_path: image.file_path,
+        thumbnail_path: Some(image.thumbnail_path),
+    }
+}
+
+// Add a change to the history and tell the webview about the new item
+fn record_change(app_handle: &AppHandle, change: ClipboardChange) {
+    let history_store = match app_handle.try_state::<ClipboardHistoryStore>() {
+        Some(history_store) => history_store,
+        None => {
+            eprintln!("Clipboard history store is not available, change dropped");
+            return;
+        }
+    };
+
+    let own_write = app_handle
+        .try_state::<OwnClipboardWrites>()
+        .is_some_and(|own_writes| own_writes.take(&change.content_hash()));
+    if own_write {
+        return;
+    }
+
+    let settings = load_clipboard_settings(app_handle);
+    let source = current_source_app(app_handle);
+    if source
+        .as_ref()
+        .is_some_and(|source| settings.is_excluded(source))
+    {
+        return;
+    }
+
+    let (mut new_item, image) = change.into_new_item();
+    if !settings
+        .sensitive
+        .apply(&mut new_item, has_exclusion_hint())
+    {
+        return;
+    }
+    new_item.source = source;
+
+    // Nothing reaches the disk before the checks above passed
+    if let Some((image_data, hash)) = image {
+        match store_hashed_clipboard_image(app_handle, image_data, hash) {
+            Ok(image) => new_item.image_data = Some(stored_image_data(image)),
+            Err(e) => {
+                eprintln!("Failed to store clipboard image: {}", e);
+                return;
+            }
+        }
+    }
+
+    // Copies of content that is already stored go on the paste stack as well
+    let stacked = app_handle
+        .try_state::<PasteStackState>()
+        .is_some_and(|paste_stack| paste_stack.is_active())
+        .then(|| new_item.clone());
+
+    match history_store.add(new_item) {
+        Ok(Some(added)) => {
+            push_to_paste_stack(app_handle, added.item.id);
+            if let Err(e) = app_handle.emit_all(CLIPBOARD_CHANGED_EVENT, added.item) {
+                eprintln!("Failed to emit clipboard change: {}", e);
+            }
+            emit_removed(app_handle, &added.removed);
+            request_clipboard_sync(app_handle);
+        }
+        // Already in the history
+        Ok(None) => {
+            if let Some(new_item) = stacked {
+                match history_store.find_same(&new_item) {
+                    Ok(Some(item_id)) => push_to_paste_stack(app_handle, item_id),
+                    Ok(None) => {}
+                    Err(e) => eprintln!("Failed to look up clipboard item: {}", e),
+                }
+            }
+        }
+        Err(e) => eprintln!("Failed to add clipboard item: {}", e),
+    }
+}
+
+fn hash_bytes(bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+// Cheap identity of an image: its size plus a sample of about 4k bytes
+fn image_fingerprint(image_data: &ArboardImageData) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    image_data.width.hash(&mut hasher);
+    image_data.height.hash(&mut hasher);
+
+    let step = (image_data.bytes.len() / 4096).max(1);
+    for byte in image_data.bytes.iter().step_by(step) {
+        byte.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+// Start watching the system clipboard. Every new piece of content is added to
+// the history store and reported once through the `clipboard-changed` event.
+pub fn start_clipboard_monitor(app_handle: AppHandle) {
+    let spawned = thread::Builder::new()
+        .name("clipboard-monitor".to_string())
+        .spawn(move || {
+            let mut tracker = ChangeTracker::default();
+            let mut report = |sampled: bool| {
+                if let Some(change) = tracker.check(sampled) {
+                    record_change(&app_handle, change);
+                }
+            };
+
+            // Pick up whatever is on the clipboard right now
+            report(false);
+
+            if let Err(e) = listen_for_changes(&mut || report(false)) {
+                eprintln!(
+                    "Clipboard change notifications unavailable, falling back to polling: {}",
+                    e
+                );
+                poll_for_changes(&mut report);
+            }
+        });
+
+    if let Err(e) = spawned {
+        eprintln!("Failed to start clipboard monitor: {}", e);
+    }
+}
+
+fn poll_for_changes(report: &mut dyn FnMut(bool)) {
+    let mut last_sequence = clipboard_sequence_number();
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        match clipboard_sequence_number() {
+            // The OS tells us whether anything changed since the last poll
+            Some(sequence) => {
+                if last_sequence != Some(sequence) {
+                    last_sequence = Some(sequence);
+                    report(false);
+                }
+            }
+            // Without it every poll has to look at the content itself
+            None => report(true),
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn clipboard_sequence_number() -> Option<u32> {
+    Some(unsafe { winapi::um::winuser::GetClipboardSequenceNumber() })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn clipboard_sequence_number() -> Option<u32> {
+    None
+}
+
+// Block on a hidden message-only window registered with AddClipboardFormatListener
+#[cfg(target_os = "windows")]
+fn listen_for_changes(on_change: &mut dyn FnMut()) -> Result<(), String> {
+    use std::ptr;
+    use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+    use winapi::shared::windef::HWND;
+    use winapi::um::libloaderapi::GetModuleHandleW;
+    use winapi::um::winuser::{
+        AddClipboardFormatListener, CreateWindowExW, DefWindowProcW, DestroyWindow,
+        DispatchMessageW, GetMessageW, PostMessageW, RegisterClassW, RemoveClipboardFormatListener,
+        TranslateMessage, HWND_MESSAGE, MSG, WM_APP, WM_CLIPBOARDUPDATE, WNDCLASSW,
+    };
+
+    // WM_CLIPBOARDUPDATE may be sent straight to the window procedure, so it is
+    // re-posted to the queue where the loop below can pick it up
+    const WM_CLIPBOARD_CHANGED: UINT = WM_APP + 1;
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        msg: UINT,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if msg == WM_CLIPBOARDUPDATE {
+            PostMessageW(hwnd, WM_CLIPBOARD_CHANGED, 0, 0);
+            return 0;
+        }
+        DefWindowProcW(hwnd, msg, wparam, lparam)
+    }
+
+    unsafe {
+        let class_name: Vec<u16> = "BlazeCastClipboardMonitor\0".encode_utf16().collect();
+        let instance = GetModuleHandleW(ptr::null());
+
+        let mut window_class: WNDCLASSW = std::mem::zeroed();
+        window_class.lpfnWndProc = Some(window_proc);
+        window_class.hInstance = instance;
+        window_class.lpszClassName = class_name.as_ptr();
+
+        if RegisterClassW(&window_class) == 0 {
+            return Err("Failed to register clipboard monitor window class".to_string());
+        }
+
+        let hwnd = CreateWindowExW(
+            0,
+            class_name.as_ptr(),
+            class_name.as_ptr(),
+            0,
+            0,
+            0,
+            0,
+            0,
+            HWND_MESSAGE, // Message-only window, never shown
+            ptr::null_mut(),
+            instance,
+            ptr::null_mut(),
+        );
+        if hwnd.is_null() {
+            return Err("Failed to create clipboard monitor window".to_string());
+        }
+
+        if AddClipboardFormatListener(hwnd) == 0 {
+            DestroyWindow(hwnd);
+            return Err("AddClipboardFormatListener failed".to_string());
+        }
+
+        let mut msg: MSG = std::mem::zeroed();
+        while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) > 0 {
+            if msg.message == WM_CLIPBOARD_CHANGED {
+                on_change();
+            } else {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+
+        RemoveClipboardFormatListener(hwnd);
+        DestroyWindow(hwnd);
+    }
+
+    Err("Clipboard monitor message loop ended".to_string())
+}
+
+// Block on XFixes selection notifications for the CLIPBOARD selection
+#[cfg(target_os = "linux")]
+fn listen_for_changes(on_change: &mut dyn FnMut()) -> Result<(), String> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+    use x11rb::protocol::xproto::ConnectionExt as _;
+    use x11rb::protocol::Event;
+
+    let (conn, screen_num) =
+        x11rb::connect(None).map_err(|e| format!("Failed to connect to X11: {}", e))?;
+
+    conn.xfixes_query_version(5, 0)
+        .map_err(|e| format!("XFixes is not available: {}", e))?
+        .reply()
+        .map_err(|e| format!("XFixes is not available: {}", e))?;
+
+    let root = conn.setup().roots[screen_num].root;
+    let clipboard_atom = conn
+        .intern_atom(false, b"CLIPBOARD")
+        .map_err(|e| format!("Failed to intern CLIPBOARD atom: {}", e))?
+        .reply()
+        .map_err(|e| format!("Failed to intern CLIPBOARD atom: {}", e))?
+        .atom;
+
+    conn.xfixes_select_selection_input(
+        root,
+        clipboard_atom,
+        SelectionEventMask::SET_SELECTION_OWNER
+            | SelectionEventMask::SELECTION_WINDOW_DESTROY
+            | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+    )
+    .map_err(|e| format!("Failed to select clipboard events: {}", e))?;
+    conn.flush()
+        .map_err(|e| format!("Failed to flush X11 connection: {}", e))?;
+
+    loop {
+        match conn.wait_for_event() {
+            Ok(Event::XfixesSelectionNotify(_)) => on_change(),
+            Ok(_) => {}
+            Err(e) => return Err(format!("X11 connection closed: {}", e)),
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn listen_for_changes(_on_change: &mut dyn FnMut()) -> Result<(), String> {
+    Err("Clipboard change notifications are not supported on this platform".to_string())
+}
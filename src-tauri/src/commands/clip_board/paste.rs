@@ -0,0 +1,735 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+use super::content_hash::{image_content_hash, text_content_hash};
+use super::formats::restore_plain_text;
+use super::history_commands::restore_item;
+use super::history_store::ClipboardHistoryStore;
+use super::image_handler::pixel_hash;
+use super::monitor::OwnClipboardWrites;
+use super::settings::{load_clipboard_settings, save_clipboard_settings};
+use super::source_app::{foreground_window, source_for_window, ClipboardSource};
+use super::storage::ClipboardItem;
+
+// Time the target window gets to take the focus before keys are sent to it
+const FOCUS_DELAY: Duration = Duration::from_millis(120);
+
+// The target app handles the paste before the caret is moved
+const PASTE_DELAY: Duration = Duration::from_millis(50);
+
+// The target app reads the clipboard after the paste keystroke arrives, what
+// was on it before is only put back after that
+const CLIPBOARD_RESTORE_DELAY: Duration = Duration::from_millis(300);
+
+// Held modifiers would turn the paste keystroke into a different shortcut
+const MODIFIER_RELEASE_TIMEOUT: Duration = Duration::from_secs(1);
+const MODIFIER_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone)]
+struct PasteTarget {
+    window: u64,
+    source: Option<ClipboardSource>,
+}
+
+// The window that was in the foreground when the launcher opened
+#[derive(Default)]
+pub struct PasteTargetState {
+    target: Mutex<Option<PasteTarget>>,
+}
+
+impl PasteTargetState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn get(&self) -> Option<PasteTarget> {
+        self.target.lock().ok().and_then(|target| target.clone())
+    }
+}
+
+// Call right before the launcher is shown. Our own windows are never a target,
+// with one of them in front the earlier target is forgotten.
+pub fn remember_paste_target(app_handle: &AppHandle) {
+    let paste_target = match app_handle.try_state::<PasteTargetState>() {
+        Some(paste_target) => paste_target,
+        None => return,
+    };
+
+    let target = foreground_window().map(|window| PasteTarget {
+        window,
+        source: source_for_window(app_handle, window),
+    });
+    let own_path = std::env::current_exe().ok();
+    let is_own = target
+        .as_ref()
+        .is_some_and(|target| match (&target.source, &own_path) {
+            (Some(source), Some(own_path)) => std::path::Path::new(&source.path) == own_path,
+            _ => false,
+        });
+
+    if let Ok(mut current) = paste_target.target.lock() {
+        *current = target.filter(|_| !is_own);
+    }
+}
+
+// Restore a history entry and paste it into the window that was active before
+// the launcher opened. Apps listed in `typed_paste_apps` get text items typed
+// in instead, for fields that block pasting. Without a known window this is a
+// plain copy.
+#[tauri::command]
+pub async fn paste_clipboard_item(
+    item_id: u64,
+    plain_text: Option<bool>,
+    app_handle: AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+    paste_target: State<'_, PasteTargetState>,
+) -> Result<ClipboardItem, String> {
+    let target = paste_target.get();
+    let settings = load_clipboard_settings(&app_handle);
+    let typed = target
+        .as_ref()
+        .and_then(|target| target.source.as_ref())
+        .is_some_and(|source| settings.types_paste(source));
+
+    let item = history_store.get(item_id)?;
+    let typed_text = match (&item.text, item.content_type.as_str()) {
+        (Some(text), "text") if typed => Some(text.clone()),
+        _ => None,
+    };
+    let item = match typed_text {
+        Some(_) => history_store.mark_copied(item_id)?,
+        None => restore_item(
+            &app_handle,
+            &history_store,
+            item_id,
+            plain_text.unwrap_or(false),
+        )?,
+    };
+
+    if let Some(window) = app_handle.get_window("main") {
+        window.hide().map_err(|e| e.to_string())?;
+    }
+
+    let target = match target {
+        Some(target) => target,
+        None => return Ok(item),
+    };
+    // Waits for the focus to move, off the async runtime's threads
+    tauri::async_runtime::spawn_blocking(move || {
+        native::focus_window(target.window)?;
+        thread::sleep(FOCUS_DELAY);
+
+        match typed_text {
+            Some(text) => native::type_text(&text),
+            None => native::send_paste_keystroke(),
+        }
+    })
+    .await
+    .map_err(|e| format!("Failed to paste clipboard item: {}", e))??;
+
+    Ok(item)
+}
+
+// Text or image on the clipboard before `paste_text` replaced it. Other
+// content, like copied files, can't be put back.
+enum SavedClipboard {
+    Text(String),
+    Image(arboard::ImageData<'static>),
+}
+
+impl SavedClipboard {
+    fn read() -> Option<Self> {
+        let mut clipboard = arboard::Clipboard::new().ok()?;
+        if let Ok(text) = clipboard.get_text() {
+            if !text.is_empty() {
+                return Some(SavedClipboard::Text(text));
+            }
+        }
+        clipboard.get_image().ok().map(SavedClipboard::Image)
+    }
+
+    fn restore(self, app_handle: &AppHandle) -> Result<(), String> {
+        let mut clipboard =
+            arboard::Clipboard::new().map_err(|e| format!("Failed to create clipboard: {}", e))?;
+        match self {
+            SavedClipboard::Text(text) => {
+                expect_own_write(app_handle, text_content_hash(&text));
+                clipboard
+                    .set_text(text)
+                    .map_err(|e| format!("Failed to restore clipboard text: {}", e))
+            }
+            SavedClipboard::Image(image) => {
+                expect_own_write(app_handle, image_content_hash(&pixel_hash(&image)));
+                clipboard
+                    .set_image(image)
+                    .map_err(|e| format!("Failed to restore clipboard image: {}", e))
+            }
+        }
+    }
+}
+
+// The clipboard monitor skips the write instead of recording it
+fn expect_own_write(app_handle: &AppHandle, hash: String) {
+    if let Some(own_writes) = app_handle.try_state::<OwnClipboardWrites>() {
+        own_writes.expect(hash);
+    }
+}
+
+// Paste text that isn't a history item, like an expanded snippet, the same
+// way `paste_clipboard_item` does. The caret is moved `cursor_back`
+// characters to the left afterwards. The text only passes through the
+// clipboard: it isn't recorded in the history, and what was on the clipboard
+// is put back once it has been pasted.
+pub fn paste_text(app_handle: &AppHandle, text: &str, cursor_back: usize) -> Result<(), String> {
+    let target = app_handle
+        .try_state::<PasteTargetState>()
+        .and_then(|paste_target| paste_target.get());
+    let settings = load_clipboard_settings(app_handle);
+    let typed = target
+        .as_ref()
+        .and_then(|target| target.source.as_ref())
+        .is_some_and(|source| settings.types_paste(source));
+
+    let saved = if typed {
+        None
+    } else {
+        copy_for_paste(app_handle, text)?
+    };
+
+    if let Some(window) = app_handle.get_window("main") {
+        window.hide().map_err(|e| e.to_string())?;
+    }
+
+    let target = match target {
+        Some(target) => target,
+        None => return Ok(()),
+    };
+    native::focus_window(target.window)?;
+    thread::sleep(FOCUS_DELAY);
+
+    insert_text(app_handle, text, cursor_back, typed, saved)
+}
+
+// Replace the `erase` characters before the caret in the focused window with
+// `text`, e.g. a typed snippet keyword with its expansion
+pub fn replace_typed_text(
+    app_handle: &AppHandle,
+    erase: usize,
+    text: &str,
+    cursor_back: usize,
+) -> Result<(), String> {
+    let settings = load_clipboard_settings(app_handle);
+    let typed = foreground_window()
+        .and_then(|window| source_for_window(app_handle, window))
+        .is_some_and(|source| settings.types_paste(&source));
+
+    native::wait_for_modifiers_released(MODIFIER_RELEASE_TIMEOUT)?;
+    native::delete_backward(erase)?;
+
+    let saved = if typed {
+        None
+    } else {
+        copy_for_paste(app_handle, text)?
+    };
+    insert_text(app_handle, text, cursor_back, typed, saved)
+}
+
+// Put `text` on the clipboard, returns what was there before
+fn copy_for_paste(app_handle: &AppHandle, text: &str) -> Result<Option<SavedClipboard>, String> {
+    let saved = SavedClipboard::read();
+    expect_own_write(app_handle, text_content_hash(text));
+    restore_plain_text(text)?;
+    Ok(saved)
+}
+
+// Type or paste into the focused window, then put the clipboard back
+fn insert_text(
+    app_handle: &AppHandle,
+    text: &str,
+    cursor_back: usize,
+    typed: bool,
+    saved: Option<SavedClipboard>,
+) -> Result<(), String> {
+    if typed {
+        native::type_text(text)?;
+    } else {
+        native::send_paste_keystroke()?;
+    }
+
+    if cursor_back > 0 {
+        thread::sleep(PASTE_DELAY);
+        native::move_cursor_left(cursor_back)?;
+    }
+
+    if let Some(saved) = saved {
+        thread::sleep(CLIPBOARD_RESTORE_DELAY);
+        saved.restore(app_handle)?;
+    }
+    Ok(())
+}
+
+// Paste into whatever window has the focus, once the user has let go of the
+// modifiers of the hotkey that triggered it
+pub fn paste_into_focused_window() -> Result<(), String> {
+    native::wait_for_modifiers_released(MODIFIER_RELEASE_TIMEOUT)?;
+    native::send_paste_keystroke()
+}
+
+#[tauri::command]
+pub fn get_clipboard_typed_paste_apps(app_handle: AppHandle) -> Vec<String> {
+    load_clipboard_settings(&app_handle).typed_paste_apps
+}
+
+#[tauri::command]
+pub fn set_clipboard_typed_paste_apps(
+    apps: Vec<String>,
+    app_handle: AppHandle,
+) -> Result<Vec<String>, String> {
+    let mut settings = load_clipboard_settings(&app_handle);
+    settings.typed_paste_apps = apps
+        .into_iter()
+        .map(|app| app.trim().to_string())
+        .filter(|app| !app.is_empty())
+        .collect();
+    save_clipboard_settings(&app_handle, &settings)?;
+
+    Ok(settings.typed_paste_apps)
+}
+
+#[cfg(target_os = "windows")]
+mod native {
+    use std::mem;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use winapi::shared::minwindef::DWORD;
+    use winapi::shared::windef::HWND;
+    use winapi::um::winuser::{
+        GetAsyncKeyState, IsIconic, SendInput, SetForegroundWindow, ShowWindow, INPUT,
+        INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY, KEYEVENTF_KEYUP, KEYEVENTF_UNICODE,
+        SW_RESTORE, VK_BACK, VK_CONTROL, VK_LEFT, VK_LWIN, VK_MENU, VK_RETURN, VK_RWIN, VK_SHIFT,
+    };
+
+    use super::MODIFIER_POLL_INTERVAL;
+
+    const VK_V: u16 = 0x56;
+
+    pub fn focus_window(window: u64) -> Result<(), String> {
+        let hwnd = window as HWND;
+        unsafe {
+            if IsIconic(hwnd) != 0 {
+                ShowWindow(hwnd, SW_RESTORE);
+            }
+            if SetForegroundWindow(hwnd) == 0 {
+                return Err("Failed to focus the previous window".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn key_input(virtual_key: u16, scan_code: u16, flags: DWORD) -> INPUT {
+        unsafe {
+            let mut input: INPUT = mem::zeroed();
+            input.type_ = INPUT_KEYBOARD;
+            *input.u.ki_mut() = KEYBDINPUT {
+                wVk: virtual_key,
+                wScan: scan_code,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            };
+            input
+        }
+    }
+
+    fn send_inputs(inputs: &mut [INPUT]) -> Result<(), String> {
+        if inputs.is_empty() {
+            return Ok(());
+        }
+
+        let sent = unsafe {
+            SendInput(
+                inputs.len() as u32,
+                inputs.as_mut_ptr(),
+                mem::size_of::<INPUT>() as i32,
+            )
+        };
+        if sent as usize != inputs.len() {
+            return Err("Failed to send keyboard input".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn wait_for_modifiers_released(timeout: Duration) -> Result<(), String> {
+        let deadline = Instant::now() + timeout;
+        let held = || {
+            [VK_SHIFT, VK_CONTROL, VK_MENU, VK_LWIN, VK_RWIN]
+                .iter()
+                .any(|&key| unsafe { GetAsyncKeyState(key) } < 0)
+        };
+        while held() && Instant::now() < deadline {
+            thread::sleep(MODIFIER_POLL_INTERVAL);
+        }
+        Ok(())
+    }
+
+    pub fn send_paste_keystroke() -> Result<(), String> {
+        let control = VK_CONTROL as u16;
+        send_inputs(&mut [
+            key_input(control, 0, 0),
+            key_input(VK_V, 0, 0),
+            key_input(VK_V, 0, KEYEVENTF_KEYUP),
+            key_input(control, 0, KEYEVENTF_KEYUP),
+        ])
+    }
+
+    // Unicode key events, so the keyboard layout doesn't matter
+    pub fn type_text(text: &str) -> Result<(), String> {
+        let mut inputs = Vec::with_capacity(text.len() * 2);
+        for unit in text.encode_utf16() {
+            match unit {
+                0x0D => {}
+                0x0A => {
+                    inputs.push(key_input(VK_RETURN as u16, 0, 0));
+                    inputs.push(key_input(VK_RETURN as u16, 0, KEYEVENTF_KEYUP));
+                }
+                unit => {
+                    inputs.push(key_input(0, unit, KEYEVENTF_UNICODE));
+                    inputs.push(key_input(0, unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP));
+                }
+            }
+        }
+        send_inputs(&mut inputs)
+    }
+
+    pub fn move_cursor_left(count: usize) -> Result<(), String> {
+        let left = VK_LEFT as u16;
+        let mut inputs = Vec::with_capacity(count * 2);
+        for _ in 0..count {
+            inputs.push(key_input(left, 0, KEYEVENTF_EXTENDEDKEY));
+            inputs.push(key_input(left, 0, KEYEVENTF_EXTENDEDKEY | KEYEVENTF_KEYUP));
+        }
+        send_inputs(&mut inputs)
+    }
+
+    pub fn delete_backward(count: usize) -> Result<(), String> {
+        let back = VK_BACK as u16;
+        let mut inputs = Vec::with_capacity(count * 2);
+        for _ in 0..count {
+            inputs.push(key_input(back, 0, 0));
+            inputs.push(key_input(back, 0, KEYEVENTF_KEYUP));
+        }
+        send_inputs(&mut inputs)
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{
+        ClientMessageEvent, ConnectionExt as _, EventMask, Keycode, Keysym, KEY_PRESS_EVENT,
+        KEY_RELEASE_EVENT,
+    };
+    use x11rb::protocol::xtest::ConnectionExt as _;
+    use x11rb::rust_connection::RustConnection;
+
+    use super::MODIFIER_POLL_INTERVAL;
+
+    const XK_RETURN: Keysym = 0xff0d;
+    const XK_TAB: Keysym = 0xff09;
+    const XK_SHIFT_L: Keysym = 0xffe1;
+    const XK_CONTROL_L: Keysym = 0xffe3;
+    const XK_V: Keysym = 0x0076;
+    const XK_LEFT: Keysym = 0xff51;
+    const XK_BACKSPACE: Keysym = 0xff08;
+
+    // Shift, Control, Meta, Alt and Super, left and right
+    const MODIFIER_KEYSYMS: &[Keysym] = &[
+        0xffe1, 0xffe2, 0xffe3, 0xffe4, 0xffe7, 0xffe8, 0xffe9, 0xffea, 0xffeb, 0xffec,
+    ];
+
+    // Clients need a moment to pick up a changed keyboard mapping
+    const REMAP_DELAY: Duration = Duration::from_millis(10);
+
+    fn connect() -> Result<(RustConnection, u32), String> {
+        let (conn, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let root = conn.setup().roots[screen_num].root;
+        Ok((conn, root))
+    }
+
+    // Ask the window manager to activate the window
+    pub fn focus_window(window: u64) -> Result<(), String> {
+        let (conn, root) = connect()?;
+        let active_window_atom = conn
+            .intern_atom(false, b"_NET_ACTIVE_WINDOW")
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?
+            .atom;
+
+        // Source indication 2 marks the request as coming from a pager, which
+        // window managers don't refuse for focus stealing prevention
+        let event = ClientMessageEvent::new(
+            32,
+            window as u32,
+            active_window_atom,
+            [2, x11rb::CURRENT_TIME, 0, 0, 0],
+        );
+        conn.send_event(
+            false,
+            root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )
+        .map_err(|e| format!("Failed to focus the previous window: {}", e))?;
+        conn.flush().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    struct Keyboard {
+        conn: RustConnection,
+        root: u32,
+        min_keycode: Keycode,
+        keysyms_per_keycode: u8,
+        keysyms: Vec<Keysym>,
+    }
+
+    impl Keyboard {
+        fn open() -> Result<Self, String> {
+            let (conn, root) = connect()?;
+            let min_keycode = conn.setup().min_keycode;
+            let count = conn.setup().max_keycode - min_keycode + 1;
+            let mapping = conn
+                .get_keyboard_mapping(min_keycode, count)
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| format!("Failed to read keyboard mapping: {}", e))?;
+
+            Ok(Self {
+                conn,
+                root,
+                min_keycode,
+                keysyms_per_keycode: mapping.keysyms_per_keycode,
+                keysyms: mapping.keysyms,
+            })
+        }
+
+        fn keycodes(&self) -> impl Iterator<Item = (Keycode, &[Keysym])> {
+            self.keysyms
+                .chunks(self.keysyms_per_keycode.max(1) as usize)
+                .enumerate()
+                .map(move |(index, keysyms)| (self.min_keycode + index as u8, keysyms))
+        }
+
+        // Keycode producing the keysym, and whether it needs shift
+        fn find(&self, keysym: Keysym) -> Option<(Keycode, bool)> {
+            self.keycodes().find_map(|(keycode, keysyms)| {
+                keysyms
+                    .iter()
+                    .take(2)
+                    .position(|&candidate| candidate == keysym)
+                    .map(|column| (keycode, column == 1))
+            })
+        }
+
+        // A keycode without any keysym, free to be remapped for characters the
+        // layout doesn't have
+        fn spare_keycode(&self) -> Option<Keycode> {
+            self.keycodes()
+                .filter(|(_, keysyms)| keysyms.iter().all(|&keysym| keysym == 0))
+                .map(|(keycode, _)| keycode)
+                .last()
+        }
+
+        fn remap(&self, keycode: Keycode, keysym: Keysym) -> Result<(), String> {
+            let keysyms = vec![keysym; self.keysyms_per_keycode as usize];
+            self.conn
+                .change_keyboard_mapping(1, keycode, self.keysyms_per_keycode, &keysyms)
+                .map_err(|e| e.to_string())?;
+            self.sync()?;
+            thread::sleep(REMAP_DELAY);
+            Ok(())
+        }
+
+        fn key(&self, keycode: Keycode, pressed: bool) -> Result<(), String> {
+            let event_type = if pressed {
+                KEY_PRESS_EVENT
+            } else {
+                KEY_RELEASE_EVENT
+            };
+            self.conn
+                .xtest_fake_input(event_type, keycode, x11rb::CURRENT_TIME, self.root, 0, 0, 0)
+                .map_err(|e| format!("Failed to send keyboard input: {}", e))?;
+            Ok(())
+        }
+
+        fn tap(&self, keycode: Keycode, modifier: Option<Keycode>) -> Result<(), String> {
+            if let Some(modifier) = modifier {
+                self.key(modifier, true)?;
+            }
+            self.key(keycode, true)?;
+            self.key(keycode, false)?;
+            if let Some(modifier) = modifier {
+                self.key(modifier, false)?;
+            }
+            Ok(())
+        }
+
+        // Round trip so everything sent so far has been processed
+        fn sync(&self) -> Result<(), String> {
+            self.conn
+                .get_input_focus()
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| e.to_string())?;
+            Ok(())
+        }
+    }
+
+    fn keysym_for(c: char) -> Keysym {
+        match c {
+            '\n' => XK_RETURN,
+            '\t' => XK_TAB,
+            // Latin-1 keysyms equal their code points, the rest are offset
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as Keysym,
+            c => 0x0100_0000 | c as Keysym,
+        }
+    }
+
+    pub fn wait_for_modifiers_released(timeout: Duration) -> Result<(), String> {
+        let keyboard = Keyboard::open()?;
+        let modifiers: Vec<Keycode> = keyboard
+            .keycodes()
+            .filter(|(_, keysyms)| {
+                keysyms
+                    .iter()
+                    .any(|keysym| MODIFIER_KEYSYMS.contains(keysym))
+            })
+            .map(|(keycode, _)| keycode)
+            .collect();
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let keys = keyboard
+                .conn
+                .query_keymap()
+                .map_err(|e| e.to_string())?
+                .reply()
+                .map_err(|e| format!("Failed to read keyboard state: {}", e))?
+                .keys;
+            let held = modifiers
+                .iter()
+                .any(|&keycode| keys[keycode as usize / 8] & (1 << (keycode % 8)) != 0);
+            if !held || Instant::now() >= deadline {
+                return Ok(());
+            }
+            thread::sleep(MODIFIER_POLL_INTERVAL);
+        }
+    }
+
+    pub fn send_paste_keystroke() -> Result<(), String> {
+        let keyboard = Keyboard::open()?;
+        let control = keyboard
+            .find(XK_CONTROL_L)
+            .map(|(keycode, _)| keycode)
+            .ok_or_else(|| "No Control key in the keyboard mapping".to_string())?;
+        let v = keyboard
+            .find(XK_V)
+            .map(|(keycode, _)| keycode)
+            .ok_or_else(|| "No V key in the keyboard mapping".to_string())?;
+
+        keyboard.tap(v, Some(control))?;
+        keyboard.sync()
+    }
+
+    // Characters missing from the layout are typed through a temporarily
+    // remapped spare keycode, which is cleared again afterwards
+    pub fn type_text(text: &str) -> Result<(), String> {
+        let keyboard = Keyboard::open()?;
+        let shift = keyboard.find(XK_SHIFT_L).map(|(keycode, _)| keycode);
+        let spare = keyboard.spare_keycode();
+        let mut remapped = false;
+
+        let result = text.chars().filter(|&c| c != '\r').try_for_each(|c| {
+            let keysym = keysym_for(c);
+            match keyboard.find(keysym) {
+                Some((keycode, needs_shift)) => {
+                    keyboard.tap(keycode, if needs_shift { shift } else { None })
+                }
+                None => match spare {
+                    Some(spare) => {
+                        keyboard.remap(spare, keysym)?;
+                        remapped = true;
+                        keyboard.tap(spare, None)?;
+                        keyboard.sync()
+                    }
+                    None => Err(format!("Can't type {:?} with this keyboard layout", c)),
+                },
+            }
+        });
+
+        if let (true, Some(spare)) = (remapped, spare) {
+            let _ = keyboard.remap(spare, 0);
+        }
+        result.and_then(|_| keyboard.sync())
+    }
+
+    pub fn move_cursor_left(count: usize) -> Result<(), String> {
+        let keyboard = Keyboard::open()?;
+        let left = keyboard
+            .find(XK_LEFT)
+            .map(|(keycode, _)| keycode)
+            .ok_or_else(|| "No Left key in the keyboard mapping".to_string())?;
+
+        for _ in 0..count {
+            keyboard.tap(left, None)?;
+        }
+        keyboard.sync()
+    }
+
+    pub fn delete_backward(count: usize) -> Result<(), String> {
+        let keyboard = Keyboard::open()?;
+        let backspace = keyboard
+            .find(XK_BACKSPACE)
+            .map(|(keycode, _)| keycode)
+            .ok_or_else(|| "No BackSpace key in the keyboard mapping".to_string())?;
+
+        for _ in 0..count {
+            keyboard.tap(backspace, None)?;
+        }
+        keyboard.sync()
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod native {
+    use std::time::Duration;
+
+    pub fn focus_window(_window: u64) -> Result<(), String> {
+        Err("Pasting into other apps is not supported on this platform".to_string())
+    }
+
+    pub fn send_paste_keystroke() -> Result<(), String> {
+        Err("Pasting into other apps is not supported on this platform".to_string())
+    }
+
+    pub fn wait_for_modifiers_released(_timeout: Duration) -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn type_text(_text: &str) -> Result<(), String> {
+        Err("Pasting into other apps is not supported on this platform".to_string())
+    }
+
+    pub fn move_cursor_left(_count: usize) -> Result<(), String> {
+        Err("Pasting into other apps is not supported on this platform".to_string())
+    }
+
+    pub fn delete_backward(_count: usize) -> Result<(), String> {
+        Err("Pasting into other apps is not supported on this platform".to_string())
+    }
+}
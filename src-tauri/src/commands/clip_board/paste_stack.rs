@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::thread;
+use tauri::{AppHandle, GlobalShortcutManager, Manager, State};
+
+use super::history_commands::restore_item;
+use super::history_store::ClipboardHistoryStore;
+use super::paste::paste_into_focused_window;
+use super::storage::ClipboardItem;
+
+pub const PASTE_STACK_EVENT: &str = "clipboard-paste-stack";
+
+// Only registered while the paste stack is active
+pub const PASTE_STACK_SHORTCUT: &str = "Alt+Shift+V";
+
+// Id of the tray menu entry that toggles the paste stack
+pub const PASTE_STACK_TRAY_ITEM: &str = "paste_stack";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PasteStackOrder {
+    // Paste in the order things were copied
+    #[default]
+    Fifo,
+    // Paste the last copy first
+    Lifo,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PasteStackStatus {
+    pub active: bool,
+    pub order: PasteStackOrder,
+    pub remaining: usize,
+    // History item the shortcut pastes next
+    pub next: Option<u64>,
+}
+
+#[derive(Default)]
+struct PasteStack {
+    active: bool,
+    order: PasteStackOrder,
+    items: VecDeque<u64>,
+    // Putting an item back on the clipboard makes the monitor report it again
+    restoring: Option<u64>,
+}
+
+impl PasteStack {
+    fn status(&self) -> PasteStackStatus {
+        let next = match self.order {
+            PasteStackOrder::Fifo => self.items.front(),
+            PasteStackOrder::Lifo => self.items.back(),
+        };
+
+        PasteStackStatus {
+            active: self.active,
+            order: self.order,
+            remaining: self.items.len(),
+            next: next.copied(),
+        }
+    }
+
+    fn pop(&mut self) -> Option<u64> {
+        match self.order {
+            PasteStackOrder::Fifo => self.items.pop_front(),
+            PasteStackOrder::Lifo => self.items.pop_back(),
+        }
+    }
+}
+
+// Queue of copied items that the paste stack shortcut pastes one by one
+#[derive(Default)]
+pub struct PasteStackState {
+    stack: Mutex<PasteStack>,
+}
+
+impl PasteStackState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, PasteStack>, String> {
+        self.stack
+            .lock()
+            .map_err(|_| "Paste stack lock poisoned".to_string())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.lock().map(|stack| stack.active).unwrap_or(false)
+    }
+
+    fn status(&self) -> Result<PasteStackStatus, String> {
+        Ok(self.lock()?.status())
+    }
+}
+
+// Called by the monitor for every copy, stored or already in the history
+pub fn push_to_paste_stack(app_handle: &AppHandle, item_id: u64) {
+    let paste_stack = match app_handle.try_state::<PasteStackState>() {
+        Some(paste_stack) => paste_stack,
+        None => return,
+    };
+
+    let status = match paste_stack.lock() {
+        Ok(mut stack) => {
+            if !stack.active || stack.restoring.take() == Some(item_id) {
+                return;
+            }
+            stack.items.push_back(item_id);
+            stack.status()
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    notify_status(app_handle, &status);
+}
+
+// Tell the webview and update the tray entry
+fn notify_status(app_handle: &AppHandle, status: &PasteStackStatus) {
+    if let Err(e) = app_handle.emit_all(PASTE_STACK_EVENT, status.clone()) {
+        eprintln!("Failed to emit paste stack status: {}", e);
+    }
+
+    let title = if status.active {
+        format!("Stop paste stack ({} left)", status.remaining)
+    } else {
+        "Start paste stack".to_string()
+    };
+    if let Err(e) = app_handle
+        .tray_handle()
+        .get_item(PASTE_STACK_TRAY_ITEM)
+        .set_title(title)
+    {
+        eprintln!("Failed to update paste stack tray item: {}", e);
+    }
+}
+
+fn set_active(
+    app_handle: &AppHandle,
+    paste_stack: &PasteStackState,
+    active: bool,
+    order: Option<PasteStackOrder>,
+) -> Result<PasteStackStatus, String> {
+    let was_active = paste_stack.lock()?.active;
+
+    // The stack only changes once the shortcut is in place, a failed
+    // registration must not leave it active without a way to paste
+    let mut shortcut_manager = app_handle.global_shortcut_manager();
+    if active && !was_active {
+        let shortcut_handle = app_handle.clone();
+        shortcut_manager
+            .register(PASTE_STACK_SHORTCUT, move || {
+                let paste_handle = shortcut_handle.clone();
+                // Waits for the hotkey to be released, off the event loop
+                thread::spawn(move || {
+                    if let Err(e) = paste_next(&paste_handle) {
+                        eprintln!("Failed to paste from paste stack: {}", e);
+                    }
+                });
+            })
+            .map_err(|e| format!("Failed to register {}: {}", PASTE_STACK_SHORTCUT, e))?;
+    } else if !active && was_active {
+        shortcut_manager
+            .unregister(PASTE_STACK_SHORTCUT)
+            .map_err(|e| format!("Failed to unregister {}: {}", PASTE_STACK_SHORTCUT, e))?;
+    }
+
+    let status = {
+        let mut stack = paste_stack.lock()?;
+        // Starting over always begins with an empty stack
+        if active != stack.active {
+            stack.items.clear();
+            stack.restoring = None;
+        }
+        stack.active = active;
+        if let Some(order) = order {
+            stack.order = order;
+        }
+        stack.status()
+    };
+
+    notify_status(app_handle, &status);
+    Ok(status)
+}
+
+// Tray menu entry
+pub fn toggle_paste_stack(app_handle: &AppHandle) {
+    let paste_stack = match app_handle.try_state::<PasteStackState>() {
+        Some(paste_stack) => paste_stack,
+        None => return,
+    };
+
+    let active = !paste_stack.is_active();
+    if let Err(e) = set_active(app_handle, &paste_stack, active, None) {
+        eprintln!("{}", e);
+    }
+}
+
+// Put the next item on the clipboard and paste it into the focused window.
+// Items deleted from the history in the meantime are skipped.
+fn paste_next(app_handle: &AppHandle) -> Result<Option<ClipboardItem>, String> {
+    let paste_stack = app_handle
+        .try_state::<PasteStackState>()
+        .ok_or_else(|| "Paste stack is not available".to_string())?;
+    let history_store = app_handle
+        .try_state::<ClipboardHistoryStore>()
+        .ok_or_else(|| "Clipboard history store is not available".to_string())?;
+
+    loop {
+        let item_id = {
+            let mut stack = paste_stack.lock()?;
+            match stack.pop() {
+                Some(item_id) => {
+                    stack.restoring = Some(item_id);
+                    item_id
+                }
+                None => return Ok(None),
+            }
+        };
+
+        match restore_item(app_handle, &history_store, item_id, false) {
+            Ok(item) => {
+                notify_status(app_handle, &paste_stack.status()?);
+                paste_into_focused_window()?;
+                return Ok(Some(item));
+            }
+            Err(e) => eprintln!("Skipping paste stack item {}: {}", item_id, e),
+        }
+    }
+}
+
+#[tauri::command]
+pub fn start_paste_stack(
+    order: Option<PasteStackOrder>,
+    app_handle: AppHandle,
+    paste_stack: State<'_, PasteStackState>,
+) -> Result<PasteStackStatus, String> {
+    set_active(&app_handle, &paste_stack, true, order)
+}
+
+#[tauri::command]
+pub fn stop_paste_stack(
+    app_handle: AppHandle,
+    paste_stack: State<'_, PasteStackState>,
+) -> Result<PasteStackStatus, String> {
+    set_active(&app_handle, &paste_stack, false, None)
+}
+
+#[tauri::command]
+pub fn get_paste_stack(
+    paste_stack: State<'_, PasteStackState>,
+) -> Result<PasteStackStatus, String> {
+    paste_stack.status()
+}
+
+// Same as the shortcut, returns the pasted item or None when the stack is empty
+#[tauri::command]
+pub async fn paste_next_from_stack(app_handle: AppHandle) -> Result<Option<ClipboardItem>, String> {
+    paste_next(&app_handle)
+}
@@ -0,0 +1,341 @@
+use rand::{rngs::OsRng, RngCore};
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+use tauri::Manager;
+
+use super::envelope::{self, key_id};
+use super::history_store::{now_millis, ClipboardHistoryStore};
+use super::image_handler::{
+    commit_rotated_images, discard_rotated_images, finish_image_rotation, has_rotating_images,
+    reencrypt_images,
+};
+use super::key_protector::{ensure_no_key_stored, get_encryption_key, replace_key, sealed_files};
+use super::storage::{
+    get_history_file_path, read_history_from, write_history_to, ClipboardHistoryFile,
+};
+use super::sync::{get_peers_path, read_sync_peers, write_sync_peers_to};
+use crate::commands::snippets::{get_snippets_path, get_snippets_rotation_path, SnippetState};
+
+pub const CLIPBOARD_RECOVERED_EVENT: &str = "clipboard-history-recovered";
+
+const BACKUP_DIR: &str = "clipboard_backups";
+const BACKUP_PREFIX: &str = "clipboard_history-";
+// Only the newest few backups are kept
+const MAX_BACKUPS: usize = 5;
+
+// Payload of the `clipboard-history-recovered` event
+#[derive(Serialize, Clone)]
+pub struct RecoveryNotice {
+    // Why the history file couldn't be read
+    pub reason: String,
+    // The key is missing, nothing was moved. The history stays unreadable until
+    // the key turns up or the user resets it with `reset_clipboard_key`.
+    pub key_unavailable: bool,
+    // Where the unreadable file was moved to
+    pub moved_to: Option<String>,
+    // Backup the history was restored from, None if it starts empty
+    pub restored_from: Option<String>,
+    pub restored_items: usize,
+}
+
+fn get_backup_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+
+    Ok(app_data_dir.join(BACKUP_DIR))
+}
+
+// Where a key rotation writes the re-encrypted history before swapping it in
+fn get_rotation_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(get_history_file_path(app_handle)?.with_extension("encrypted.rotating"))
+}
+
+fn get_peers_rotation_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(get_peers_path(app_handle)?.with_extension("encrypted.rotating"))
+}
+
+// Backups, newest first
+fn list_backups(app_handle: &tauri::AppHandle) -> Result<Vec<PathBuf>, String> {
+    let backup_dir = get_backup_dir(app_handle)?;
+    if !backup_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(&backup_dir)
+        .map_err(|e| format!("Failed to read backup directory: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().starts_with(BACKUP_PREFIX))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    // Names carry the creation time in milliseconds, so they sort chronologically
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+fn remove_backups(backups: impl Iterator<Item = PathBuf>) {
+    for backup in backups {
+        if let Err(e) = fs::remove_file(&backup) {
+            eprintln!("Failed to remove backup {}: {}", backup.display(), e);
+        }
+    }
+}
+
+// Copy the current history file into the backup directory before it is rewritten
+pub fn backup_history_file(app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let history_file = get_history_file_path(app_handle)?;
+    if !history_file.exists() {
+        return Ok(());
+    }
+
+    let backup_dir = get_backup_dir(app_handle)?;
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let backup_path = backup_dir.join(format!("{}{}.encrypted", BACKUP_PREFIX, now_millis()));
+    fs::copy(&history_file, &backup_path)
+        .map_err(|e| format!("Failed to copy clipboard history: {}", e))?;
+
+    remove_backups(list_backups(app_handle)?.into_iter().skip(MAX_BACKUPS));
+    Ok(())
+}
+
+// A rotation that was interrupted leaves its re-encrypted files next to the
+// real ones. If the new key made it into the protector the rotation is finished,
+// otherwise the half-done files are thrown away.
+pub fn finish_interrupted_rotation(app_handle: &tauri::AppHandle) {
+    let rotation_path = get_rotation_path(app_handle)
+        .ok()
+        .filter(|path| path.exists());
+    // Other files sealed with the history key, rotated alongside it
+    let sealed_rotations: Vec<(PathBuf, PathBuf)> = [
+        get_peers_rotation_path(app_handle)
+            .and_then(|rotating| Ok((rotating, get_peers_path(app_handle)?))),
+        get_snippets_rotation_path(app_handle)
+            .and_then(|rotating| Ok((rotating, get_snippets_path(app_handle)?))),
+    ]
+    .into_iter()
+    .filter_map(Result::ok)
+    .filter(|(rotating, _)| rotating.exists())
+    .collect();
+    if rotation_path.is_none() && sealed_rotations.is_empty() && !has_rotating_images(app_handle) {
+        return;
+    }
+
+    let key = match get_encryption_key(app_handle) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!("Can't check interrupted key rotation: {}", e);
+            return;
+        }
+    };
+
+    finish_image_rotation(app_handle, &key);
+
+    for (rotating, sealed) in sealed_rotations {
+        let opens =
+            fs::read_to_string(&rotating).is_ok_and(|data| envelope::open(&key, &data).is_ok());
+        let result = if opens {
+            fs::rename(&rotating, &sealed)
+                .map_err(|e| format!("Failed to replace {}: {}", sealed.display(), e))
+        } else {
+            fs::remove_file(&rotating)
+                .map_err(|e| format!("Failed to remove unfinished rotation: {}", e))
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+        }
+    }
+
+    let rotation_path = match rotation_path {
+        Some(path) => path,
+        None => return,
+    };
+
+    let result = match read_history_from(&rotation_path, &key) {
+        Ok(_) => {
+            println!("Finishing interrupted clipboard key rotation");
+            get_history_file_path(app_handle).and_then(|history_file| {
+                fs::rename(&rotation_path, history_file)
+                    .map_err(|e| format!("Failed to replace clipboard history: {}", e))
+            })
+        }
+        Err(_) => fs::remove_file(&rotation_path)
+            .map_err(|e| format!("Failed to remove unfinished rotation: {}", e)),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+    }
+}
+
+pub enum HistoryRecovery {
+    Restored(ClipboardHistoryFile, RecoveryNotice),
+    // The file is left alone, see `RecoveryNotice::key_unavailable`
+    KeyUnavailable(RecoveryNotice),
+    Failed,
+}
+
+fn emit_recovery_notice(app_handle: &tauri::AppHandle, notice: &RecoveryNotice) {
+    if let Err(e) = app_handle.emit_all(CLIPBOARD_RECOVERED_EVENT, notice.clone()) {
+        eprintln!("Failed to emit clipboard recovery: {}", e);
+    }
+}
+
+// The history file exists but can't be read with the current key. Restore the
+// newest backup that still decrypts, move the unreadable file aside and tell the
+// UI. If the key itself is unavailable the file is left alone and the UI is told
+// so it can offer a reset.
+pub fn recover_history(app_handle: &tauri::AppHandle, reason: &str) -> HistoryRecovery {
+    let key = match get_encryption_key(app_handle) {
+        Ok(key) => key,
+        Err(e) => {
+            eprintln!(
+                "Clipboard key unavailable, leaving history untouched: {}",
+                e
+            );
+            let notice = RecoveryNotice {
+                reason: e,
+                key_unavailable: true,
+                moved_to: None,
+                restored_from: None,
+                restored_items: 0,
+            };
+            emit_recovery_notice(app_handle, &notice);
+            return HistoryRecovery::KeyUnavailable(notice);
+        }
+    };
+
+    let history_file = match get_history_file_path(app_handle) {
+        Ok(history_file) => history_file,
+        Err(_) => return HistoryRecovery::Failed,
+    };
+
+    let mut restored = None;
+    for backup in list_backups(app_handle).unwrap_or_default() {
+        match read_history_from(&backup, &key) {
+            Ok(history) => {
+                restored = Some((history, backup));
+                break;
+            }
+            Err(e) => eprintln!("Backup {} is not readable either: {}", backup.display(), e),
+        }
+    }
+
+    let moved_to = history_file.with_extension(format!("encrypted.unreadable-{}", now_millis()));
+    if let Err(e) = fs::rename(&history_file, &moved_to) {
+        eprintln!("Failed to move unreadable clipboard history aside: {}", e);
+        return HistoryRecovery::Failed;
+    }
+
+    let (history, restored_from) = match restored {
+        Some((history, backup)) => (history, Some(backup.to_string_lossy().to_string())),
+        None => (ClipboardHistoryFile::default(), None),
+    };
+
+    let notice = RecoveryNotice {
+        reason: reason.to_string(),
+        key_unavailable: false,
+        moved_to: Some(moved_to.to_string_lossy().to_string()),
+        restored_from,
+        restored_items: history.items.len(),
+    };
+    eprintln!(
+        "Clipboard history could not be read ({}), moved it to {}",
+        notice.reason,
+        moved_to.display()
+    );
+    emit_recovery_notice(app_handle, &notice);
+
+    HistoryRecovery::Restored(history, notice)
+}
+
+// The key is gone for good: move everything sealed with it aside, let a new
+// key be generated and load the store. Items copied in the meantime are kept.
+pub fn reset_history_key(
+    app_handle: &tauri::AppHandle,
+    history_store: &ClipboardHistoryStore,
+) -> Result<(), String> {
+    // A key store that can't be reached might still hold the key
+    ensure_no_key_stored(app_handle)?;
+
+    let suffix = format!("encrypted.unreadable-{}", now_millis());
+    for sealed in sealed_files(app_handle)? {
+        let moved_to = sealed.with_extension(&suffix);
+        fs::rename(&sealed, &moved_to)
+            .map_err(|e| format!("Failed to move {} aside: {}", sealed.display(), e))?;
+        eprintln!("Moved {} to {}", sealed.display(), moved_to.display());
+    }
+
+    get_encryption_key(app_handle)?;
+    history_store.reload(app_handle)
+}
+
+// Re-encrypt the history with a fresh key and return the new key id. The new
+// file is written before the key is replaced, see `finish_interrupted_rotation`.
+pub fn rotate_history_key(
+    app_handle: &tauri::AppHandle,
+    history_store: &ClipboardHistoryStore,
+) -> Result<String, String> {
+    history_store.with_history_locked(|history| {
+        let history_file = get_history_file_path(app_handle)?;
+        let rotation_path = get_rotation_path(app_handle)?;
+        let old_backups = list_backups(app_handle)?;
+
+        let old_key = get_encryption_key(app_handle)?;
+        let mut new_key = [0u8; 32];
+        OsRng.fill_bytes(&mut new_key);
+        // Paired sync devices are sealed with the history key as well
+        let peers_path = get_peers_path(app_handle)?;
+        let peers_rotation_path = get_peers_rotation_path(app_handle)?;
+        let sync_peers = if peers_path.exists() {
+            Some(read_sync_peers(app_handle, &old_key)?)
+        } else {
+            None
+        };
+
+        let rotated_images = reencrypt_images(app_handle, &old_key, &new_key)?;
+
+        let commit = || {
+            write_history_to(&rotation_path, history, &new_key)
+                .and_then(|_| match &sync_peers {
+                    Some(peers) => write_sync_peers_to(&peers_rotation_path, peers, &new_key),
+                    None => Ok(()),
+                })
+                .and_then(|_| replace_key(app_handle, new_key))
+        };
+        // Snippets are re-sealed before the key is replaced and swapped in after
+        let replaced = match app_handle.try_state::<SnippetState>() {
+            Some(snippet_state) => snippet_state.rotate(app_handle, &old_key, &new_key, commit),
+            None => commit(),
+        };
+        if let Err(e) = replaced {
+            let _ = fs::remove_file(&rotation_path);
+            let _ = fs::remove_file(&peers_rotation_path);
+            discard_rotated_images(&rotated_images);
+            return Err(e);
+        }
+
+        fs::rename(&rotation_path, &history_file)
+            .map_err(|e| format!("Failed to replace clipboard history: {}", e))?;
+        commit_rotated_images(&rotated_images);
+        if sync_peers.is_some() {
+            fs::rename(&peers_rotation_path, &peers_path)
+                .map_err(|e| format!("Failed to replace paired sync devices: {}", e))?;
+        }
+
+        // Backups under the old key can't be read anymore
+        remove_backups(old_backups.into_iter());
+
+        println!("Rotated clipboard history key");
+        Ok(key_id(&new_key))
+    })
+}
@@ -0,0 +1,404 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::thread;
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Manager, State};
+
+use super::history_store::{now_millis, ClipboardHistoryStore};
+use super::image_handler::list_image_files;
+use super::sensitive::CLIPBOARD_EXPIRED_EVENT;
+use super::settings::{load_clipboard_settings, save_clipboard_settings};
+use super::storage::{ClipboardItem, ImageData};
+
+// How often age limits are applied and orphaned images collected
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// Images younger than this may belong to an item that is still being added
+const ORPHAN_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+// Limits for the clipboard history, pinned items are never removed by them.
+// Limits set to None are not enforced.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub max_items: usize,
+    // Items not copied for this many days are removed
+    pub max_age_days: Option<u64>,
+    // Combined size of all stored image files
+    pub max_image_bytes: Option<u64>,
+    // Larger content is not recorded at all
+    pub max_item_bytes: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_items: 100,
+            max_age_days: None,
+            max_image_bytes: Some(512 * 1024 * 1024),
+            max_item_bytes: Some(32 * 1024 * 1024),
+        }
+    }
+}
+
+// Sizes of image files by path, see `image_file_sizes`
+pub type ImageSizes = HashMap<String, u64>;
+
+impl RetentionPolicy {
+    pub fn allows_item_size(&self, item: &ClipboardItem, image_sizes: &ImageSizes) -> bool {
+        match self.max_item_bytes {
+            Some(max_item_bytes) => item_size(item, image_sizes) <= max_item_bytes,
+            None => true,
+        }
+    }
+
+    // Remove what is over the limits, oldest unpinned items first. Items are
+    // expected newest first, as the history keeps them.
+    pub fn apply(
+        &self,
+        items: &mut Vec<ClipboardItem>,
+        now: u64,
+        image_sizes: &ImageSizes,
+    ) -> Vec<ClipboardItem> {
+        let mut removed = Vec::new();
+
+        if let Some(max_age_days) = self.max_age_days {
+            let cutoff = now.saturating_sub(max_age_days.saturating_mul(DAY_MILLIS));
+            let (expired, kept) = std::mem::take(items)
+                .into_iter()
+                .partition(|item: &ClipboardItem| !item.pinned && item.last_copied < cutoff);
+            *items = kept;
+            removed.extend(expired);
+        }
+
+        let mut index = items.len();
+        while items.len() > self.max_items && index > 0 {
+            index -= 1;
+            if !items[index].pinned {
+                removed.push(items.remove(index));
+            }
+        }
+
+        if let Some(max_image_bytes) = self.max_image_bytes {
+            let mut image_bytes = total_image_bytes(items, image_sizes);
+            let mut index = items.len();
+            while image_bytes > max_image_bytes && index > 0 {
+                index -= 1;
+                if !items[index].pinned && items[index].image_data.is_some() {
+                    let item = items.remove(index);
+                    image_bytes = total_image_bytes(items, image_sizes);
+                    removed.push(item);
+                }
+            }
+        }
+
+        removed
+    }
+}
+
+// Stat the image files up front, the history is locked while the limits are
+// applied and readers shouldn't wait on the disk. Missing files count as empty.
+pub fn image_file_sizes<'a>(images: impl Iterator<Item = &'a ImageData>) -> ImageSizes {
+    images
+        .flat_map(|image| image.file_paths())
+        .filter_map(|path| {
+            fs::metadata(path)
+                .ok()
+                .map(|metadata| (path.clone(), metadata.len()))
+        })
+        .collect()
+}
+
+// Bytes of text, rich formats and the stored image file of an item
+pub fn item_size(item: &ClipboardItem, image_sizes: &ImageSizes) -> u64 {
+    let text_bytes = item.text.as_ref().map_or(0, |text| text.len());
+    let format_bytes = item.formats.html.as_ref().map_or(0, |html| html.len())
+        + item.formats.rtf.as_ref().map_or(0, |rtf| rtf.len());
+
+    (text_bytes + format_bytes) as u64 + image_file_size(item, image_sizes)
+}
+
+// Original and thumbnail together. Items added after the sizes were read
+// count as empty until the next run.
+fn image_file_size(item: &ClipboardItem, image_sizes: &ImageSizes) -> u64 {
+    item.image_data
+        .iter()
+        .flat_map(|image| image.file_paths())
+        .filter_map(|path| image_sizes.get(path))
+        .sum()
+}
+
+// Items may share an image file, each file is only counted once
+fn total_image_bytes(items: &[ClipboardItem], image_sizes: &ImageSizes) -> u64 {
+    let mut seen = HashSet::new();
+    items
+        .iter()
+        .filter(|item| {
+            item.image_data
+                .as_ref()
+                .is_some_and(|image| seen.insert(image.file_path.clone()))
+        })
+        .map(|item| image_file_size(item, image_sizes))
+        .sum()
+}
+
+// Delete image files that no history item points at. Returns how many were removed.
+pub fn remove_orphaned_images(
+    app_handle: &AppHandle,
+    referenced: &HashSet<String>,
+) -> Result<usize, String> {
+    let mut removed = 0;
+
+    for path in list_image_files(app_handle, "")? {
+        let path_string = path.to_string_lossy().to_string();
+        // Key rotation cleans up after itself
+        if referenced.contains(&path_string) || path_string.ends_with(".rotating") {
+            continue;
+        }
+
+        let recently_written = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .map(|age| age < ORPHAN_GRACE_PERIOD)
+            .unwrap_or(true);
+        if recently_written {
+            continue;
+        }
+
+        match fs::remove_file(&path) {
+            Ok(_) => removed += 1,
+            Err(e) => eprintln!("Failed to delete orphaned image {}: {}", path_string, e),
+        }
+    }
+
+    Ok(removed)
+}
+
+// Apply age limits and collect orphaned images in the background
+pub fn start_history_maintenance(app_handle: AppHandle) {
+    let spawned = thread::Builder::new()
+        .name("clipboard-maintenance".to_string())
+        .spawn(move || loop {
+            if let Some(history_store) = app_handle.try_state::<ClipboardHistoryStore>() {
+                run_maintenance(&app_handle, &history_store);
+            }
+
+            thread::sleep(MAINTENANCE_INTERVAL);
+        });
+
+    if let Err(e) = spawned {
+        eprintln!("Failed to start clipboard maintenance: {}", e);
+    }
+}
+
+fn run_maintenance(app_handle: &AppHandle, history_store: &ClipboardHistoryStore) {
+    match history_store.enforce_retention(now_millis()) {
+        Ok(removed) => emit_removed(app_handle, &removed),
+        Err(e) => eprintln!("Failed to apply clipboard retention: {}", e),
+    }
+
+    // Without a loaded history every image would look orphaned
+    let referenced = match history_store.referenced_image_paths() {
+        Ok(referenced) => referenced,
+        Err(e) => {
+            eprintln!("Skipping clipboard image cleanup: {}", e);
+            return;
+        }
+    };
+    match remove_orphaned_images(app_handle, &referenced) {
+        Ok(0) => {}
+        Ok(removed) => println!("Removed {} orphaned clipboard images", removed),
+        Err(e) => eprintln!("Failed to remove orphaned clipboard images: {}", e),
+    }
+}
+
+// Removed items are reported like expired ones so the webview drops them
+pub fn emit_removed(app_handle: &AppHandle, removed: &[ClipboardItem]) {
+    if removed.is_empty() {
+        return;
+    }
+
+    let ids: Vec<u64> = removed.iter().map(|item| item.id).collect();
+    if let Err(e) = app_handle.emit_all(CLIPBOARD_EXPIRED_EVENT, ids) {
+        eprintln!("Failed to emit removed clipboard items: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_clipboard_retention(app_handle: AppHandle) -> RetentionPolicy {
+    load_clipboard_settings(&app_handle).retention
+}
+
+// Save new limits and apply them right away
+#[tauri::command]
+pub fn set_clipboard_retention(
+    policy: RetentionPolicy,
+    app_handle: AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<RetentionPolicy, String> {
+    let mut settings = load_clipboard_settings(&app_handle);
+    settings.retention = policy;
+    save_clipboard_settings(&app_handle, &settings)?;
+
+    history_store.set_retention(settings.retention.clone());
+    let removed = history_store.enforce_retention(now_millis())?;
+    emit_removed(&app_handle, &removed);
+
+    Ok(settings.retention)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(max_items: usize) -> RetentionPolicy {
+        RetentionPolicy {
+            max_items,
+            max_age_days: None,
+            max_image_bytes: None,
+            max_item_bytes: None,
+        }
+    }
+
+    fn text(id: u64, pinned: bool) -> ClipboardItem {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "type": "text",
+            "text": "x".repeat(10),
+            "imageData": null,
+            "timestamp": id,
+            "last_copied": id,
+            "pinned": pinned,
+        }))
+        .unwrap()
+    }
+
+    fn image(id: u64, file: &str, pinned: bool) -> ClipboardItem {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "type": "image",
+            "text": null,
+            "imageData": {
+                "width": 1,
+                "height": 1,
+                "hash": file,
+                "filePath": file,
+                "thumbnailPath": format!("{}.thumb", file),
+            },
+            "timestamp": id,
+            "last_copied": id,
+            "pinned": pinned,
+        }))
+        .unwrap()
+    }
+
+    fn sizes(files: &[(&str, u64)]) -> ImageSizes {
+        files
+            .iter()
+            .flat_map(|&(file, size)| [(file.to_string(), size), (format!("{}.thumb", file), 1)])
+            .collect()
+    }
+
+    fn ids(items: &[ClipboardItem]) -> Vec<u64> {
+        items.iter().map(|item| item.id).collect()
+    }
+
+    #[test]
+    fn oldest_unpinned_items_go_first_when_over_max_items() {
+        let mut items = vec![
+            text(5, false),
+            text(4, false),
+            text(3, true),
+            text(2, false),
+            text(1, true),
+        ];
+        let removed = policy(2).apply(&mut items, 0, &ImageSizes::new());
+        assert_eq!(ids(&items), [3, 1]);
+        assert_eq!(ids(&removed), [2, 4, 5]);
+    }
+
+    #[test]
+    fn pinned_items_are_kept_over_max_items() {
+        let mut items = vec![text(3, true), text(2, true), text(1, true)];
+        let removed = policy(1).apply(&mut items, 0, &ImageSizes::new());
+        assert_eq!(ids(&items), [3, 2, 1]);
+        assert!(removed.is_empty());
+    }
+
+    #[test]
+    fn items_not_copied_within_max_age_are_removed_unless_pinned() {
+        let now = 10 * DAY_MILLIS;
+        let mut items = vec![
+            text(9 * DAY_MILLIS, false),
+            text(2 * DAY_MILLIS, true),
+            text(DAY_MILLIS, false),
+        ];
+        let removed = RetentionPolicy {
+            max_age_days: Some(7),
+            ..policy(100)
+        }
+        .apply(&mut items, now, &ImageSizes::new());
+        assert_eq!(ids(&items), [9 * DAY_MILLIS, 2 * DAY_MILLIS]);
+        assert_eq!(ids(&removed), [DAY_MILLIS]);
+    }
+
+    #[test]
+    fn shared_image_files_are_counted_once() {
+        let image_sizes = sizes(&[("a.png", 100), ("b.png", 50)]);
+        let items = vec![
+            image(3, "a.png", false),
+            image(2, "a.png", false),
+            image(1, "b.png", false),
+            text(0, false),
+        ];
+        // 101 for a.png and its thumbnail, 51 for b.png
+        assert_eq!(total_image_bytes(&items, &image_sizes), 152);
+    }
+
+    #[test]
+    fn oldest_unpinned_images_go_first_when_over_max_image_bytes() {
+        let image_sizes = sizes(&[("a.png", 100), ("b.png", 100), ("c.png", 100)]);
+        let limit = |max_image_bytes| RetentionPolicy {
+            max_image_bytes: Some(max_image_bytes),
+            ..policy(100)
+        };
+
+        let mut items = vec![
+            image(4, "a.png", false),
+            image(3, "a.png", false),
+            image(2, "b.png", true),
+            image(1, "c.png", false),
+        ];
+        let removed = limit(202).apply(&mut items, 0, &image_sizes);
+        assert_eq!(ids(&items), [4, 3, 2]);
+        assert_eq!(ids(&removed), [1]);
+
+        // Removing 3 frees nothing while 4 still uses a.png
+        let removed = limit(101).apply(&mut items, 0, &image_sizes);
+        assert_eq!(ids(&items), [2]);
+        assert_eq!(ids(&removed), [3, 4]);
+    }
+
+    #[test]
+    fn item_size_counts_text_formats_and_image_files() {
+        let image_sizes = sizes(&[("a.png", 100)]);
+        let mut item = text(1, false);
+        item.formats.html = Some("<b>x</b>".to_string());
+        assert_eq!(item_size(&item, &image_sizes), 18);
+        assert_eq!(item_size(&image(2, "a.png", false), &image_sizes), 101);
+        // Files that weren't found count as empty
+        assert_eq!(item_size(&image(3, "gone.png", false), &image_sizes), 0);
+
+        let limited = RetentionPolicy {
+            max_item_bytes: Some(18),
+            ..policy(100)
+        };
+        assert!(limited.allows_item_size(&item, &image_sizes));
+        assert!(!limited.allows_item_size(&image(2, "a.png", false), &image_sizes));
+        assert!(policy(100).allows_item_size(&image(2, "a.png", false), &image_sizes));
+    }
+}
@@ -0,0 +1,450 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+
+use super::storage::ClipboardItem;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+
+// Subsequence matching is quadratic, so only the start of long texts is searched that way
+const MAX_FUZZY_CHARS: usize = 4096;
+
+// Scores of the different kinds of matches, a better kind always wins
+const EXACT_MATCH_SCORE: u32 = 3000;
+const ALL_WORDS_SCORE: u32 = 2000;
+const FUZZY_SCORE: u32 = 1000;
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardSort {
+    // Best matches first, most recent first without a query
+    #[default]
+    Relevance,
+    // Most recently copied first
+    Recent,
+    // Most often copied first
+    CopyCount,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ClipboardSearchRequest {
+    pub query: String,
+    // Content types to include ("text", "image", "files"), all when empty
+    pub types: Vec<String>,
+    // Creation time range in milliseconds since the epoch, both ends inclusive
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub pinned: Option<bool>,
+    // See `ClipboardSource::matches`
+    pub source_app: Option<String>,
+    pub sort: ClipboardSort,
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+// Matched part of an item's text, in UTF-16 code units so the webview can
+// slice the string directly
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize)]
+pub struct ClipboardSearchHit {
+    pub item: ClipboardItem,
+    pub score: u32,
+    pub highlights: Vec<Highlight>,
+}
+
+#[derive(Serialize)]
+pub struct ClipboardSearchResults {
+    pub hits: Vec<ClipboardSearchHit>,
+    // Number of matching items across all pages
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl ClipboardSearchRequest {
+    fn passes_filters(&self, item: &ClipboardItem) -> bool {
+        let from_source = |source_app: &String| {
+            item.source
+                .as_ref()
+                .is_some_and(|source| source.matches(source_app))
+        };
+
+        (self.types.is_empty() || self.types.contains(&item.content_type))
+            && self.from.is_none_or(|from| item.timestamp >= from)
+            && self.to.is_none_or(|to| item.timestamp <= to)
+            && self.pinned.is_none_or(|pinned| item.pinned == pinned)
+            && self.source_app.as_ref().is_none_or(from_source)
+    }
+}
+
+// Run a search over the items, which are expected newest first
+pub fn search_items(
+    items: &[ClipboardItem],
+    request: &ClipboardSearchRequest,
+) -> ClipboardSearchResults {
+    let query = request.query.trim();
+    let limit = request.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+
+    let mut hits: Vec<ClipboardSearchHit> = items
+        .iter()
+        .filter(|item| request.passes_filters(item))
+        .filter_map(|item| {
+            if query.is_empty() {
+                return Some(ClipboardSearchHit {
+                    item: item.clone(),
+                    score: 0,
+                    highlights: Vec::new(),
+                });
+            }
+
+            let (score, highlights) = match_text(item.text.as_deref()?, query)?;
+            Some(ClipboardSearchHit {
+                item: item.clone(),
+                score,
+                highlights,
+            })
+        })
+        .collect();
+
+    // Stable sorts, so ties keep the history order
+    match request.sort {
+        ClipboardSort::Relevance if !query.is_empty() => hits.sort_by_key(|hit| Reverse(hit.score)),
+        ClipboardSort::Relevance => {}
+        ClipboardSort::Recent => hits.sort_by_key(|hit| Reverse(hit.item.last_copied)),
+        ClipboardSort::CopyCount => hits.sort_by_key(|hit| Reverse(hit.item.copy_count)),
+    }
+
+    let total = hits.len();
+    let hits = hits.into_iter().skip(request.offset).take(limit).collect();
+
+    ClipboardSearchResults {
+        hits,
+        total,
+        offset: request.offset,
+        limit,
+    }
+}
+
+// Score how well `query` matches `text`. Tries the whole query as a substring,
+// then every word of it, then its characters in order.
+pub fn match_text(text: &str, query: &str) -> Option<(u32, Vec<Highlight>)> {
+    let text_chars: Vec<char> = text.chars().map(lowercase_char).collect();
+    let query_chars: Vec<char> = query.chars().map(lowercase_char).collect();
+
+    let (score, ranges) = if let Some(start) = find_chars(&text_chars, &query_chars) {
+        let bonus = position_bonus(&text_chars, start);
+        let ranges = vec![(start, start + query_chars.len())];
+        (EXACT_MATCH_SCORE + bonus, ranges)
+    } else if let Some(ranges) = match_words(&text_chars, query) {
+        let bonus = ranges
+            .iter()
+            .map(|&(start, _)| position_bonus(&text_chars, start))
+            .min()
+            .unwrap_or(0);
+        (ALL_WORDS_SCORE + bonus, ranges)
+    } else {
+        let fuzzy_chars = &text_chars[..text_chars.len().min(MAX_FUZZY_CHARS)];
+        let needle: Vec<char> = query_chars
+            .into_iter()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        let (bonus, ranges) = match_subsequence(fuzzy_chars, &needle)?;
+        (FUZZY_SCORE + bonus, ranges)
+    };
+
+    Some((score, to_utf16_highlights(text, ranges)))
+}
+
+// Lowercase without changing the number of characters, so indices stay aligned
+fn lowercase_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn find_chars(text: &[char], needle: &[char]) -> Option<usize> {
+    if needle.is_empty() || text.len() < needle.len() {
+        return None;
+    }
+
+    (0..=text.len() - needle.len()).find(|&start| text[start..start + needle.len()] == *needle)
+}
+
+fn is_word_start(text: &[char], index: usize) -> bool {
+    index == 0 || !text[index - 1].is_alphanumeric()
+}
+
+// Matches at the very start or at a word start rank higher, earlier ones too
+fn position_bonus(text: &[char], start: usize) -> u32 {
+    let boundary = if start == 0 {
+        300
+    } else if is_word_start(text, start) {
+        200
+    } else {
+        0
+    };
+    boundary + 100u32.saturating_sub(start as u32)
+}
+
+// Every whitespace separated word of the query occurs somewhere in the text
+fn match_words(text: &[char], query: &str) -> Option<Vec<(usize, usize)>> {
+    let words: Vec<Vec<char>> = query
+        .split_whitespace()
+        .map(|word| word.chars().map(lowercase_char).collect())
+        .collect();
+    if words.len() < 2 {
+        return None;
+    }
+
+    let mut ranges = words
+        .iter()
+        .map(|word| find_chars(text, word).map(|start| (start, start + word.len())))
+        .collect::<Option<Vec<_>>>()?;
+    ranges.sort_unstable();
+    Some(merge_ranges(ranges))
+}
+
+// All characters of `needle` in order. Every start position is tried and the
+// best scoring one wins, with bonuses for consecutive characters and word starts.
+fn match_subsequence(text: &[char], needle: &[char]) -> Option<(u32, Vec<(usize, usize)>)> {
+    let first = *needle.first()?;
+    let mut best: Option<(u32, Vec<(usize, usize)>)> = None;
+
+    for start in (0..text.len()).filter(|&index| text[index] == first) {
+        let mut positions = Vec::with_capacity(needle.len());
+        let mut index = start;
+        for &c in needle {
+            match (index..text.len()).find(|&candidate| text[candidate] == c) {
+                Some(found) => {
+                    positions.push(found);
+                    index = found + 1;
+                }
+                None => break,
+            }
+        }
+        if positions.len() < needle.len() {
+            // Later starts can't find what this one couldn't
+            break;
+        }
+
+        let span = positions[positions.len() - 1] - positions[0] + 1;
+        // Characters scattered all over the text are not a meaningful match
+        if span > needle.len() * 4 + 8 {
+            continue;
+        }
+
+        let consecutive = positions
+            .windows(2)
+            .filter(|pair| pair[1] == pair[0] + 1)
+            .count() as u32;
+        let word_starts = positions
+            .iter()
+            .filter(|&&position| is_word_start(text, position))
+            .count() as u32;
+        let gaps = (span - needle.len()) as u32;
+        let score = (consecutive * 20 + word_starts * 30).saturating_sub(gaps * 5);
+
+        if let Some((best_score, _)) = &best {
+            if score <= *best_score {
+                continue;
+            }
+        }
+        let ranges = positions
+            .iter()
+            .map(|&position| (position, position + 1))
+            .collect();
+        best = Some((score.min(FUZZY_SCORE - 1), merge_ranges(ranges)));
+    }
+
+    best
+}
+
+// Join overlapping or touching ranges, expects them sorted by start
+fn merge_ranges(ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+// Character indices to UTF-16 offsets
+fn to_utf16_highlights(text: &str, ranges: Vec<(usize, usize)>) -> Vec<Highlight> {
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    let mut offset = 0;
+    for c in text.chars() {
+        offsets.push(offset);
+        offset += c.len_utf16();
+    }
+    offsets.push(offset);
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| Highlight {
+            start: offsets[start],
+            end: offsets[end],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: u64, text: &str, copy_count: u64, pinned: bool) -> ClipboardItem {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "type": "text",
+            "text": text,
+            "imageData": null,
+            "timestamp": id,
+            "last_copied": 100 - id,
+            "copy_count": copy_count,
+            "pinned": pinned,
+        }))
+        .unwrap()
+    }
+
+    fn highlighted(text: &str, query: &str) -> Vec<String> {
+        let utf16: Vec<u16> = text.encode_utf16().collect();
+        match_text(text, query)
+            .unwrap()
+            .1
+            .iter()
+            .map(|highlight| String::from_utf16(&utf16[highlight.start..highlight.end]).unwrap())
+            .collect()
+    }
+
+    fn ids(results: &ClipboardSearchResults) -> Vec<u64> {
+        results.hits.iter().map(|hit| hit.item.id).collect()
+    }
+
+    #[test]
+    fn substrings_beat_all_words_which_beat_fuzzy_matches() {
+        let exact = match_text("the quick brown fox", "brown fox").unwrap().0;
+        let words = match_text("the fox is quick and brown", "brown fox")
+            .unwrap()
+            .0;
+        let fuzzy = match_text("bring rowan fox", "brown").unwrap().0;
+        assert!(
+            exact >= EXACT_MATCH_SCORE && exact > words,
+            "{} {}",
+            exact,
+            words
+        );
+        assert!(
+            words >= ALL_WORDS_SCORE && words > fuzzy,
+            "{} {}",
+            words,
+            fuzzy
+        );
+        assert!((FUZZY_SCORE..ALL_WORDS_SCORE).contains(&fuzzy), "{}", fuzzy);
+    }
+
+    #[test]
+    fn earlier_matches_at_word_starts_rank_higher() {
+        let score = |text| match_text(text, "log").unwrap().0;
+        assert!(score("log file") > score("a log file"));
+        assert!(score("a log file") > score("catalog file"));
+        assert!(score("catalog") > score(&format!("{}catalog", "x".repeat(200))));
+    }
+
+    #[test]
+    fn highlights_cover_the_matched_text() {
+        assert_eq!(highlighted("Hello World", "WORLD"), ["World"]);
+        assert_eq!(
+            highlighted("alpha beta gamma", "gamma alpha"),
+            ["alpha", "gamma"]
+        );
+        assert_eq!(highlighted("alphabet", "alpha alphab"), ["alphab"]);
+        assert_eq!(highlighted("clipboard history", "cbh"), ["c", "b", "h"]);
+        assert_eq!(highlighted("get_file_path", "getfile"), ["get", "file"]);
+    }
+
+    #[test]
+    fn highlights_are_utf16_offsets() {
+        let text = "😀 naïve café";
+        let (_, highlights) = match_text(text, "café").unwrap();
+        assert_eq!(highlights, [Highlight { start: 9, end: 13 }]);
+        assert_eq!(highlighted(text, "naïve"), ["naïve"]);
+    }
+
+    #[test]
+    fn scattered_or_missing_characters_do_not_match() {
+        assert!(match_text("hello", "xyz").is_none());
+        assert!(match_text("hello", "helloo").is_none());
+        assert!(match_text(&format!("a{}b{}c", " ".repeat(20), " ".repeat(20)), "abc").is_none());
+        assert!(match_text("hello", "   ").is_none());
+    }
+
+    #[test]
+    fn results_are_ranked_then_paged() {
+        let items = [
+            item(1, "fuzzy: b r o w n", 1, false),
+            item(2, "brown fox", 1, false),
+            item(3, "something else", 1, false),
+            item(4, "fox, brown", 1, false),
+        ];
+        let request = |offset, limit| ClipboardSearchRequest {
+            query: "brown fox".to_string(),
+            offset,
+            limit: Some(limit),
+            ..Default::default()
+        };
+
+        let results = search_items(&items, &request(0, 10));
+        assert_eq!(ids(&results), [2, 4]);
+        assert_eq!(results.total, 2);
+
+        let results = search_items(&items, &request(1, 1));
+        assert_eq!(ids(&results), [4]);
+        assert_eq!(results.total, 2);
+    }
+
+    #[test]
+    fn filters_and_sort_orders_apply_without_a_query() {
+        let items = [
+            item(1, "a", 5, true),
+            item(2, "b", 1, false),
+            item(3, "c", 9, true),
+        ];
+
+        let results = search_items(&items, &ClipboardSearchRequest::default());
+        assert_eq!(ids(&results), [1, 2, 3]);
+
+        let results = search_items(
+            &items,
+            &ClipboardSearchRequest {
+                pinned: Some(true),
+                sort: ClipboardSort::CopyCount,
+                ..Default::default()
+            },
+        );
+        assert_eq!(ids(&results), [3, 1]);
+
+        let results = search_items(
+            &items,
+            &ClipboardSearchRequest {
+                from: Some(2),
+                sort: ClipboardSort::Recent,
+                ..Default::default()
+            },
+        );
+        assert_eq!(ids(&results), [2, 3]);
+
+        let results = search_items(
+            &items,
+            &ClipboardSearchRequest {
+                types: vec!["image".to_string()],
+                ..Default::default()
+            },
+        );
+        assert_eq!(results.total, 0);
+    }
+}
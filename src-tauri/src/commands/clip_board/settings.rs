@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+use super::key_protector::KeyProtectorKind;
+use super::retention::RetentionPolicy;
+use super::sensitive::SensitiveRules;
+use super::source_app::ClipboardSource;
+use super::sync::SyncSettings;
+use crate::persistence::files::write_atomic;
+
+// User configurable clipboard behaviour, stored as plain JSON next to the history
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ClipboardSettings {
+    // Where the history encryption key is kept
+    pub key_protector: KeyProtectorKind,
+    // What happens to passwords, tokens and other secrets
+    pub sensitive: SensitiveRules,
+    // Apps whose copies are never recorded, see `ClipboardSource::matches`
+    pub excluded_apps: Vec<String>,
+    // Limits on how much history is kept
+    pub retention: RetentionPolicy,
+    // Apps that get text typed in instead of pasted, e.g. for fields that block pasting
+    pub typed_paste_apps: Vec<String>,
+    // LAN sync with paired devices, off by default
+    pub sync: SyncSettings,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self {
+            key_protector: KeyProtectorKind::platform_default(),
+            sensitive: SensitiveRules::default(),
+            excluded_apps: ["KeePass", "KeePassXC", "1Password", "Bitwarden"]
+                .iter()
+                .map(|app| app.to_string())
+                .collect(),
+            retention: RetentionPolicy::default(),
+            typed_paste_apps: Vec::new(),
+            sync: SyncSettings::default(),
+        }
+    }
+}
+
+fn get_settings_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+
+    Ok(app_data_dir.join("clipboard_settings.json"))
+}
+
+// Missing or unreadable settings fall back to the defaults
+pub fn load_clipboard_settings(app_handle: &tauri::AppHandle) -> ClipboardSettings {
+    let settings_path = match get_settings_path(app_handle) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ClipboardSettings::default();
+        }
+    };
+
+    if !settings_path.exists() {
+        return ClipboardSettings::default();
+    }
+
+    fs::read_to_string(&settings_path)
+        .map_err(|e| format!("Failed to read clipboard settings: {}", e))
+        .and_then(|json| {
+            serde_json::from_str(&json)
+                .map_err(|e| format!("Failed to parse clipboard settings: {}", e))
+        })
+        .unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            ClipboardSettings::default()
+        })
+}
+
+impl ClipboardSettings {
+    pub fn is_excluded(&self, source: &ClipboardSource) -> bool {
+        self.excluded_apps.iter().any(|app| source.matches(app))
+    }
+
+    pub fn types_paste(&self, source: &ClipboardSource) -> bool {
+        self.typed_paste_apps.iter().any(|app| source.matches(app))
+    }
+}
+
+pub fn save_clipboard_settings(
+    app_handle: &tauri::AppHandle,
+    settings: &ClipboardSettings,
+) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize clipboard settings: {}", e))?;
+
+    write_atomic(&get_settings_path(app_handle)?, json.as_bytes())
+}
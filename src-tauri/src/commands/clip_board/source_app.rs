@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use super::settings::{load_clipboard_settings, save_clipboard_settings};
+use crate::commands::fetch_app::models::AppIndexState;
+
+// The application that was in the foreground when content was copied
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClipboardSource {
+    // Id in the app index, if the application is indexed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    pub name: String,
+    // Full path of the executable
+    pub path: String,
+}
+
+impl ClipboardSource {
+    // Exclusions match the app id, the executable name with or without its
+    // extension, the indexed app name or the full path, ignoring case
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern = pattern.trim().to_lowercase();
+        if pattern.is_empty() {
+            return false;
+        }
+
+        let executable = Path::new(&self.path);
+        let candidates = [
+            self.app_id.clone(),
+            Some(self.name.clone()),
+            Some(self.path.clone()),
+            executable
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string()),
+            executable
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string()),
+        ];
+
+        candidates
+            .iter()
+            .flatten()
+            .any(|candidate| candidate.to_lowercase() == pattern)
+    }
+}
+
+// Look up the foreground process and map it to an indexed app where possible
+pub fn current_source_app(app_handle: &AppHandle) -> Option<ClipboardSource> {
+    source_for_window(app_handle, foreground_window()?)
+}
+
+// The application owning `window`, a handle returned by `foreground_window`
+pub fn source_for_window(app_handle: &AppHandle, window: u64) -> Option<ClipboardSource> {
+    let path = window_process_path(window)?;
+    let app = app_handle
+        .try_state::<AppIndexState>()
+        .and_then(|app_index_state| {
+            let index = app_index_state.index.read().ok()?;
+            index
+                .apps
+                .values()
+                .find(|app| app.path.eq_ignore_ascii_case(&path))
+                .cloned()
+        });
+
+    Some(match app {
+        Some(app) => ClipboardSource {
+            app_id: Some(app.id),
+            name: app.name,
+            path,
+        },
+        None => ClipboardSource {
+            app_id: None,
+            name: Path::new(&path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| path.clone()),
+            path,
+        },
+    })
+}
+
+// Handle of the foreground window, an HWND on Windows and an X11 window id on Linux
+#[cfg(target_os = "windows")]
+pub fn foreground_window() -> Option<u64> {
+    let hwnd = unsafe { winapi::um::winuser::GetForegroundWindow() };
+    if hwnd.is_null() {
+        None
+    } else {
+        Some(hwnd as u64)
+    }
+}
+
+// Executable of the process that owns the window
+#[cfg(target_os = "windows")]
+fn window_process_path(window: u64) -> Option<String> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use winapi::shared::minwindef::{DWORD, FALSE, MAX_PATH};
+    use winapi::shared::windef::HWND;
+    use winapi::um::handleapi::CloseHandle;
+    use winapi::um::processthreadsapi::OpenProcess;
+    use winapi::um::winbase::QueryFullProcessImageNameW;
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+    use winapi::um::winuser::GetWindowThreadProcessId;
+
+    unsafe {
+        let mut process_id: DWORD = 0;
+        GetWindowThreadProcessId(window as HWND, &mut process_id);
+        if process_id == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, process_id);
+        if process.is_null() {
+            return None;
+        }
+
+        let mut buffer = [0u16; MAX_PATH * 4];
+        let mut length = buffer.len() as DWORD;
+        let queried = QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut length);
+        CloseHandle(process);
+
+        if queried == 0 {
+            return None;
+        }
+        Some(
+            OsString::from_wide(&buffer[..length as usize])
+                .to_string_lossy()
+                .to_string(),
+        )
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn intern_atom(conn: &impl x11rb::connection::Connection, name: &[u8]) -> Option<u32> {
+    use x11rb::protocol::xproto::ConnectionExt as _;
+
+    Some(conn.intern_atom(false, name).ok()?.reply().ok()?.atom)
+}
+
+#[cfg(target_os = "linux")]
+pub fn foreground_window() -> Option<u64> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _};
+
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots[screen_num].root;
+    let active_window_atom = intern_atom(&conn, b"_NET_ACTIVE_WINDOW")?;
+
+    let window = conn
+        .get_property(false, root, active_window_atom, AtomEnum::WINDOW, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?
+        .value32()?
+        .next()?;
+    if window == x11rb::NONE {
+        return None;
+    }
+    Some(window as u64)
+}
+
+// Follow the window's _NET_WM_PID and resolve the executable through /proc
+#[cfg(target_os = "linux")]
+fn window_process_path(window: u64) -> Option<String> {
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt as _};
+
+    let (conn, _) = x11rb::connect(None).ok()?;
+    let pid_atom = intern_atom(&conn, b"_NET_WM_PID")?;
+
+    let pid = conn
+        .get_property(false, window as u32, pid_atom, AtomEnum::CARDINAL, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?
+        .value32()?
+        .next()?;
+
+    std::fs::read_link(format!("/proc/{}/exe", pid))
+        .ok()
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+pub fn foreground_window() -> Option<u64> {
+    None
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn window_process_path(_window: u64) -> Option<String> {
+    None
+}
+
+#[tauri::command]
+pub fn get_clipboard_excluded_apps(app_handle: AppHandle) -> Vec<String> {
+    load_clipboard_settings(&app_handle).excluded_apps
+}
+
+// Content copied from these apps is never recorded
+#[tauri::command]
+pub fn set_clipboard_excluded_apps(
+    apps: Vec<String>,
+    app_handle: AppHandle,
+) -> Result<Vec<String>, String> {
+    let mut settings = load_clipboard_settings(&app_handle);
+    settings.excluded_apps = apps
+        .into_iter()
+        .map(|app| app.trim().to_string())
+        .filter(|app| !app.is_empty())
+        .collect();
+    save_clipboard_settings(&app_handle, &settings)?;
+
+    Ok(settings.excluded_apps)
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use super::super::settings::{load_clipboard_settings, save_clipboard_settings};
+use super::discovery::DiscoveredDevice;
+use super::peers::SyncPeer;
+use super::session::{pair_with, ClipboardSyncResult};
+use super::{ensure_device_id, sync_all, ClipboardSyncState, PairingCode};
+
+#[derive(Serialize)]
+pub struct SyncPeerInfo {
+    pub device_id: String,
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub paired_at: u64,
+    pub last_synced: Option<u64>,
+}
+
+impl From<SyncPeer> for SyncPeerInfo {
+    fn from(peer: SyncPeer) -> Self {
+        Self {
+            device_id: peer.device_id,
+            name: peer.name,
+            host: peer.host,
+            port: peer.port,
+            paired_at: peer.paired_at,
+            last_synced: peer.last_synced,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct ClipboardSyncStatus {
+    pub enabled: bool,
+    pub device_id: String,
+    pub device_name: String,
+    // Port actually listened on, None while sync is off
+    pub port: Option<u16>,
+    pub pairing: Option<PairingCode>,
+    pub peers: Vec<SyncPeerInfo>,
+    // Devices seen over mDNS that aren't paired yet
+    pub discovered: Vec<DiscoveredDevice>,
+}
+
+fn sync_status(
+    app_handle: &AppHandle,
+    sync_state: &ClipboardSyncState,
+) -> Result<ClipboardSyncStatus, String> {
+    let settings = load_clipboard_settings(app_handle).sync;
+    let local = sync_state.local_device().ok();
+    let peers = sync_state.peers(app_handle)?;
+    let discovered = sync_state
+        .discovered()
+        .into_iter()
+        .filter(|device| !peers.iter().any(|peer| peer.device_id == device.device_id))
+        .collect();
+
+    Ok(ClipboardSyncStatus {
+        enabled: settings.enabled,
+        device_name: settings.name(),
+        device_id: settings.device_id,
+        port: local.map(|local| local.port),
+        pairing: sync_state.pending_pairing(),
+        peers: peers.into_iter().map(SyncPeerInfo::from).collect(),
+        discovered,
+    })
+}
+
+#[tauri::command]
+pub fn get_clipboard_sync_status(
+    app_handle: AppHandle,
+    sync_state: State<'_, ClipboardSyncState>,
+) -> Result<ClipboardSyncStatus, String> {
+    sync_status(&app_handle, &sync_state)
+}
+
+// Turn sync on or off. Changing the port or name restarts it.
+#[tauri::command]
+pub fn set_clipboard_sync(
+    enabled: bool,
+    port: Option<u16>,
+    device_name: Option<String>,
+    app_handle: AppHandle,
+    sync_state: State<'_, ClipboardSyncState>,
+) -> Result<ClipboardSyncStatus, String> {
+    let mut settings = load_clipboard_settings(&app_handle);
+    settings.sync.enabled = enabled;
+    if let Some(port) = port {
+        settings.sync.port = port;
+    }
+    if let Some(device_name) = device_name {
+        settings.sync.device_name = device_name.trim().to_string();
+    }
+    save_clipboard_settings(&app_handle, &settings)?;
+
+    sync_state.stop();
+    if enabled {
+        sync_state.start(&app_handle, &ensure_device_id(&app_handle)?)?;
+    }
+    sync_status(&app_handle, &sync_state)
+}
+
+// Show a one-time code that another device enters to pair with this one
+#[tauri::command]
+pub fn start_clipboard_sync_pairing(
+    sync_state: State<'_, ClipboardSyncState>,
+) -> Result<PairingCode, String> {
+    sync_state.start_pairing()
+}
+
+// Pair with a device that is showing a pairing code
+#[tauri::command]
+pub async fn pair_clipboard_sync_device(
+    host: String,
+    port: u16,
+    code: String,
+    app_handle: AppHandle,
+) -> Result<SyncPeerInfo, String> {
+    let sync_state = app_handle.state::<ClipboardSyncState>();
+    let local = sync_state.local_device()?;
+
+    let peer = pair_with(&app_handle, &local, host.trim(), port, &code)?;
+    sync_state.paired(&app_handle, peer.clone())?;
+    Ok(SyncPeerInfo::from(peer))
+}
+
+// Forget a paired device. It can no longer sync with this one.
+#[tauri::command]
+pub fn remove_clipboard_sync_device(
+    device_id: String,
+    app_handle: AppHandle,
+    sync_state: State<'_, ClipboardSyncState>,
+) -> Result<(), String> {
+    sync_state.update_peers(&app_handle, |peers| {
+        peers.retain(|peer| peer.device_id != device_id)
+    })
+}
+
+#[tauri::command]
+pub async fn sync_clipboard_now(app_handle: AppHandle) -> Result<Vec<ClipboardSyncResult>, String> {
+    app_handle.state::<ClipboardSyncState>().local_device()?;
+    Ok(sync_all(&app_handle))
+}
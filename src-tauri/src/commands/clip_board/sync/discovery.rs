@@ -0,0 +1,115 @@
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const SERVICE_TYPE: &str = "_blazecast-sync._tcp.local.";
+
+#[derive(Serialize, Clone)]
+pub struct DiscoveredDevice {
+    pub device_id: String,
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+}
+
+// Announces this device over mDNS and keeps track of the others on the network
+pub struct Discovery {
+    daemon: ServiceDaemon,
+    // Keyed by the mDNS instance name, which is the device id
+    devices: Arc<Mutex<HashMap<String, DiscoveredDevice>>>,
+}
+
+impl Discovery {
+    pub fn start(device_id: &str, name: &str, port: u16) -> Result<Self, String> {
+        let daemon =
+            ServiceDaemon::new().map_err(|e| format!("Failed to start mDNS discovery: {}", e))?;
+
+        let host_name = format!("blazecast-{}.local.", &device_id[..8.min(device_id.len())]);
+        let properties = [("id", device_id), ("name", name)];
+        let service = ServiceInfo::new(
+            SERVICE_TYPE,
+            device_id,
+            &host_name,
+            "",
+            port,
+            &properties[..],
+        )
+        .map_err(|e| format!("Failed to describe sync service: {}", e))?
+        .enable_addr_auto();
+        daemon
+            .register(service)
+            .map_err(|e| format!("Failed to announce sync service: {}", e))?;
+
+        let events = daemon
+            .browse(SERVICE_TYPE)
+            .map_err(|e| format!("Failed to browse for sync devices: {}", e))?;
+        let devices = Arc::new(Mutex::new(HashMap::new()));
+        let found = devices.clone();
+        let own_id = device_id.to_string();
+
+        // Ends when the daemon shuts down and closes the channel
+        thread::spawn(move || {
+            while let Ok(event) = events.recv() {
+                let mut found = match found.lock() {
+                    Ok(found) => found,
+                    Err(_) => break,
+                };
+                match event {
+                    ServiceEvent::ServiceResolved(info) => {
+                        if let Some(device) = discovered_device(&info) {
+                            if device.device_id != own_id {
+                                found.insert(info.get_fullname().to_string(), device);
+                            }
+                        }
+                    }
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        found.remove(&fullname);
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        Ok(Self { daemon, devices })
+    }
+
+    pub fn devices(&self) -> Vec<DiscoveredDevice> {
+        self.devices
+            .lock()
+            .map(|devices| devices.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn find(&self, device_id: &str) -> Option<DiscoveredDevice> {
+        self.devices
+            .lock()
+            .ok()?
+            .values()
+            .find(|device| device.device_id == device_id)
+            .cloned()
+    }
+
+    pub fn stop(&self) {
+        if let Err(e) = self.daemon.shutdown() {
+            eprintln!("Failed to stop mDNS discovery: {}", e);
+        }
+    }
+}
+
+fn discovered_device(info: &ServiceInfo) -> Option<DiscoveredDevice> {
+    // Prefer IPv4, link-local IPv6 addresses need a scope to connect to
+    let address = info
+        .get_addresses()
+        .iter()
+        .min_by_key(|address| !matches!(address, IpAddr::V4(_)))?;
+
+    Some(DiscoveredDevice {
+        device_id: info.get_property_val_str("id")?.to_string(),
+        name: info.get_property_val_str("name").unwrap_or("").to_string(),
+        host: address.to_string(),
+        port: info.get_port(),
+    })
+}
@@ -0,0 +1,490 @@
+// Opt-in history sync between paired devices on the local network. Items are
+// matched by content hash and merged like an import. Deleted content is
+// remembered for a while and removed on the other devices as well.
+// Two instances on one machine need their own data directory (e.g. a separate
+// XDG_DATA_HOME) and port, then pair them through 127.0.0.1.
+mod commands;
+mod discovery;
+mod peers;
+mod protocol;
+mod session;
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::ErrorKind;
+use std::net::TcpListener;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::history_store::{now_millis, ClipboardHistoryStore};
+use super::key_protector::get_encryption_key;
+use super::settings::{load_clipboard_settings, save_clipboard_settings};
+use discovery::{DiscoveredDevice, Discovery};
+use peers::{write_sync_peers, SyncPeer};
+use session::{ClipboardSyncResult, LocalDevice};
+
+pub use commands::{
+    get_clipboard_sync_status, pair_clipboard_sync_device, remove_clipboard_sync_device,
+    set_clipboard_sync, start_clipboard_sync_pairing, sync_clipboard_now,
+};
+pub(crate) use peers::get_peers_path;
+pub use peers::{read_sync_peers, write_sync_peers_to};
+
+pub const DEFAULT_SYNC_PORT: u16 = 47321;
+// Sent when another device paired with this one
+pub const CLIPBOARD_SYNC_PAIRED_EVENT: &str = "clipboard-sync-paired";
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(60);
+// Copies in quick succession go out in one sync
+const SYNC_DELAY: Duration = Duration::from_secs(2);
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(200);
+// Connections served at once, more are closed right away. Pairing hashes the
+// code with Argon2 for every connection.
+const MAX_CONNECTIONS: usize = 8;
+const PAIRING_CODE_LIFETIME_MS: u64 = 5 * 60 * 1000;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SyncSettings {
+    pub enabled: bool,
+    // Port other devices connect to
+    pub port: u16,
+    // Generated the first time sync is turned on
+    pub device_id: String,
+    // Shown on the other devices, the host name if empty
+    pub device_name: String,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_SYNC_PORT,
+            device_id: String::new(),
+            device_name: String::new(),
+        }
+    }
+}
+
+impl SyncSettings {
+    fn name(&self) -> String {
+        if !self.device_name.is_empty() {
+            return self.device_name.clone();
+        }
+
+        std::env::var("COMPUTERNAME")
+            .or_else(|_| std::env::var("HOSTNAME"))
+            .ok()
+            .or_else(|| fs::read_to_string("/etc/hostname").ok())
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "BlazeCast".to_string())
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct PairingCode {
+    pub code: String,
+    pub expires_at: u64,
+}
+
+struct PendingPairing {
+    code: PairingCode,
+    failed_attempts: u32,
+}
+
+struct RunningSync {
+    local: LocalDevice,
+    stop: Arc<AtomicBool>,
+    wake: Sender<()>,
+    listener: JoinHandle<()>,
+    discovery: Option<Discovery>,
+}
+
+// The paired devices file is sealed with the history key, so it is read and
+// written under the history lock that key rotation holds
+fn with_peers_locked<T>(
+    app_handle: &AppHandle,
+    f: impl FnOnce() -> Result<T, String>,
+) -> Result<T, String> {
+    let history_store = app_handle
+        .try_state::<ClipboardHistoryStore>()
+        .ok_or_else(|| "Clipboard history store is not available".to_string())?;
+    history_store.with_history_locked(|_| f())
+}
+
+#[derive(Default)]
+pub struct ClipboardSyncState {
+    running: Mutex<Option<RunningSync>>,
+    pairing: Mutex<Option<PendingPairing>>,
+}
+
+impl ClipboardSyncState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn local_device(&self) -> Result<LocalDevice, String> {
+        self.running
+            .lock()
+            .map_err(|_| "Clipboard sync lock poisoned".to_string())?
+            .as_ref()
+            .map(|running| running.local.clone())
+            .ok_or_else(|| "Clipboard sync is turned off".to_string())
+    }
+
+    fn start_pairing(&self) -> Result<PairingCode, String> {
+        self.local_device()?;
+        let code = PairingCode {
+            code: protocol::generate_pairing_code(),
+            expires_at: now_millis() + PAIRING_CODE_LIFETIME_MS,
+        };
+
+        *self
+            .pairing
+            .lock()
+            .map_err(|_| "Clipboard sync lock poisoned".to_string())? = Some(PendingPairing {
+            code: code.clone(),
+            failed_attempts: 0,
+        });
+        Ok(code)
+    }
+
+    fn pending_pairing(&self) -> Option<PairingCode> {
+        let mut pairing = self.pairing.lock().ok()?;
+        if pairing
+            .as_ref()
+            .is_some_and(|pending| pending.code.expires_at <= now_millis())
+        {
+            *pairing = None;
+        }
+        pairing.as_ref().map(|pending| pending.code.clone())
+    }
+
+    fn pairing_code(&self) -> Option<String> {
+        self.pending_pairing().map(|pairing| pairing.code)
+    }
+
+    // Too many wrong guesses use up the code
+    fn pairing_failed(&self, max_attempts: u32) {
+        if let Ok(mut pairing) = self.pairing.lock() {
+            if let Some(pending) = pairing.as_mut() {
+                pending.failed_attempts += 1;
+                if pending.failed_attempts >= max_attempts {
+                    *pairing = None;
+                }
+            }
+        }
+    }
+
+    fn peers(&self, app_handle: &AppHandle) -> Result<Vec<SyncPeer>, String> {
+        with_peers_locked(app_handle, || {
+            read_sync_peers(app_handle, &get_encryption_key(app_handle)?)
+        })
+    }
+
+    fn peer(&self, app_handle: &AppHandle, device_id: &str) -> Result<Option<SyncPeer>, String> {
+        Ok(self
+            .peers(app_handle)?
+            .into_iter()
+            .find(|peer| peer.device_id == device_id))
+    }
+
+    fn update_peers(
+        &self,
+        app_handle: &AppHandle,
+        f: impl FnOnce(&mut Vec<SyncPeer>),
+    ) -> Result<(), String> {
+        with_peers_locked(app_handle, || {
+            let key = get_encryption_key(app_handle)?;
+            let mut peers = read_sync_peers(app_handle, &key)?;
+            f(&mut peers);
+            write_sync_peers(app_handle, &peers, &key)
+        })
+    }
+
+    // Store a newly paired device, replacing an earlier pairing with it
+    fn paired(&self, app_handle: &AppHandle, peer: SyncPeer) -> Result<(), String> {
+        if let Ok(mut pairing) = self.pairing.lock() {
+            *pairing = None;
+        }
+
+        println!("Paired clipboard sync with {}", peer.name);
+        let name = peer.name.clone();
+        self.update_peers(app_handle, |peers| {
+            peers.retain(|existing| existing.device_id != peer.device_id);
+            peers.push(peer);
+        })?;
+
+        if let Err(e) = app_handle.emit_all(CLIPBOARD_SYNC_PAIRED_EVENT, name) {
+            eprintln!("Failed to emit clipboard sync pairing: {}", e);
+        }
+        self.request_sync();
+        Ok(())
+    }
+
+    fn synced(&self, app_handle: &AppHandle, peer: &SyncPeer) -> Result<(), String> {
+        self.update_peers(app_handle, |peers| {
+            if let Some(existing) = peers
+                .iter_mut()
+                .find(|existing| existing.device_id == peer.device_id)
+            {
+                existing.host = peer.host.clone();
+                existing.port = peer.port;
+                existing.last_synced = Some(now_millis());
+            }
+        })
+    }
+
+    fn request_sync(&self) {
+        if let Ok(running) = self.running.lock() {
+            if let Some(running) = running.as_ref() {
+                let _ = running.wake.send(());
+            }
+        }
+    }
+
+    fn discovered(&self) -> Vec<DiscoveredDevice> {
+        self.running
+            .lock()
+            .ok()
+            .and_then(|running| {
+                running
+                    .as_ref()?
+                    .discovery
+                    .as_ref()
+                    .map(|discovery| discovery.devices())
+            })
+            .unwrap_or_default()
+    }
+
+    // Where the device can be reached, preferring what mDNS saw last
+    fn address_of(&self, peer: &SyncPeer) -> (String, u16) {
+        self.running
+            .lock()
+            .ok()
+            .and_then(|running| running.as_ref()?.discovery.as_ref()?.find(&peer.device_id))
+            .map(|device| (device.host, device.port))
+            .unwrap_or_else(|| (peer.host.clone(), peer.port))
+    }
+
+    fn start(&self, app_handle: &AppHandle, settings: &SyncSettings) -> Result<(), String> {
+        let mut running = self
+            .running
+            .lock()
+            .map_err(|_| "Clipboard sync lock poisoned".to_string())?;
+        if running.is_some() {
+            return Ok(());
+        }
+
+        let listener = TcpListener::bind(("0.0.0.0", settings.port))
+            .map_err(|e| format!("Failed to listen on port {}: {}", settings.port, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure sync listener: {}", e))?;
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to get sync listener address: {}", e))?
+            .port();
+
+        let local = LocalDevice {
+            device_id: settings.device_id.clone(),
+            name: settings.name(),
+            port,
+        };
+        // Manually entered addresses still work without mDNS
+        let discovery = Discovery::start(&local.device_id, &local.name, port)
+            .map_err(|e| eprintln!("{}", e))
+            .ok();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let (wake, wakeups) = mpsc::channel();
+        let listener = spawn_listener(app_handle.clone(), local.clone(), listener, stop.clone());
+
+        let worker_handle = app_handle.clone();
+        let worker_stop = stop.clone();
+        thread::spawn(move || loop {
+            match wakeups.recv_timeout(SYNC_INTERVAL) {
+                Ok(()) => {
+                    thread::sleep(SYNC_DELAY);
+                    while wakeups.try_recv().is_ok() {}
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if worker_stop.load(Ordering::SeqCst) {
+                break;
+            }
+            for result in sync_all(&worker_handle) {
+                if let Some(error) = result.error {
+                    eprintln!("Failed to sync clipboard with {}: {}", result.name, error);
+                }
+            }
+        });
+
+        println!("Clipboard sync listening on port {}", port);
+        *running = Some(RunningSync {
+            local,
+            stop,
+            wake,
+            listener,
+            discovery,
+        });
+        Ok(())
+    }
+
+    fn stop(&self) {
+        let running = match self.running.lock() {
+            Ok(mut running) => running.take(),
+            Err(_) => None,
+        };
+
+        // Dropping the wake sender ends the worker
+        if let Some(running) = running {
+            running.stop.store(true, Ordering::SeqCst);
+            if let Some(discovery) = running.discovery.as_ref() {
+                discovery.stop();
+            }
+            // Frees the port before sync is started again
+            let _ = running.listener.join();
+        }
+        if let Ok(mut pairing) = self.pairing.lock() {
+            *pairing = None;
+        }
+    }
+}
+
+// Counts a connection being served until it is dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn take(connections: &Arc<AtomicUsize>) -> Option<Self> {
+        connections
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < MAX_CONNECTIONS).then_some(count + 1)
+            })
+            .ok()
+            .map(|_| Self(connections.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn spawn_listener(
+    app_handle: AppHandle,
+    local: LocalDevice,
+    listener: TcpListener,
+    stop: Arc<AtomicBool>,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let connections = Arc::new(AtomicUsize::new(0));
+        while !stop.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, address)) => {
+                    let slot = match ConnectionSlot::take(&connections) {
+                        Some(slot) => slot,
+                        None => {
+                            eprintln!("Too many sync connections, dropping {}", address);
+                            continue;
+                        }
+                    };
+                    if let Err(e) = stream.set_nonblocking(false) {
+                        eprintln!("Failed to configure sync connection: {}", e);
+                        continue;
+                    }
+                    let connection_handle = app_handle.clone();
+                    let connection_local = local.clone();
+                    thread::spawn(move || {
+                        let _slot = slot;
+                        if let Err(e) =
+                            session::serve(&connection_handle, &connection_local, stream)
+                        {
+                            eprintln!("Clipboard sync connection failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
+                Err(e) => {
+                    eprintln!("Failed to accept sync connection: {}", e);
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                }
+            }
+        }
+    })
+}
+
+// Sync with every paired device in turn
+fn sync_all(app_handle: &AppHandle) -> Vec<ClipboardSyncResult> {
+    let sync_state = match app_handle.try_state::<ClipboardSyncState>() {
+        Some(sync_state) => sync_state,
+        None => return Vec::new(),
+    };
+    let local = match sync_state.local_device() {
+        Ok(local) => local,
+        Err(_) => return Vec::new(),
+    };
+    let peers = match sync_state.peers(app_handle) {
+        Ok(peers) => peers,
+        Err(e) => {
+            eprintln!("Failed to load paired sync devices: {}", e);
+            return Vec::new();
+        }
+    };
+
+    peers
+        .iter()
+        .map(|peer| {
+            let (host, port) = sync_state.address_of(peer);
+            session::sync_with(app_handle, &local, peer, &host, port).unwrap_or_else(|e| {
+                ClipboardSyncResult {
+                    device_id: peer.device_id.clone(),
+                    name: peer.name.clone(),
+                    error: Some(e),
+                    ..Default::default()
+                }
+            })
+        })
+        .collect()
+}
+
+// Give the device an id before it is announced anywhere
+fn ensure_device_id(app_handle: &AppHandle) -> Result<SyncSettings, String> {
+    let mut settings = load_clipboard_settings(app_handle);
+    if settings.sync.device_id.is_empty() {
+        settings.sync.device_id = uuid::Uuid::new_v4().to_string();
+        save_clipboard_settings(app_handle, &settings)?;
+    }
+    Ok(settings.sync)
+}
+
+// Start sync at launch if it was turned on
+pub fn start_clipboard_sync(app_handle: AppHandle) {
+    let settings = load_clipboard_settings(&app_handle).sync;
+    if !settings.enabled {
+        return;
+    }
+
+    let started = ensure_device_id(&app_handle).and_then(|settings| {
+        app_handle
+            .state::<ClipboardSyncState>()
+            .start(&app_handle, &settings)
+    });
+    if let Err(e) = started {
+        eprintln!("Failed to start clipboard sync: {}", e);
+    }
+}
+
+// Called by the monitor so new copies reach the other devices quickly
+pub fn request_clipboard_sync(app_handle: &AppHandle) {
+    if let Some(sync_state) = app_handle.try_state::<ClipboardSyncState>() {
+        sync_state.request_sync();
+    }
+}
@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::super::envelope;
+use super::super::key_protector::HistoryKey;
+use super::protocol::decode_key;
+use crate::persistence::files::write_atomic;
+
+// A device this one was paired with. The pair key is only ever stored
+// encrypted with the history key.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SyncPeer {
+    pub device_id: String,
+    pub name: String,
+    // Last known address, mDNS results take precedence
+    pub host: String,
+    pub port: u16,
+    pub key: String,
+    pub paired_at: u64,
+    #[serde(default)]
+    pub last_synced: Option<u64>,
+}
+
+impl SyncPeer {
+    pub fn pair_key(&self) -> Result<HistoryKey, String> {
+        decode_key(&self.key)
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SyncPeersFile {
+    peers: Vec<SyncPeer>,
+}
+
+pub(crate) fn get_peers_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+
+    Ok(app_data_dir.join("clipboard_sync_peers.encrypted"))
+}
+
+pub fn read_sync_peers(
+    app_handle: &tauri::AppHandle,
+    key: &HistoryKey,
+) -> Result<Vec<SyncPeer>, String> {
+    let peers_path = get_peers_path(app_handle)?;
+    if !peers_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let data = fs::read_to_string(&peers_path)
+        .map_err(|e| format!("Failed to read paired sync devices: {}", e))?;
+    let peers: SyncPeersFile = serde_json::from_slice(&envelope::open(key, &data)?)
+        .map_err(|e| format!("Failed to parse paired sync devices: {}", e))?;
+    Ok(peers.peers)
+}
+
+pub fn write_sync_peers(
+    app_handle: &tauri::AppHandle,
+    peers: &[SyncPeer],
+    key: &HistoryKey,
+) -> Result<(), String> {
+    write_sync_peers_to(&get_peers_path(app_handle)?, peers, key)
+}
+
+pub fn write_sync_peers_to(
+    path: &Path,
+    peers: &[SyncPeer],
+    key: &HistoryKey,
+) -> Result<(), String> {
+    let json = serde_json::to_vec(&SyncPeersFile {
+        peers: peers.to_vec(),
+    })
+    .map_err(|e| format!("Failed to serialize paired sync devices: {}", e))?;
+
+    write_atomic(path, envelope::seal(key, &json)?.as_bytes())
+}
@@ -0,0 +1,624 @@
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use hkdf::Hkdf;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+use super::super::bundle::BundleItem;
+use super::super::content_hash::item_content_hash;
+use super::super::envelope::{
+    decrypt_bytes, encrypt_bytes_with_nonce, NONCE_LEN as FRAME_NONCE_LEN,
+};
+use super::super::key_protector::HistoryKey;
+use super::super::storage::{ClipboardItem, DeletedContent};
+
+// 2 seals every frame with a key per direction and counts frames
+pub const PROTOCOL_VERSION: u32 = 2;
+pub const NONCE_LEN: usize = 16;
+
+// A single item with its image is the largest frame, anything above is a broken peer
+const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+// Greetings and errors are all that is sent before a key is agreed on, anyone
+// on the network can send them
+const MAX_PLAIN_FRAME_LEN: usize = 16 * 1024;
+const FRAME_PLAIN: u8 = 0;
+const FRAME_SEALED: u8 = 1;
+
+// One-time codes avoid characters that are easy to mix up. 10 characters
+// from 32 give 50 bits, on top of the Argon2 cost of every guess.
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const CODE_LEN: usize = 10;
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Hello {
+        version: u32,
+        device_id: String,
+        name: String,
+        // Port the sender accepts sync connections on
+        port: u16,
+        nonce: String,
+        pairing: bool,
+    },
+    // Secret each side contributes to the pair key, sealed with the code key
+    PairSecret {
+        secret: String,
+    },
+    Manifest {
+        entries: Vec<ManifestEntry>,
+        // Content deleted on the sending device
+        #[serde(default)]
+        deleted: Vec<DeletedContent>,
+    },
+    Item {
+        item: Box<BundleItem>,
+    },
+    ItemsEnd,
+    // Always sent in plain text, the other side may not have a key yet
+    Error {
+        message: String,
+    },
+}
+
+// What a device has of one piece of content
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManifestEntry {
+    pub hash: String,
+    pub pinned: bool,
+    pub copy_count: u64,
+    pub last_copied: u64,
+    pub timestamp: u64,
+}
+
+// Only a sealed frame that doesn't open with the channel key says the other
+// side used a different key, e.g. a wrong pairing code
+pub enum ReceiveError {
+    Undecryptable,
+    Failed(String),
+}
+
+impl ReceiveError {
+    pub fn into_message(self) -> String {
+        match self {
+            ReceiveError::Undecryptable => "Sync message could not be decrypted".to_string(),
+            ReceiveError::Failed(message) => message,
+        }
+    }
+}
+
+// Which end of the connection a channel is, each side seals with a key of its
+// own so a frame can't be sent back to where it came from
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Client,
+    Server,
+}
+
+struct ChannelKeys {
+    send: HistoryKey,
+    receive: HistoryKey,
+    // Frames sealed and opened so far, the count is the nonce of the next one.
+    // A frame that is replayed, dropped or reordered doesn't open.
+    sent: u64,
+    received: u64,
+}
+
+// Length prefixed frames over TCP. Once a key is set everything except errors
+// is sealed with it.
+pub struct Channel {
+    stream: TcpStream,
+    side: Side,
+    keys: Option<ChannelKeys>,
+}
+
+fn frame_nonce(count: u64) -> [u8; FRAME_NONCE_LEN] {
+    let mut nonce = [0u8; FRAME_NONCE_LEN];
+    nonce[FRAME_NONCE_LEN - 8..].copy_from_slice(&count.to_be_bytes());
+    nonce
+}
+
+impl Channel {
+    pub fn new(stream: TcpStream, side: Side) -> Self {
+        Self {
+            stream,
+            side,
+            keys: None,
+        }
+    }
+
+    pub fn set_key(&mut self, key: HistoryKey) -> Result<(), String> {
+        let client_to_server = derive(&key, &[], &[b"blazecast-sync-client-to-server"])?;
+        let server_to_client = derive(&key, &[], &[b"blazecast-sync-server-to-client"])?;
+        let (send, receive) = match self.side {
+            Side::Client => (client_to_server, server_to_client),
+            Side::Server => (server_to_client, client_to_server),
+        };
+        self.keys = Some(ChannelKeys {
+            send,
+            receive,
+            sent: 0,
+            received: 0,
+        });
+        Ok(())
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        let json = serde_json::to_vec(message)
+            .map_err(|e| format!("Failed to serialize sync message: {}", e))?;
+
+        let mut frame = Vec::with_capacity(json.len() + 32);
+        match (&mut self.keys, message) {
+            (Some(keys), message) if !matches!(message, Message::Error { .. }) => {
+                let ciphertext =
+                    encrypt_bytes_with_nonce(&keys.send, &frame_nonce(keys.sent), &json)?;
+                keys.sent += 1;
+                frame.push(FRAME_SEALED);
+                frame.extend_from_slice(&ciphertext);
+            }
+            _ => {
+                frame.push(FRAME_PLAIN);
+                frame.extend_from_slice(&json);
+            }
+        }
+        if frame.len() > MAX_FRAME_LEN {
+            return Err("Sync message is too large".to_string());
+        }
+
+        self.stream
+            .write_all(&(frame.len() as u32).to_be_bytes())
+            .and_then(|_| self.stream.write_all(&frame))
+            .map_err(|e| format!("Failed to send sync message: {}", e))
+    }
+
+    // Errors sent by the other side come back as Err with their message
+    pub fn receive(&mut self) -> Result<Message, String> {
+        self.try_receive().map_err(ReceiveError::into_message)
+    }
+
+    pub fn try_receive(&mut self) -> Result<Message, ReceiveError> {
+        let mut len = [0u8; 4];
+        self.stream
+            .read_exact(&mut len)
+            .map_err(|e| ReceiveError::Failed(format!("Failed to receive sync message: {}", e)))?;
+        let len = u32::from_be_bytes(len) as usize;
+        let max_len = match self.keys {
+            Some(_) => MAX_FRAME_LEN,
+            None => MAX_PLAIN_FRAME_LEN,
+        };
+        if len == 0 || len > max_len {
+            return Err(ReceiveError::Failed(
+                "Invalid sync message length".to_string(),
+            ));
+        }
+
+        // The buffer grows with what actually arrives, not with the length
+        // the other side claims
+        let mut frame = Vec::new();
+        (&mut self.stream)
+            .take(len as u64)
+            .read_to_end(&mut frame)
+            .map_err(|e| ReceiveError::Failed(format!("Failed to receive sync message: {}", e)))?;
+        if frame.len() != len {
+            return Err(ReceiveError::Failed(
+                "Sync connection closed in the middle of a message".to_string(),
+            ));
+        }
+
+        let json = match (frame[0], &mut self.keys) {
+            (FRAME_SEALED, Some(keys)) => {
+                let json = decrypt_bytes(&keys.receive, &frame_nonce(keys.received), &frame[1..])
+                    .map_err(|_| ReceiveError::Undecryptable)?;
+                keys.received += 1;
+                json
+            }
+            (FRAME_PLAIN, _) => frame[1..].to_vec(),
+            _ => {
+                return Err(ReceiveError::Failed(
+                    "Unexpected sync message encryption".to_string(),
+                ))
+            }
+        };
+
+        let message: Message = serde_json::from_slice(&json)
+            .map_err(|e| ReceiveError::Failed(format!("Failed to parse sync message: {}", e)))?;
+        match message {
+            Message::Error { message } => Err(ReceiveError::Failed(message)),
+            // Only errors may skip encryption once a key is in place
+            _ if frame[0] == FRAME_PLAIN && self.keys.is_some() => {
+                Err(ReceiveError::Failed("Unencrypted sync message".to_string()))
+            }
+            message => Ok(message),
+        }
+    }
+
+    pub fn send_error(&mut self, message: &str) {
+        let _ = self.send(&Message::Error {
+            message: message.to_string(),
+        });
+    }
+}
+
+pub fn random_nonce() -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+    nonce
+}
+
+pub fn encode(bytes: &[u8]) -> String {
+    STANDARD.encode(bytes)
+}
+
+pub fn decode_nonce(nonce: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(nonce)
+        .ok()
+        .filter(|nonce| nonce.len() == NONCE_LEN)
+        .ok_or_else(|| "Invalid sync nonce".to_string())
+}
+
+pub fn decode_key(key: &str) -> Result<HistoryKey, String> {
+    let bytes = STANDARD
+        .decode(key)
+        .map_err(|e| format!("Invalid sync key: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|_| "Invalid sync key length".to_string())
+}
+
+pub fn generate_pairing_code() -> String {
+    let mut bytes = [0u8; CODE_LEN];
+    OsRng.fill_bytes(&mut bytes);
+    let code: String = bytes
+        .iter()
+        .map(|byte| CODE_ALPHABET[*byte as usize % CODE_ALPHABET.len()] as char)
+        .collect();
+    format!("{}-{}", &code[..CODE_LEN / 2], &code[CODE_LEN / 2..])
+}
+
+// Codes are typed in by hand, so case and separators don't matter
+fn normalize_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn derive(ikm: &[u8], salt: &[u8], info: &[&[u8]]) -> Result<HistoryKey, String> {
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand_multi_info(info, &mut key)
+        .map_err(|e| format!("Failed to derive sync key: {}", e))?;
+    Ok(key)
+}
+
+// Key that protects the pairing exchange, both nonces make it unique per attempt
+pub fn code_key(
+    code: &str,
+    client_nonce: &[u8],
+    server_nonce: &[u8],
+) -> Result<HistoryKey, String> {
+    let salt = [client_nonce, server_nonce].concat();
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(normalize_code(code).as_bytes(), &salt, &mut key)
+        .map_err(|e| format!("Failed to derive pairing key: {}", e))?;
+    Ok(key)
+}
+
+// This device's share of a pair key, derived from the history key so no
+// separate secret has to be kept around. The nonce makes every share unique.
+pub fn pair_secret(history_key: &HistoryKey, nonce: &[u8]) -> Result<HistoryKey, String> {
+    derive(history_key, nonce, &[b"blazecast-sync-pair-secret"])
+}
+
+pub fn pair_key(
+    client_secret: &[u8],
+    server_secret: &[u8],
+    client_id: &str,
+    server_id: &str,
+) -> Result<HistoryKey, String> {
+    derive(
+        &[client_secret, server_secret].concat(),
+        b"blazecast-sync-pair",
+        &[client_id.as_bytes(), b"|", server_id.as_bytes()],
+    )
+}
+
+// Fresh key for every connection between paired devices
+pub fn session_key(
+    pair_key: &HistoryKey,
+    client_nonce: &[u8],
+    server_nonce: &[u8],
+) -> Result<HistoryKey, String> {
+    derive(
+        pair_key,
+        &[client_nonce, server_nonce].concat(),
+        &[b"blazecast-sync-session"],
+    )
+}
+
+// Items without content have nothing to compare with the other side
+pub fn manifest_entry(item: &ClipboardItem) -> Option<ManifestEntry> {
+    Some(ManifestEntry {
+        hash: item_content_hash(item)?,
+        pinned: item.pinned,
+        copy_count: item.copy_count,
+        last_copied: item.last_copied,
+        timestamp: item.timestamp,
+    })
+}
+
+// Whether merging the item would change the other device's copy. Merging
+// only ever pins, raises counts and moves the first copy time back, so both
+// sides end up the same no matter which one syncs first.
+pub fn is_ahead_of(item: &ClipboardItem, entry: &ManifestEntry) -> bool {
+    (item.pinned && !entry.pinned)
+        || item.copy_count > entry.copy_count
+        || item.last_copied > entry.last_copied
+        || item.timestamp < entry.timestamp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    const HISTORY_KEY: HistoryKey = [7u8; 32];
+
+    fn hello(device_id: &str, nonce: &[u8], pairing: bool) -> Message {
+        Message::Hello {
+            version: PROTOCOL_VERSION,
+            device_id: device_id.to_string(),
+            name: device_id.to_string(),
+            port: 0,
+            nonce: encode(nonce),
+            pairing,
+        }
+    }
+
+    fn hello_nonce(message: Message) -> Vec<u8> {
+        match message {
+            Message::Hello { nonce, .. } => decode_nonce(&nonce).unwrap(),
+            _ => panic!("expected a greeting"),
+        }
+    }
+
+    fn pair_secret_of(message: Message) -> Vec<u8> {
+        match message {
+            Message::PairSecret { secret } => decode_key(&secret).unwrap().to_vec(),
+            _ => panic!("expected a pairing secret"),
+        }
+    }
+
+    // Server side of a pairing, returns its pair key or why the secret was refused
+    fn serve_pairing(listener: TcpListener, code: &str) -> Result<HistoryKey, ReceiveError> {
+        let (stream, _) = listener.accept().unwrap();
+        let mut channel = Channel::new(stream, Side::Server);
+        let client_nonce = hello_nonce(channel.receive().unwrap());
+        let server_nonce = random_nonce();
+        channel.send(&hello("server", &server_nonce, true)).unwrap();
+        channel
+            .set_key(code_key(code, &client_nonce, &server_nonce).unwrap())
+            .unwrap();
+
+        let client_secret = pair_secret_of(channel.try_receive()?);
+        let server_secret = pair_secret(&HISTORY_KEY, &server_nonce).unwrap();
+        channel
+            .send(&Message::PairSecret {
+                secret: encode(&server_secret),
+            })
+            .unwrap();
+        Ok(pair_key(&client_secret, &server_secret, "client", "server").unwrap())
+    }
+
+    fn pair(port: u16, code: &str) -> Result<HistoryKey, String> {
+        let mut channel = Channel::new(
+            TcpStream::connect(("127.0.0.1", port)).unwrap(),
+            Side::Client,
+        );
+        let client_nonce = random_nonce();
+        channel.send(&hello("client", &client_nonce, true))?;
+        let server_nonce = hello_nonce(channel.receive()?);
+        channel.set_key(code_key(code, &client_nonce, &server_nonce)?)?;
+
+        let client_secret = pair_secret(&HISTORY_KEY, &client_nonce)?;
+        channel.send(&Message::PairSecret {
+            secret: encode(&client_secret),
+        })?;
+        let server_secret = pair_secret_of(channel.receive()?);
+        pair_key(&client_secret, &server_secret, "client", "server")
+    }
+
+    #[test]
+    fn pairs_and_exchanges_sealed_messages_over_loopback() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || serve_pairing(listener, "ABCDE-FGHJK"));
+
+        // Codes are typed by hand, case and separators don't matter
+        let client_pair_key = pair(port, "abcdefghjk").unwrap();
+        let server_pair_key = server.join().unwrap().ok().unwrap();
+        assert_eq!(client_pair_key, server_pair_key);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut channel = Channel::new(stream, Side::Server);
+            let client_nonce = hello_nonce(channel.receive().unwrap());
+            let server_nonce = random_nonce();
+            channel
+                .send(&hello("server", &server_nonce, false))
+                .unwrap();
+            channel
+                .set_key(session_key(&server_pair_key, &client_nonce, &server_nonce).unwrap())
+                .unwrap();
+
+            let received = match channel.receive().unwrap() {
+                Message::Manifest { entries, .. } => entries,
+                _ => panic!("expected a manifest"),
+            };
+            channel
+                .send(&Message::Manifest {
+                    entries: Vec::new(),
+                    deleted: Vec::new(),
+                })
+                .unwrap();
+            received
+        });
+
+        let mut channel = Channel::new(
+            TcpStream::connect(("127.0.0.1", port)).unwrap(),
+            Side::Client,
+        );
+        let client_nonce = random_nonce();
+        channel
+            .send(&hello("client", &client_nonce, false))
+            .unwrap();
+        let server_nonce = hello_nonce(channel.receive().unwrap());
+        channel
+            .set_key(session_key(&client_pair_key, &client_nonce, &server_nonce).unwrap())
+            .unwrap();
+        channel
+            .send(&Message::Manifest {
+                entries: vec![ManifestEntry {
+                    hash: "abc".to_string(),
+                    pinned: true,
+                    copy_count: 2,
+                    last_copied: 3,
+                    timestamp: 4,
+                }],
+                deleted: Vec::new(),
+            })
+            .unwrap();
+        match channel.receive().unwrap() {
+            Message::Manifest { entries, .. } => assert!(entries.is_empty()),
+            _ => panic!("expected a manifest"),
+        }
+
+        let received = server.join().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].hash, "abc");
+        assert!(received[0].pinned);
+    }
+
+    #[test]
+    fn wrong_code_is_reported_as_undecryptable() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || serve_pairing(listener, "ABCDE-FGHJK"));
+
+        assert!(pair(port, "ZZZZZ-ZZZZZ").is_err());
+        assert!(matches!(
+            server.join().unwrap(),
+            Err(ReceiveError::Undecryptable)
+        ));
+    }
+
+    #[test]
+    fn dropped_connection_is_not_a_wrong_code() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || serve_pairing(listener, "ABCDE-FGHJK"));
+
+        let mut channel = Channel::new(
+            TcpStream::connect(("127.0.0.1", port)).unwrap(),
+            Side::Client,
+        );
+        channel
+            .send(&hello("client", &random_nonce(), true))
+            .unwrap();
+        channel.receive().unwrap();
+        drop(channel);
+
+        assert!(matches!(
+            server.join().unwrap(),
+            Err(ReceiveError::Failed(_))
+        ));
+    }
+
+    // Both ends of a loopback connection
+    fn socket_pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (client, server)
+    }
+
+    fn read_raw_frame(stream: &mut TcpStream) -> Vec<u8> {
+        let mut len = [0u8; 4];
+        stream.read_exact(&mut len).unwrap();
+        let mut frame = vec![0u8; u32::from_be_bytes(len) as usize];
+        stream.read_exact(&mut frame).unwrap();
+        [len.to_vec(), frame].concat()
+    }
+
+    // The client's frames go through `tap`, the server reads what is written to `inject`
+    fn tapped_channels() -> (Channel, TcpStream, Channel, TcpStream) {
+        let (client_end, tap) = socket_pair();
+        let (inject, server_end) = socket_pair();
+        let mut client = Channel::new(client_end, Side::Client);
+        let mut server = Channel::new(server_end, Side::Server);
+        client.set_key(HISTORY_KEY).unwrap();
+        server.set_key(HISTORY_KEY).unwrap();
+        (client, tap, server, inject)
+    }
+
+    #[test]
+    fn replayed_frames_are_rejected() {
+        let (mut client, mut tap, mut server, mut inject) = tapped_channels();
+
+        client.send(&Message::ItemsEnd).unwrap();
+        let frame = read_raw_frame(&mut tap);
+        inject.write_all(&frame).unwrap();
+        inject.write_all(&frame).unwrap();
+
+        assert!(matches!(server.try_receive(), Ok(Message::ItemsEnd)));
+        assert!(matches!(
+            server.try_receive(),
+            Err(ReceiveError::Undecryptable)
+        ));
+    }
+
+    #[test]
+    fn reflected_frames_are_rejected() {
+        let (_, _, mut server, mut inject) = tapped_channels();
+
+        // The server's own frame, sent back to it
+        server.send(&Message::ItemsEnd).unwrap();
+        let frame = read_raw_frame(&mut inject);
+        inject.write_all(&frame).unwrap();
+
+        assert!(matches!(
+            server.try_receive(),
+            Err(ReceiveError::Undecryptable)
+        ));
+    }
+
+    #[test]
+    fn large_frames_are_refused_before_a_key_is_set() {
+        let (mut client, server_end) = socket_pair();
+        let mut server = Channel::new(server_end, Side::Server);
+
+        client
+            .write_all(&(MAX_PLAIN_FRAME_LEN as u32 + 1).to_be_bytes())
+            .unwrap();
+        assert!(matches!(server.try_receive(), Err(ReceiveError::Failed(_))));
+    }
+
+    #[test]
+    fn truncated_frames_are_rejected() {
+        let (mut client, server_end) = socket_pair();
+        let mut server = Channel::new(server_end, Side::Server);
+
+        client.write_all(&100u32.to_be_bytes()).unwrap();
+        client.write_all(&[FRAME_PLAIN, b'{']).unwrap();
+        drop(client);
+        assert!(matches!(server.try_receive(), Err(ReceiveError::Failed(_))));
+    }
+}
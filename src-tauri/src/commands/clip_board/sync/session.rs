@@ -0,0 +1,487 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::super::bundle::{pack_item, unpack_item, BundleItem};
+use super::super::content_hash::item_content_hash;
+use super::super::history_store::{now_millis, ClipboardHistoryStore};
+use super::super::key_protector::get_encryption_key;
+use super::super::retention::emit_removed;
+use super::super::storage::{ClipboardItem, DeletedContent};
+use super::peers::SyncPeer;
+use super::protocol::{
+    code_key, decode_key, decode_nonce, encode, is_ahead_of, manifest_entry, pair_key, pair_secret,
+    random_nonce, session_key, Channel, ManifestEntry, Message, ReceiveError, Side,
+    PROTOCOL_VERSION,
+};
+use super::ClipboardSyncState;
+
+// Sent after every sync that changed the local history
+pub const CLIPBOARD_SYNCED_EVENT: &str = "clipboard-history-synced";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+// Wrong codes allowed before the pairing code is thrown away
+const MAX_PAIRING_ATTEMPTS: u32 = 3;
+
+// This device as the other side sees it
+#[derive(Clone)]
+pub struct LocalDevice {
+    pub device_id: String,
+    pub name: String,
+    pub port: u16,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct ClipboardSyncResult {
+    pub device_id: String,
+    pub name: String,
+    // Received items that were new here
+    pub added: usize,
+    // Received items that updated content stored here
+    pub merged: usize,
+    pub sent: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// Items received from the other device
+struct Received {
+    items: Vec<ClipboardItem>,
+    // Newer metadata for images stored here, without the image files
+    updates: Vec<ClipboardItem>,
+}
+
+// What the other device has and what it deleted, by content hash
+struct RemoteManifest {
+    entries: HashMap<String, ManifestEntry>,
+    deleted: Vec<DeletedContent>,
+}
+
+struct Hello {
+    device_id: String,
+    name: String,
+    port: u16,
+    nonce: Vec<u8>,
+    pairing: bool,
+}
+
+fn connect(host: &str, port: u16) -> Result<Channel, String> {
+    let addresses: Vec<SocketAddr> = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .collect();
+
+    let mut last_error = format!("No address found for {}", host);
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return open_channel(stream, Side::Client),
+            Err(e) => last_error = format!("Failed to connect to {}: {}", address, e),
+        }
+    }
+    Err(last_error)
+}
+
+fn open_channel(stream: TcpStream, side: Side) -> Result<Channel, String> {
+    stream
+        .set_read_timeout(Some(IO_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(IO_TIMEOUT)))
+        .map_err(|e| format!("Failed to configure sync connection: {}", e))?;
+    Ok(Channel::new(stream, side))
+}
+
+fn send_hello(
+    channel: &mut Channel,
+    local: &LocalDevice,
+    nonce: &[u8],
+    pairing: bool,
+) -> Result<(), String> {
+    channel.send(&Message::Hello {
+        version: PROTOCOL_VERSION,
+        device_id: local.device_id.clone(),
+        name: local.name.clone(),
+        port: local.port,
+        nonce: encode(nonce),
+        pairing,
+    })
+}
+
+fn receive_hello(channel: &mut Channel) -> Result<Hello, String> {
+    match channel.receive()? {
+        Message::Hello {
+            version,
+            device_id,
+            name,
+            port,
+            nonce,
+            pairing,
+        } => {
+            if version != PROTOCOL_VERSION {
+                return Err(format!("Unsupported sync protocol version {}", version));
+            }
+            Ok(Hello {
+                device_id,
+                name,
+                port,
+                nonce: decode_nonce(&nonce)?,
+                pairing,
+            })
+        }
+        _ => Err("Expected a sync greeting".to_string()),
+    }
+}
+
+fn receive_pair_secret(channel: &mut Channel) -> Result<Vec<u8>, String> {
+    try_receive_pair_secret(channel).map_err(ReceiveError::into_message)
+}
+
+fn try_receive_pair_secret(channel: &mut Channel) -> Result<Vec<u8>, ReceiveError> {
+    match channel.try_receive()? {
+        Message::PairSecret { secret } => decode_key(&secret)
+            .map(|secret| secret.to_vec())
+            .map_err(ReceiveError::Failed),
+        _ => Err(ReceiveError::Failed(
+            "Expected a pairing secret".to_string(),
+        )),
+    }
+}
+
+// Pair with the device at host:port using the code it shows
+pub fn pair_with(
+    app_handle: &AppHandle,
+    local: &LocalDevice,
+    host: &str,
+    port: u16,
+    code: &str,
+) -> Result<SyncPeer, String> {
+    let mut channel = connect(host, port)?;
+    let client_nonce = random_nonce();
+    send_hello(&mut channel, local, &client_nonce, true)?;
+    let server = receive_hello(&mut channel)?;
+
+    channel.set_key(code_key(code, &client_nonce, &server.nonce)?)?;
+    let client_secret = pair_secret(&get_encryption_key(app_handle)?, &client_nonce)?;
+    channel.send(&Message::PairSecret {
+        secret: encode(&client_secret),
+    })?;
+    // Only a device that knows the code can seal its answer
+    let server_secret = receive_pair_secret(&mut channel)?;
+
+    Ok(SyncPeer {
+        key: encode(&pair_key(
+            &client_secret,
+            &server_secret,
+            &local.device_id,
+            &server.device_id,
+        )?),
+        device_id: server.device_id,
+        name: server.name,
+        host: host.to_string(),
+        port: server.port,
+        paired_at: now_millis(),
+        last_synced: None,
+    })
+}
+
+// Exchange history with a paired device at host:port
+pub fn sync_with(
+    app_handle: &AppHandle,
+    local: &LocalDevice,
+    peer: &SyncPeer,
+    host: &str,
+    port: u16,
+) -> Result<ClipboardSyncResult, String> {
+    let mut channel = connect(host, port)?;
+    let client_nonce = random_nonce();
+    send_hello(&mut channel, local, &client_nonce, false)?;
+    let server = receive_hello(&mut channel)?;
+    if server.device_id != peer.device_id {
+        return Err(format!("{} is a different device now", host));
+    }
+    channel.set_key(session_key(
+        &peer.pair_key()?,
+        &client_nonce,
+        &server.nonce,
+    )?)?;
+
+    let history_store = app_handle
+        .try_state::<ClipboardHistoryStore>()
+        .ok_or_else(|| "Clipboard history store is not available".to_string())?;
+    let local_items = syncable_items(&history_store)?;
+
+    channel.send(&manifest(&history_store, &local_items)?)?;
+    let remote = receive_manifest(&mut channel)?;
+    let sent = send_items(&mut channel, app_handle, local_items, &remote.entries)?;
+    let received = receive_items(&mut channel, app_handle, &remote.entries)?;
+
+    merge_received(
+        app_handle,
+        &history_store,
+        peer,
+        received,
+        remote.deleted,
+        sent,
+    )
+}
+
+// Answer a connection from another device, either a pairing attempt or a sync
+pub fn serve(app_handle: &AppHandle, local: &LocalDevice, stream: TcpStream) -> Result<(), String> {
+    let peer_host = stream
+        .peer_addr()
+        .map(|address| address.ip().to_string())
+        .map_err(|e| format!("Failed to get sync peer address: {}", e))?;
+    let mut channel = open_channel(stream, Side::Server)?;
+    let client = receive_hello(&mut channel)?;
+    let sync_state = app_handle
+        .try_state::<ClipboardSyncState>()
+        .ok_or_else(|| "Clipboard sync is not available".to_string())?;
+
+    if client.pairing {
+        let code = match sync_state.pairing_code() {
+            Some(code) => code,
+            None => {
+                channel.send_error("This device is not waiting to be paired");
+                return Err(format!("Unexpected pairing request from {}", peer_host));
+            }
+        };
+
+        let server_nonce = random_nonce();
+        send_hello(&mut channel, local, &server_nonce, true)?;
+        channel.set_key(code_key(&code, &client.nonce, &server_nonce)?)?;
+
+        // Only a secret sealed with another code is a wrong guess, a dropped
+        // connection doesn't use up an attempt
+        let client_secret = match try_receive_pair_secret(&mut channel) {
+            Ok(secret) => secret,
+            Err(ReceiveError::Undecryptable) => {
+                sync_state.pairing_failed(MAX_PAIRING_ATTEMPTS);
+                channel.send_error("Wrong pairing code");
+                return Err(format!("Pairing with {} failed: wrong code", peer_host));
+            }
+            Err(ReceiveError::Failed(e)) => {
+                return Err(format!("Pairing with {} failed: {}", peer_host, e));
+            }
+        };
+
+        let server_secret = pair_secret(&get_encryption_key(app_handle)?, &server_nonce)?;
+        channel.send(&Message::PairSecret {
+            secret: encode(&server_secret),
+        })?;
+
+        sync_state.paired(
+            app_handle,
+            SyncPeer {
+                key: encode(&pair_key(
+                    &client_secret,
+                    &server_secret,
+                    &client.device_id,
+                    &local.device_id,
+                )?),
+                device_id: client.device_id,
+                name: client.name,
+                host: peer_host,
+                port: client.port,
+                paired_at: now_millis(),
+                last_synced: None,
+            },
+        )?;
+        return Ok(());
+    }
+
+    let mut peer = match sync_state.peer(app_handle, &client.device_id)? {
+        Some(peer) => peer,
+        None => {
+            channel.send_error("This device is not paired with yours");
+            return Err(format!("Sync request from unpaired device {}", peer_host));
+        }
+    };
+
+    let server_nonce = random_nonce();
+    send_hello(&mut channel, local, &server_nonce, false)?;
+    channel.set_key(session_key(
+        &peer.pair_key()?,
+        &client.nonce,
+        &server_nonce,
+    )?)?;
+
+    let history_store = app_handle
+        .try_state::<ClipboardHistoryStore>()
+        .ok_or_else(|| "Clipboard history store is not available".to_string())?;
+    let local_items = syncable_items(&history_store)?;
+
+    let remote = receive_manifest(&mut channel)?;
+    channel.send(&manifest(&history_store, &local_items)?)?;
+    let received = receive_items(&mut channel, app_handle, &remote.entries)?;
+    let sent = send_items(&mut channel, app_handle, local_items, &remote.entries)?;
+
+    // The device may have moved since it was paired
+    peer.host = peer_host;
+    peer.port = client.port;
+    merge_received(
+        app_handle,
+        &history_store,
+        &peer,
+        received,
+        remote.deleted,
+        sent,
+    )
+    .map(|_| ())
+}
+
+// Sensitive and expiring items never leave the device
+fn syncable_items(history_store: &ClipboardHistoryStore) -> Result<Vec<ClipboardItem>, String> {
+    history_store.with_items(|items| {
+        items
+            .iter()
+            .filter(|item| is_syncable(item))
+            .cloned()
+            .collect()
+    })
+}
+
+fn is_syncable(item: &ClipboardItem) -> bool {
+    item.sensitive.is_none() && item.expires_at.is_none()
+}
+
+fn manifest(
+    history_store: &ClipboardHistoryStore,
+    items: &[ClipboardItem],
+) -> Result<Message, String> {
+    Ok(Message::Manifest {
+        entries: items.iter().filter_map(manifest_entry).collect(),
+        deleted: history_store.shared_deletions()?,
+    })
+}
+
+fn receive_manifest(channel: &mut Channel) -> Result<RemoteManifest, String> {
+    match channel.receive()? {
+        Message::Manifest { entries, deleted } => Ok(RemoteManifest {
+            entries: entries
+                .into_iter()
+                .map(|entry| (entry.hash.clone(), entry))
+                .collect(),
+            deleted,
+        }),
+        _ => Err("Expected a sync manifest".to_string()),
+    }
+}
+
+// Send what the other side is missing. Content it already has only needs the
+// newer metadata, not the image files.
+fn send_items(
+    channel: &mut Channel,
+    app_handle: &AppHandle,
+    items: Vec<ClipboardItem>,
+    remote: &HashMap<String, ManifestEntry>,
+) -> Result<usize, String> {
+    let mut sent = 0;
+    for item in items {
+        let hash = match item_content_hash(&item) {
+            Some(hash) => hash,
+            None => continue,
+        };
+        let bundle_item = match remote.get(&hash) {
+            None => match pack_item(app_handle, item) {
+                Ok(bundle_item) => bundle_item,
+                Err(e) => {
+                    eprintln!("Skipping clipboard item in sync: {}", e);
+                    continue;
+                }
+            },
+            Some(entry) if is_ahead_of(&item, entry) => BundleItem {
+                item,
+                image_png: None,
+                thumbnail_png: None,
+            },
+            Some(_) => continue,
+        };
+
+        channel.send(&Message::Item {
+            item: Box::new(bundle_item),
+        })?;
+        sent += 1;
+    }
+
+    channel.send(&Message::ItemsEnd)?;
+    Ok(sent)
+}
+
+fn receive_items(
+    channel: &mut Channel,
+    app_handle: &AppHandle,
+    remote: &HashMap<String, ManifestEntry>,
+) -> Result<Received, String> {
+    let mut received = Received {
+        items: Vec::new(),
+        updates: Vec::new(),
+    };
+
+    loop {
+        let bundle_item = match channel.receive()? {
+            Message::Item { item } => *item,
+            Message::ItemsEnd => return Ok(received),
+            _ => return Err("Expected a clipboard item".to_string()),
+        };
+        // Only content the other side listed is accepted
+        let listed =
+            item_content_hash(&bundle_item.item).is_some_and(|hash| remote.contains_key(&hash));
+        if !is_syncable(&bundle_item.item) || !listed {
+            continue;
+        }
+
+        if bundle_item.item.image_data.is_some() && bundle_item.image_png.is_none() {
+            received.updates.push(bundle_item.item);
+            continue;
+        }
+
+        match unpack_item(app_handle, bundle_item) {
+            Ok(item) => received.items.push(item),
+            Err(e) => eprintln!("Skipping synced clipboard item: {}", e),
+        }
+    }
+}
+
+fn merge_received(
+    app_handle: &AppHandle,
+    history_store: &ClipboardHistoryStore,
+    peer: &SyncPeer,
+    received: Received,
+    remote_deleted: Vec<DeletedContent>,
+    sent: usize,
+) -> Result<ClipboardSyncResult, String> {
+    // Updates point at the other device's image files, they must not be
+    // added if the image was deleted here in the meantime
+    let stored: HashSet<String> =
+        history_store.with_items(|items| items.iter().filter_map(item_content_hash).collect())?;
+    let mut items = received.items;
+    items.extend(
+        received
+            .updates
+            .into_iter()
+            .filter(|item| item_content_hash(item).is_some_and(|hash| stored.contains(&hash))),
+    );
+
+    let merged = history_store.merge_synced(items, remote_deleted)?;
+    emit_removed(app_handle, &merged.removed);
+
+    let result = ClipboardSyncResult {
+        device_id: peer.device_id.clone(),
+        name: peer.name.clone(),
+        added: merged.added,
+        merged: merged.merged,
+        sent,
+        error: None,
+    };
+    if result.added > 0 || result.merged > 0 || !merged.removed.is_empty() {
+        if let Err(e) = app_handle.emit_all(CLIPBOARD_SYNCED_EVENT, result.clone()) {
+            eprintln!("Failed to emit clipboard sync: {}", e);
+        }
+    }
+
+    if let Some(sync_state) = app_handle.try_state::<ClipboardSyncState>() {
+        sync_state.synced(app_handle, peer)?;
+    }
+    Ok(result)
+}
@@ -0,0 +1,767 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashSet;
+use tauri::State;
+
+use super::content_hash::{item_content_hash, text_content_hash};
+use super::content_kind::{convert_color, ColorFormat};
+use super::formats::restore_plain_text;
+use super::history_store::{ClipboardHistoryStore, ClipboardItemUpdate};
+use super::settings::load_clipboard_settings;
+use super::storage::ClipboardItem;
+
+const INDENT: &str = "  ";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TextTransform {
+    Uppercase,
+    Lowercase,
+    TitleCase,
+    SentenceCase,
+    CamelCase,
+    SnakeCase,
+    KebabCase,
+    // Trailing whitespace of every line and blank lines around the text
+    Trim,
+    // Indentation shared by all lines
+    Dedent,
+    SortLines,
+    // Keeps the first occurrence of every line
+    DedupeLines,
+    JsonPretty,
+    JsonMinify,
+    XmlPretty,
+    XmlMinify,
+    Base64Encode,
+    Base64Decode,
+    UrlEncode,
+    UrlDecode,
+    Sha256,
+    Sha512,
+    // Contents of a JSON string literal
+    EscapeJson,
+    UnescapeJson,
+    EscapeHtml,
+    UnescapeHtml,
+    // A CSS color in another notation
+    ColorHex,
+    ColorRgb,
+    ColorHsl,
+}
+
+// What to do with the transformed text
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformTarget {
+    // Put it on the system clipboard, the monitor records it as a new entry
+    Copy,
+    // Overwrite the text of the history entry it came from
+    Replace,
+}
+
+#[derive(Serialize)]
+pub struct TransformResult {
+    pub text: String,
+    // The updated entry when the result replaced it in the history
+    pub item: Option<ClipboardItem>,
+}
+
+impl TextTransform {
+    pub fn apply(self, text: &str) -> Result<String, String> {
+        Ok(match self {
+            TextTransform::Uppercase => text.to_uppercase(),
+            TextTransform::Lowercase => text.to_lowercase(),
+            TextTransform::TitleCase => title_case(text),
+            TextTransform::SentenceCase => sentence_case(text),
+            TextTransform::CamelCase => camel_case(text),
+            TextTransform::SnakeCase => split_words(text).join("_"),
+            TextTransform::KebabCase => split_words(text).join("-"),
+            TextTransform::Trim => trim_lines(text),
+            TextTransform::Dedent => dedent(text),
+            TextTransform::SortLines => {
+                let mut lines: Vec<&str> = text.lines().collect();
+                lines.sort_unstable();
+                lines.join("\n")
+            }
+            TextTransform::DedupeLines => {
+                let mut seen = HashSet::new();
+                text.lines()
+                    .filter(|line| seen.insert(*line))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            TextTransform::JsonPretty => format_json(text, true)?,
+            TextTransform::JsonMinify => format_json(text, false)?,
+            TextTransform::XmlPretty => format_xml(text, true)?,
+            TextTransform::XmlMinify => format_xml(text, false)?,
+            TextTransform::Base64Encode => STANDARD.encode(text),
+            TextTransform::Base64Decode => {
+                let compact: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+                let bytes = STANDARD
+                    .decode(compact)
+                    .map_err(|e| format!("Failed to decode Base64: {}", e))?;
+                String::from_utf8(bytes)
+                    .map_err(|_| "Decoded Base64 is not valid text".to_string())?
+            }
+            TextTransform::UrlEncode => urlencoding::encode(text).into_owned(),
+            TextTransform::UrlDecode => urlencoding::decode(text)
+                .map_err(|e| format!("Failed to decode URL: {}", e))?
+                .into_owned(),
+            TextTransform::Sha256 => format!("{:x}", Sha256::digest(text.as_bytes())),
+            TextTransform::Sha512 => format!("{:x}", Sha512::digest(text.as_bytes())),
+            TextTransform::EscapeJson => {
+                let quoted = serde_json::to_string(text)
+                    .map_err(|e| format!("Failed to escape text: {}", e))?;
+                quoted[1..quoted.len() - 1].to_string()
+            }
+            TextTransform::UnescapeJson => {
+                let trimmed = text.trim();
+                let quoted =
+                    if trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"') {
+                        trimmed.to_string()
+                    } else {
+                        format!("\"{}\"", text)
+                    };
+                serde_json::from_str::<String>(&quoted)
+                    .map_err(|e| format!("Failed to unescape text: {}", e))?
+            }
+            TextTransform::EscapeHtml => escape_html(text),
+            TextTransform::UnescapeHtml => unescape_html(text),
+            TextTransform::ColorHex => convert_color(text, ColorFormat::Hex)?,
+            TextTransform::ColorRgb => convert_color(text, ColorFormat::Rgb)?,
+            TextTransform::ColorHsl => convert_color(text, ColorFormat::Hsl)?,
+        })
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_uppercase()
+            .chain(chars.flat_map(char::to_lowercase))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+// Capitalize every word, keeping the whitespace between them
+fn title_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_whitespace() {
+            result.push_str(&capitalize(&word));
+            word.clear();
+            result.push(c);
+        } else {
+            word.push(c);
+        }
+    }
+    result.push_str(&capitalize(&word));
+    result
+}
+
+// Lowercase everything except the first letter of each sentence
+fn sentence_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut sentence_start = true;
+    for c in text.chars() {
+        if sentence_start && c.is_alphanumeric() {
+            result.extend(c.to_uppercase());
+            sentence_start = false;
+        } else {
+            result.extend(c.to_lowercase());
+        }
+        if matches!(c, '.' | '!' | '?' | '\n') {
+            sentence_start = true;
+        }
+    }
+    result
+}
+
+// Lowercase words of an identifier or phrase, split at separators and camel case humps
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    for segment in text.split(|c: char| !c.is_alphanumeric()) {
+        let chars: Vec<char> = segment.chars().collect();
+        let mut word = String::new();
+        for (index, &c) in chars.iter().enumerate() {
+            let hump = c.is_uppercase()
+                && index > 0
+                && (chars[index - 1].is_lowercase()
+                    || chars[index - 1].is_ascii_digit()
+                    // The last capital of an acronym starts the next word, as in "HTTPServer"
+                    || (chars[index - 1].is_uppercase()
+                        && chars.get(index + 1).is_some_and(|next| next.is_lowercase())));
+            if hump && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.extend(c.to_lowercase());
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn camel_case(text: &str) -> String {
+    split_words(text)
+        .iter()
+        .enumerate()
+        .map(|(index, word)| {
+            if index == 0 {
+                word.clone()
+            } else {
+                capitalize(word)
+            }
+        })
+        .collect()
+}
+
+fn trim_lines(text: &str) -> String {
+    text.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or_else(|| line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Reformat JSON without parsing it into a map, so key order and number
+// formatting survive. Validated with serde_json first.
+fn format_json(text: &str, pretty: bool) -> Result<String, String> {
+    serde_json::from_str::<serde::de::IgnoredAny>(text)
+        .map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let mut result = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = text.chars().peekable();
+
+    let newline = |result: &mut String, depth: usize| {
+        result.push('\n');
+        for _ in 0..depth {
+            result.push_str(INDENT);
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+
+        match c {
+            c if c.is_whitespace() => {}
+            '"' => {
+                in_string = true;
+                result.push(c);
+            }
+            '{' | '[' => {
+                result.push(c);
+                while chars.peek().is_some_and(|next| next.is_whitespace()) {
+                    chars.next();
+                }
+                // Empty containers stay on one line
+                if matches!(chars.peek(), Some('}') | Some(']')) {
+                    result.extend(chars.next());
+                } else {
+                    depth += 1;
+                    if pretty {
+                        newline(&mut result, depth);
+                    }
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                if pretty {
+                    newline(&mut result, depth);
+                }
+                result.push(c);
+            }
+            ',' => {
+                result.push(c);
+                if pretty {
+                    newline(&mut result, depth);
+                }
+            }
+            ':' => result.push_str(if pretty { ": " } else { ":" }),
+            c => result.push(c),
+        }
+    }
+
+    Ok(result)
+}
+
+enum XmlToken<'a> {
+    Open(&'a str, &'a str),
+    Close(&'a str, &'a str),
+    // Self-closing tags, comments, CDATA, declarations and processing instructions
+    Standalone(&'a str),
+    Text(&'a str),
+}
+
+fn tag_name(tag: &str) -> &str {
+    tag.trim_start_matches("</")
+        .trim_start_matches('<')
+        .split(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .next()
+        .unwrap_or("")
+}
+
+fn tokenize_xml(text: &str) -> Result<Vec<XmlToken<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            tokens.push(XmlToken::Text(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+
+        let terminator = if rest.starts_with("<!--") {
+            Some("-->")
+        } else if rest.starts_with("<![CDATA[") {
+            Some("]]>")
+        } else {
+            None
+        };
+        let end = match terminator {
+            Some(terminator) => rest.find(terminator).map(|index| index + terminator.len()),
+            None => tag_end(rest),
+        }
+        .ok_or_else(|| "Invalid XML: unterminated tag".to_string())?;
+
+        let tag = &rest[..end];
+        tokens.push(
+            if terminator.is_some()
+                || tag.starts_with("<?")
+                || tag.starts_with("<!")
+                || tag.ends_with("/>")
+            {
+                XmlToken::Standalone(tag)
+            } else if tag.starts_with("</") {
+                XmlToken::Close(tag, tag_name(tag))
+            } else {
+                XmlToken::Open(tag, tag_name(tag))
+            },
+        );
+        rest = &rest[end..];
+    }
+
+    Ok(tokens)
+}
+
+// End of a tag starting at the beginning of `text`, skipping `>` inside quoted attributes
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(open), c) if c == open => quote = None,
+            (None, '>') => return Some(index + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+// Indent nested elements, or drop the whitespace between tags when not pretty.
+// Elements with only text in them stay on one line.
+fn format_xml(text: &str, pretty: bool) -> Result<String, String> {
+    let tokens = tokenize_xml(text.trim())?;
+    let mut lines: Vec<String> = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+    let mut index = 0;
+
+    let push = |lines: &mut Vec<String>, depth: usize, line: String| {
+        let indent = if pretty {
+            INDENT.repeat(depth)
+        } else {
+            String::new()
+        };
+        lines.push(format!("{}{}", indent, line));
+    };
+
+    while index < tokens.len() {
+        match &tokens[index] {
+            XmlToken::Open(tag, name) => {
+                // <a>text</a> and <a></a>
+                let inline = match (tokens.get(index + 1), tokens.get(index + 2)) {
+                    (Some(XmlToken::Close(close, close_name)), _) if close_name == name => {
+                        Some((format!("{}{}", tag, close), 2))
+                    }
+                    (Some(XmlToken::Text(text)), Some(XmlToken::Close(close, close_name)))
+                        if close_name == name =>
+                    {
+                        let text = if pretty { text.trim() } else { text };
+                        Some((format!("{}{}{}", tag, text, close), 3))
+                    }
+                    _ => None,
+                };
+
+                match inline {
+                    Some((line, consumed)) => {
+                        push(&mut lines, open.len(), line);
+                        index += consumed;
+                        continue;
+                    }
+                    None => {
+                        push(&mut lines, open.len(), tag.to_string());
+                        open.push(name);
+                    }
+                }
+            }
+            XmlToken::Close(tag, name) => match open.pop() {
+                Some(expected) if expected == *name => {
+                    push(&mut lines, open.len(), tag.to_string())
+                }
+                Some(expected) => {
+                    return Err(format!(
+                        "Invalid XML: expected </{}>, found {}",
+                        expected, tag
+                    ))
+                }
+                None => return Err(format!("Invalid XML: unexpected {}", tag)),
+            },
+            XmlToken::Standalone(tag) => push(&mut lines, open.len(), tag.to_string()),
+            XmlToken::Text(text) => {
+                if !text.trim().is_empty() {
+                    push(&mut lines, open.len(), text.trim().to_string());
+                }
+            }
+        }
+        index += 1;
+    }
+
+    if let Some(unclosed) = open.last() {
+        return Err(format!("Invalid XML: <{}> is never closed", unclosed));
+    }
+
+    Ok(lines.join(if pretty { "\n" } else { "" }))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+// Named entities for the escaped characters plus numeric ones, unknown entities stay as they are
+fn unescape_html(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end + 1))
+        });
+
+        match decoded {
+            Some((c, length)) => {
+                result.push(c);
+                rest = &rest[length..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+// Transform the text of a history entry, or of the current clipboard when no
+// entry is given. Replacing the current clipboard updates the newest entry
+// holding the same text.
+#[tauri::command]
+pub fn transform_clipboard_text(
+    transform: TextTransform,
+    target: TransformTarget,
+    item_id: Option<u64>,
+    app_handle: tauri::AppHandle,
+    history_store: State<'_, ClipboardHistoryStore>,
+) -> Result<TransformResult, String> {
+    let (text, item_id) = match item_id {
+        Some(item_id) => {
+            let item = history_store.get(item_id)?;
+            match (item.content_type.as_str(), item.text) {
+                ("text", Some(text)) => (text, Some(item_id)),
+                _ => return Err("Only text clipboard items can be transformed".to_string()),
+            }
+        }
+        None => {
+            let text = arboard::Clipboard::new()
+                .map_err(|e| format!("Failed to create clipboard: {}", e))?
+                .get_text()
+                .map_err(|e| format!("Failed to get clipboard text: {}", e))?;
+            let hash = text_content_hash(&text);
+            let item_id = history_store.with_items(|items| {
+                items
+                    .iter()
+                    .find(|item| {
+                        item.content_type == "text"
+                            && item_content_hash(item).as_deref() == Some(hash.as_str())
+                    })
+                    .map(|item| item.id)
+            })?;
+            (text, item_id)
+        }
+    };
+
+    let result = transform.apply(&text)?;
+
+    let item = match target {
+        TransformTarget::Copy => {
+            restore_plain_text(&result)?;
+            None
+        }
+        TransformTarget::Replace => {
+            let item_id =
+                item_id.ok_or_else(|| "The clipboard text is not in the history".to_string())?;
+            Some(
+                history_store.update(
+                    item_id,
+                    ClipboardItemUpdate {
+                        text: Some(result.clone()),
+                        sensitive: load_clipboard_settings(&app_handle)
+                            .sensitive
+                            .classify_edit(&result)?,
+                        ..Default::default()
+                    },
+                )?,
+            )
+        }
+    };
+
+    Ok(TransformResult { text: result, item })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(transform: TextTransform, text: &str) -> String {
+        transform.apply(text).unwrap()
+    }
+
+    #[test]
+    fn identifiers_split_at_separators_humps_and_acronyms() {
+        let cases = [
+            ("HTTPServer", vec!["http", "server"]),
+            ("parseHTTPResponse", vec!["parse", "http", "response"]),
+            ("getUserID", vec!["get", "user", "id"]),
+            (
+                "snake_case-and kebab",
+                vec!["snake", "case", "and", "kebab"],
+            ),
+            ("version2Update", vec!["version2", "update"]),
+            ("  ", vec![]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(split_words(text), expected, "{}", text);
+        }
+    }
+
+    #[test]
+    fn case_transforms() {
+        let cases = [
+            (
+                TextTransform::CamelCase,
+                "HTTPServer config",
+                "httpServerConfig",
+            ),
+            (
+                TextTransform::SnakeCase,
+                "HTTPServer config",
+                "http_server_config",
+            ),
+            (
+                TextTransform::KebabCase,
+                "myVariableName",
+                "my-variable-name",
+            ),
+            (
+                TextTransform::TitleCase,
+                "hello  wORLD\nagain",
+                "Hello  World\nAgain",
+            ),
+            (
+                TextTransform::SentenceCase,
+                "HELLO THERE. how ARE you?\nfine",
+                "Hello there. How are you?\nFine",
+            ),
+        ];
+        for (transform, text, expected) in cases {
+            assert_eq!(apply(transform, text), expected, "{:?}", transform);
+        }
+    }
+
+    #[test]
+    fn line_transforms() {
+        assert_eq!(apply(TextTransform::Trim, "\n\n  a  \nb\t\n\n"), "  a\nb");
+        assert_eq!(
+            apply(TextTransform::Dedent, "    if x:\n\n      y()\n    z"),
+            "if x:\n\n  y()\nz"
+        );
+        assert_eq!(apply(TextTransform::SortLines, "b\nc\na"), "a\nb\nc");
+        assert_eq!(
+            apply(TextTransform::DedupeLines, "b\na\nb\na\nc"),
+            "b\na\nc"
+        );
+    }
+
+    #[test]
+    fn json_keeps_key_order_and_number_formatting() {
+        let text = r#"{"b": 1.50, "a": [1, {}], "s": "x, {y}: \"z\""}"#;
+        assert_eq!(
+            apply(TextTransform::JsonPretty, text),
+            "{\n  \"b\": 1.50,\n  \"a\": [\n    1,\n    {}\n  ],\n  \"s\": \"x, {y}: \\\"z\\\"\"\n}"
+        );
+        assert_eq!(
+            apply(TextTransform::JsonMinify, text),
+            r#"{"b":1.50,"a":[1,{}],"s":"x, {y}: \"z\""}"#
+        );
+        assert!(TextTransform::JsonPretty.apply("{\"a\": }").is_err());
+    }
+
+    #[test]
+    fn xml_nests_elements_and_keeps_text_inline() {
+        let text = "<?xml version=\"1.0\"?><a x=\"1>2\"><b>text</b><c/><!-- note --><d></d></a>";
+        assert_eq!(
+            apply(TextTransform::XmlPretty, text),
+            "<?xml version=\"1.0\"?>\n<a x=\"1>2\">\n  <b>text</b>\n  <c/>\n  <!-- note -->\n  <d></d>\n</a>"
+        );
+        assert_eq!(
+            apply(TextTransform::XmlMinify, "<a>\n  <b> text </b>\n</a>"),
+            "<a><b> text </b></a>"
+        );
+    }
+
+    #[test]
+    fn malformed_xml_is_rejected() {
+        let cases = [
+            ("<a><b></b>", "Invalid XML: <a> is never closed"),
+            ("<a></b>", "Invalid XML: expected </a>, found </b>"),
+            ("</a>", "Invalid XML: unexpected </a>"),
+            ("<a x=\"1>", "Invalid XML: unterminated tag"),
+            ("<a><!-- open", "Invalid XML: unterminated tag"),
+        ];
+        for (text, error) in cases {
+            assert_eq!(TextTransform::XmlPretty.apply(text), Err(error.to_string()));
+        }
+    }
+
+    #[test]
+    fn html_escapes_round_trip() {
+        let text = "<a href=\"x\">Tom & Jerry's</a>";
+        let escaped = apply(TextTransform::EscapeHtml, text);
+        assert_eq!(
+            escaped,
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;"
+        );
+        assert_eq!(apply(TextTransform::UnescapeHtml, &escaped), text);
+    }
+
+    #[test]
+    fn html_unescape_decodes_numeric_entities_and_keeps_unknown_ones() {
+        let cases = [
+            ("&#65;&#x42;&#X43;", "ABC"),
+            ("a&nbsp;b &apos;c&apos;", "a\u{a0}b 'c'"),
+            ("&copy; &unknown; & alone", "&copy; &unknown; & alone"),
+            ("&#xD800;", "&#xD800;"),
+            ("&amp;lt;", "&lt;"),
+            ("&averyveryverylongname;", "&averyveryverylongname;"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(
+                apply(TextTransform::UnescapeHtml, text),
+                expected,
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        let text = "a b/ü\n\"q\"";
+        for (encode, decode) in [
+            (TextTransform::Base64Encode, TextTransform::Base64Decode),
+            (TextTransform::UrlEncode, TextTransform::UrlDecode),
+            (TextTransform::EscapeJson, TextTransform::UnescapeJson),
+        ] {
+            assert_eq!(apply(decode, &apply(encode, text)), text, "{:?}", encode);
+        }
+        assert_eq!(apply(TextTransform::Base64Decode, "aGVs\nbG8="), "hello");
+        assert_eq!(apply(TextTransform::UnescapeJson, "\"a\\tb\""), "a\tb");
+        assert!(TextTransform::Base64Decode.apply("not base64!").is_err());
+        assert!(TextTransform::Base64Decode.apply("/w==").is_err());
+    }
+
+    #[test]
+    fn hashes_are_lowercase_hex() {
+        assert_eq!(
+            apply(TextTransform::Sha256, "abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(apply(TextTransform::Sha512, "").len(), 128);
+    }
+}
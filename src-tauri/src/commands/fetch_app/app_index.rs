@@ -1,21 +1,25 @@
 use uuid::Uuid;
 
 use crate::commands::fetch_app::icons::extract_icon;
-use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, RwLock};
 use std::thread;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use crate::commands::fetch_app::{
     app_registry::read_installed_apps,
-    models::{AppIndex, AppIndexState},
+    models::{AppIndex, AppIndexState, AppIndexUpdate},
 };
+use crate::persistence::background_writer::{BackgroundWriter, DebounceTiming};
+use crate::persistence::files::write_atomic;
 
 use super::app_registry::parse_shortcut;
 use super::categorization::categorize_app;
 use super::models::AppInfo;
+use super::search_index::SearchIndex;
 
 // Function to get the index file path
 pub fn get_index_path() -> PathBuf {
@@ -24,7 +28,14 @@ pub fn get_index_path() -> PathBuf {
     path
 }
 
-pub fn add_manual_app(name: String, path: String) -> Result<AppInfo, String> {
+// Add an app by hand, or update the entry with the same path or name. The
+// in-memory index is changed and the index writer persists it, so neither
+// launches recorded since the last write nor a running refresh lose it.
+pub fn add_manual_app(
+    app_index_state: &AppIndexState,
+    name: String,
+    path: String,
+) -> Result<AppInfo, String> {
     // Validate basic path existence - this is a bit tricky with args
     let path_parts: Vec<&str> = path.split_whitespace().collect();
     let exe_path = if path.to_lowercase().ends_with(".lnk") {
@@ -37,81 +48,50 @@ pub fn add_manual_app(name: String, path: String) -> Result<AppInfo, String> {
         return Err(format!("Executable path does not exist: {}", exe_path));
     }
 
-    // Load the current app index
-    let mut index = load_app_index();
-
-    // Check for duplicates by path or name
-    let mut existing_id = None;
-    for (id, app) in &index.apps {
-        // Check if the path (without arguments) or the exact path match
-        let app_exe_path = app
-            .path
-            .split_whitespace()
-            .next()
-            .unwrap_or("")
-            .trim_matches('"');
-        if app_exe_path == exe_path || app.path == path {
-            existing_id = Some(id.clone());
-            break;
-        }
-
-        // Optional: Also check by name if you want to prevent duplicate names
-        if app.name.to_lowercase() == name.to_lowercase() {
-            existing_id = Some(id.clone());
-            break;
-        }
-    }
-
     // Extract icon from the executable path
     let icon = extract_icon(&exe_path).unwrap_or_default();
 
     // Categorize the app
     let category = categorize_app(&exe_path, &name);
 
-    // If a duplicate exists, update that entry instead of creating a new one
-    if let Some(id) = existing_id {
+    let app_info = {
+        let mut index = app_index_state.index.write().unwrap();
+
+        // Check for duplicates by path or name
+        let existing = index.apps.values().find(|app| {
+            // Check if the path (without arguments) or the exact path match
+            let app_exe_path = app
+                .path
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .trim_matches('"');
+            app_exe_path == exe_path
+                || app.path == path
+                || app.name.to_lowercase() == name.to_lowercase()
+        });
+
+        // If a duplicate exists, update that entry instead of creating a new one
         let app_info = AppInfo {
-            id: id.clone(),
+            id: existing
+                .map(|app| app.id.clone())
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
             name,
             path, // Store the full path with arguments
             icon,
             category,
             // Preserve access statistics
-            last_accessed: index.apps.get(&id).and_then(|app| app.last_accessed),
-            access_count: index.apps.get(&id).map_or(0, |app| app.access_count),
+            last_accessed: existing.and_then(|app| app.last_accessed),
+            access_count: existing.map_or(0, |app| app.access_count),
         };
 
-        // Add the new app to the index
-        index.apps.insert(id, app_info.clone());
-
-        // Save the updated index
-        match save_app_index(&index) {
-            Ok(_) => println!("Successfully saved app index"),
-            Err(e) => println!("Error saving app index: {}", e),
-        }
-
-        return Ok(app_info);
-    }
-
-    // No duplicate found, generate a new UUID for the app
-    let id = Uuid::new_v4().to_string();
-
-    // Create the new app info
-    let app_info = AppInfo {
-        id: id.clone(),
-        name,
-        path, // Store the full path with arguments
-        icon,
-        category,
-        last_accessed: None,
-        access_count: 0,
+        index.apps.insert(app_info.id.clone(), app_info.clone());
+        app_info
     };
 
-    // Add the new app to the index
-    index.apps.insert(id, app_info.clone());
-
-    // Save the updated index
-    save_app_index(&index)?;
+    // Make the new entry searchable right away
+    rebuild_search_index(app_index_state);
+    app_index_state.index_writer.send(AppIndexUpdate::Edited);
 
     Ok(app_info)
 }
@@ -140,139 +120,184 @@ pub fn load_app_index() -> AppIndex {
     }
 }
 
-// Save the app index to disk
-pub fn save_app_index(index: &AppIndex) -> Result<(), String> {
-    let path = get_index_path();
-
-    // Ensure directory exists
-    if let Some(parent) = path.parent() {
-        match fs::create_dir_all(parent) {
-            Ok(_) => println!("Directory created or already exists"),
-            Err(e) => {
-                println!("Error creating directory: {}", e);
-                return Err(e.to_string());
-            }
-        }
-    }
-
-    let json = match serde_json::to_string_pretty(index) {
-        Ok(j) => j,
-        Err(e) => {
-            println!("Error serializing index to JSON: {}", e);
-            return Err(e.to_string());
-        }
-    };
-
-    match File::create(&path) {
-        Ok(mut file) => match file.write_all(json.as_bytes()) {
-            Ok(_) => println!("Successfully wrote index file"),
-            Err(e) => {
-                println!("Error writing to index file: {}", e);
-                return Err(e.to_string());
-            }
-        },
-        Err(e) => {
-            println!("Error creating index file: {}", e);
-            return Err(e.to_string());
-        }
-    }
-
-    // Add verification after write
-    match fs::read_to_string(&path) {
-        Ok(contents) => {
-            // Check if our file has valid JSON
-            match serde_json::from_str::<AppIndex>(&contents) {
-                Ok(read_index) => println!(
-                    "Verified file contains valid index with {} apps",
-                    read_index.apps.len()
-                ),
-                Err(e) => println!("Warning: File exists but contains invalid JSON: {}", e),
-            }
-        }
-        Err(e) => println!("Warning: Could not read back file: {}", e),
-    }
+fn serialize_app_index(index: &AppIndex) -> Result<Vec<u8>, String> {
+    serde_json::to_vec(index).map_err(|e| format!("Error serializing index to JSON: {}", e))
+}
 
-    Ok(())
+fn write_app_index_json(json: &[u8]) -> Result<(), String> {
+    write_atomic(&get_index_path(), json)
 }
 
-// Build app index from registry
-pub fn build_app_index(force: bool) -> Result<AppIndex, String> {
-    let mut index = load_app_index();
-    let now = SystemTime::now()
+fn now_secs() -> u64 {
+    SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap_or_default()
-        .as_secs();
-
-    // Save existing manual entries
-    let existing_apps = index.apps.clone();
+        .as_secs()
+}
 
-    // Only update if older than 1 hour
-    if !force && now - index.last_update < 3600 && !index.apps.is_empty() {
-        return Ok(index);
+// Scan for installed apps on top of `existing`. Apps that are found again keep
+// their ID and usage, entries that weren't found (e.g. manual ones) are kept.
+// The caller swaps it in and has the index writer persist it.
+fn rebuild_app_index(existing: AppIndex) -> Result<AppIndex, String> {
+    let mut existing_by_path: HashMap<String, AppInfo> = existing
+        .apps
+        .into_values()
+        .map(|app| (app.path.clone(), app))
+        .collect();
+
+    let mut apps: HashMap<String, AppInfo> = read_installed_apps()?
+        .into_values()
+        .map(|mut app| {
+            if let Some(known) = existing_by_path.remove(&app.path) {
+                app.id = known.id;
+                app.last_accessed = known.last_accessed;
+                app.access_count = known.access_count;
+            }
+            (app.id.clone(), app)
+        })
+        .collect();
+    for (_, app) in existing_by_path {
+        apps.insert(app.id.clone(), app);
     }
 
-    // Get installed apps from registry
-    let new_apps = read_installed_apps()?;
-
-    index.apps = new_apps;
+    Ok(AppIndex {
+        apps,
+        last_update: now_secs(),
+    })
+}
 
-    // Preserve existing entries that might have been added manually
-    for (id, app) in existing_apps {
-        // Only keep apps that aren't already in the new list
-        // We can check by path or some other identifier
-        if !index.apps.values().any(|a| a.path == app.path) {
-            index.apps.insert(id, app);
+// Launches recorded and apps added while a new index was being built only
+// exist in the current one, copy them over. The new index was built on top of
+// an earlier copy of the current one, so nothing in it was removed on purpose.
+fn carry_over_changes(new_index: &mut AppIndex, current: &AppIndex) {
+    for (id, current_app) in &current.apps {
+        match new_index.apps.get_mut(id) {
+            Some(app) => {
+                app.last_accessed = current_app.last_accessed;
+                app.access_count = current_app.access_count;
+            }
+            None => {
+                new_index.apps.insert(id.clone(), current_app.clone());
+            }
         }
     }
+}
 
-    index.last_update = now;
-    save_app_index(&index)?;
+// Swap in a freshly built index and rebuild the search index to match
+pub fn replace_app_index(app_index_state: &AppIndexState, mut new_index: AppIndex) {
+    let mut index = app_index_state.index.write().unwrap();
+    carry_over_changes(&mut new_index, &index);
 
-    Ok(index)
+    let search_index = SearchIndex::build(&new_index);
+    *index = new_index;
+    *app_index_state.search_index.write().unwrap() = search_index;
+}
+
+// Rebuild the search index after the in-memory AppIndex was modified in place
+pub fn rebuild_search_index(app_index_state: &AppIndexState) {
+    let index = app_index_state.index.read().unwrap();
+    *app_index_state.search_index.write().unwrap() = SearchIndex::build(&index);
 }
 
 pub fn refresh_app_index(app_index_state: &AppIndexState) {
-    let index_clone = app_index_state.index.clone();
+    let state = app_index_state.clone();
 
     // Launch background thread to rebuild index
     thread::spawn(move || {
-        if let Ok(new_index) = build_app_index(true) {
-            let mut index = index_clone.lock().unwrap();
-            *index = new_index;
-        } else {
-            println!("Background thread: Failed to build app index");
+        // Start from the in-memory index, the file may lag behind recent launches
+        let existing = state.index.read().unwrap().clone();
+
+        match rebuild_app_index(existing) {
+            Ok(new_index) => {
+                replace_app_index(&state, new_index);
+                // Changes carried over during the swap aren't on disk yet
+                state.index_writer.send(AppIndexUpdate::Edited);
+            }
+            Err(e) => println!("Background thread: Failed to build app index: {}", e),
         }
     });
 }
 
 // Initialize app index state
 pub fn init_app_index() -> AppIndexState {
-    let index = Arc::new(Mutex::new(AppIndex::default()));
-    let index_clone = index.clone();
+    let index = Arc::new(RwLock::new(AppIndex::default()));
+    let search_index = Arc::new(RwLock::new(SearchIndex::default()));
+    let index_writer = spawn_index_writer(index.clone(), search_index.clone());
+
+    let app_index_state = AppIndexState {
+        index,
+        search_index,
+        index_writer,
+    };
+    let state = app_index_state.clone();
 
     // Launch background thread to build index
     thread::spawn(move || {
-        if let Ok(new_index) = build_app_index(false) {
-            let mut index = index_clone.lock().unwrap();
-            *index = new_index;
+        let index = load_app_index();
+
+        // Only rescan if older than 1 hour
+        if now_secs().saturating_sub(index.last_update) < 3600 && !index.apps.is_empty() {
+            replace_app_index(&state, index);
+            return;
+        }
+
+        match rebuild_app_index(index) {
+            Ok(new_index) => {
+                replace_app_index(&state, new_index);
+                state.index_writer.send(AppIndexUpdate::Edited);
+            }
+            Err(e) => println!("Background thread: Failed to build app index: {}", e),
         }
     });
 
-    AppIndexState { index }
+    app_index_state
 }
 
-// Record app access
-pub fn record_app_access(index: &mut AppIndex, app_id: &str) {
-    if let Some(app) = index.apps.get_mut(app_id) {
-        app.access_count += 1;
-        app.last_accessed = Some(
-            SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs(),
-        );
-
-        // Save updated index
-        let _ = save_app_index(index);
+// Apply launches as they come in, but only write the index to disk once they
+// settle. Every write of the index goes through here.
+fn spawn_index_writer(
+    index: Arc<RwLock<AppIndex>>,
+    search_index: Arc<RwLock<SearchIndex>>,
+) -> BackgroundWriter<AppIndexUpdate> {
+    let persisted_index = index.clone();
+
+    BackgroundWriter::spawn(
+        "app-index",
+        DebounceTiming {
+            quiet: Duration::from_secs(2),
+            max_delay: Duration::from_secs(10),
+        },
+        move |updates: Vec<AppIndexUpdate>| {
+            let mut index = index.write().unwrap();
+            let mut search_index = search_index.write().unwrap();
+            for update in updates {
+                if let AppIndexUpdate::Launched {
+                    app_id,
+                    accessed_at,
+                } = update
+                {
+                    if record_app_access(&mut index, &app_id, accessed_at) {
+                        search_index.record_access(&app_id);
+                    }
+                }
+            }
+        },
+        move || {
+            // Serialize under the read lock, write to disk without holding it
+            let json = serialize_app_index(&persisted_index.read().unwrap())?;
+            write_app_index_json(&json)
+        },
+    )
+}
+
+// Record app access in memory. Returns false if the app is no longer indexed.
+pub fn record_app_access(index: &mut AppIndex, app_id: &str, accessed_at: u64) -> bool {
+    match index.apps.get_mut(app_id) {
+        Some(app) => {
+            app.access_count += 1;
+            app.last_accessed = Some(accessed_at);
+            true
+        }
+        None => false,
     }
 }
@@ -1,8 +1,9 @@
+use std::time::SystemTime;
 use tauri::{AppHandle, Manager, State};
 
 use crate::commands::fetch_app::{
-    app_index::record_app_access,
-    models::{AppIndexState, AppInfo},
+    models::{AppIndexState, AppIndexUpdate, AppInfo},
+    search_index::AppSearchResult,
 };
 
 use super::app_index::add_manual_app;
@@ -11,7 +12,7 @@ use super::app_index::add_manual_app;
 pub async fn get_index_status(
     app_index_state: State<'_, AppIndexState>,
 ) -> Result<serde_json::Value, String> {
-    let index = app_index_state.index.lock().unwrap();
+    let index = app_index_state.index.read().unwrap();
     let building = index.apps.is_empty() || index.last_update == 0;
 
     Ok(serde_json::json!({
@@ -26,35 +27,9 @@ pub async fn get_index_status(
 pub async fn search_apps(
     query: String,
     app_index_state: State<'_, AppIndexState>,
-) -> Result<Vec<AppInfo>, String> {
-    let index = app_index_state.index.lock().unwrap();
-    let query = query.to_lowercase();
-
-    let mut matching_apps: Vec<AppInfo> = index
-        .apps
-        .values()
-        .filter(|app| app.name.to_lowercase().contains(&query))
-        .cloned()
-        .collect();
-
-    // Sort by relevance (exact match first, then by access count)
-    matching_apps.sort_by(|a, b| {
-        let a_exact = a.name.to_lowercase() == query;
-        let b_exact = b.name.to_lowercase() == query;
-
-        if a_exact && !b_exact {
-            std::cmp::Ordering::Less
-        } else if !a_exact && b_exact {
-            std::cmp::Ordering::Greater
-        } else {
-            b.access_count.cmp(&a.access_count)
-        }
-    });
-
-    // Limit results
-    matching_apps.truncate(10);
-
-    Ok(matching_apps)
+) -> Result<Vec<AppSearchResult>, String> {
+    let search_index = app_index_state.search_index.read().unwrap();
+    Ok(search_index.search(&query, 10))
 }
 
 // Get recently used apps
@@ -62,7 +37,7 @@ pub async fn search_apps(
 pub async fn get_recent_apps(
     app_index_state: State<'_, AppIndexState>,
 ) -> Result<Vec<AppInfo>, String> {
-    let index = app_index_state.index.lock().unwrap();
+    let index = app_index_state.index.read().unwrap();
 
     let mut recent_apps: Vec<AppInfo> = index
         .apps
@@ -104,8 +79,12 @@ pub async fn get_recent_apps(
 }
 
 #[tauri::command]
-pub fn add_manual_application(name: String, path: String) -> Result<AppInfo, String> {
-    add_manual_app(name, path)
+pub fn add_manual_application(
+    name: String,
+    path: String,
+    app_index_state: State<'_, AppIndexState>,
+) -> Result<AppInfo, String> {
+    add_manual_app(&app_index_state, name, path)
 }
 
 #[tauri::command]
@@ -125,7 +104,7 @@ pub async fn open_app(
 
     // Get the app path and check if it's Discord
     let (path, is_discord) = {
-        let mut index = app_index_state.index.lock().unwrap();
+        let index = app_index_state.index.read().unwrap();
         let app = index
             .apps
             .get(&app_id)
@@ -136,11 +115,18 @@ pub async fn open_app(
         let is_discord =
             path.to_lowercase().contains("discord") && path.to_lowercase().contains("update.exe");
 
-        // Record this access
-        record_app_access(&mut index, &app_id);
         (path, is_discord)
     };
 
+    // Record this access without waiting for the index to be written
+    app_index_state.index_writer.send(AppIndexUpdate::Launched {
+        app_id: app_id.clone(),
+        accessed_at: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    });
+
     println!("Launching app at path: {}", path);
 
     // Use WinAPI on Windows, fallback to std::process on other platforms
@@ -4,6 +4,7 @@ pub mod categorization;
 pub mod commands;
 pub mod icons;
 pub mod models;
+pub mod search_index;
 
 // Re-export functions that are used in main.rs
 pub use app_index::init_app_index;
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, RwLock};
+
+use super::search_index::SearchIndex;
+use crate::persistence::background_writer::BackgroundWriter;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AppInfo {
@@ -13,12 +16,22 @@ pub struct AppInfo {
     pub access_count: u32,
 }
 
-#[derive(Default, Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, Clone)]
 pub struct AppIndex {
     pub apps: HashMap<String, AppInfo>,
     pub last_update: u64,
 }
 
+// Change to the index, applied and persisted by the index writer thread
+pub enum AppIndexUpdate {
+    Launched { app_id: String, accessed_at: u64 },
+    // Changed in memory already, only needs writing
+    Edited,
+}
+
+#[derive(Clone)]
 pub struct AppIndexState {
-    pub index: Arc<Mutex<AppIndex>>,
-}
\ No newline at end of file
+    pub index: Arc<RwLock<AppIndex>>,
+    pub search_index: Arc<RwLock<SearchIndex>>,
+    pub index_writer: BackgroundWriter<AppIndexUpdate>,
+}
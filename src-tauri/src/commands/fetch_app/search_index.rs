@@ -0,0 +1,536 @@
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Instant;
+
+use super::models::{AppIndex, AppInfo};
+
+// Longest n-gram stored in the index. Queries up to this length are answered
+// straight from the posting lists, longer ones intersect their trigrams.
+const MAX_GRAM: usize = 3;
+
+// Lightweight search result sent to the webview instead of a full AppInfo
+#[derive(Serialize, Clone, Debug)]
+pub struct AppSearchResult {
+    pub id: String,
+    pub name: String,
+    pub icon: String,
+    pub category: String,
+    pub score: u32,
+}
+
+struct IndexedApp {
+    id: String,
+    name: String,
+    icon: String,
+    category: String,
+    // Lowercased name with punctuation collapsed into single spaces
+    normalized: String,
+    access_count: u32,
+}
+
+// Where an n-gram occurs in a name, so short queries can be ranked without
+// scanning the name again
+const AT_START: u8 = 2;
+const AT_WORD_START: u8 = 1;
+const INSIDE_WORD: u8 = 0;
+
+#[derive(Clone, Copy)]
+struct Posting {
+    position: u32,
+    class: u8,
+}
+
+// Precomputed n-gram index over app names, rebuilt whenever the AppIndex changes
+#[derive(Default)]
+pub struct SearchIndex {
+    entries: Vec<IndexedApp>,
+    grams: HashMap<u64, Vec<Posting>>,
+    ids: HashMap<String, u32>,
+}
+
+// Lowercase and split on anything that isn't a letter or digit
+pub fn normalize(text: &str) -> String {
+    let mut normalized = String::with_capacity(text.len());
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+        normalized.extend(word.chars().flat_map(char::to_lowercase));
+    }
+    normalized
+}
+
+// Pack up to three chars into a single key (21 bits per char)
+fn pack_gram(chars: &[char]) -> u64 {
+    chars
+        .iter()
+        .fold(0u64, |key, c| (key << 21) | (*c as u64 & 0x1F_FFFF))
+}
+
+impl SearchIndex {
+    pub fn build(index: &AppIndex) -> Self {
+        Self::from_apps(index.apps.values())
+    }
+
+    pub fn from_apps<'a>(apps: impl Iterator<Item = &'a AppInfo>) -> Self {
+        let mut search_index = SearchIndex::default();
+
+        for app in apps {
+            let position = search_index.entries.len() as u32;
+            let normalized = normalize(&app.name);
+            let chars: Vec<char> = normalized.chars().collect();
+
+            for size in 1..=MAX_GRAM {
+                for (offset, window) in chars.windows(size).enumerate() {
+                    let class = if offset == 0 {
+                        AT_START
+                    } else if chars[offset - 1] == ' ' {
+                        AT_WORD_START
+                    } else {
+                        INSIDE_WORD
+                    };
+
+                    let postings = search_index.grams.entry(pack_gram(window)).or_default();
+                    // Entries are added in order, so only the last element can be a repeat
+                    match postings.last_mut() {
+                        Some(last) if last.position == position => {
+                            last.class = last.class.max(class)
+                        }
+                        _ => postings.push(Posting { position, class }),
+                    }
+                }
+            }
+
+            search_index.ids.insert(app.id.clone(), position);
+            search_index.entries.push(IndexedApp {
+                id: app.id.clone(),
+                name: app.name.clone(),
+                icon: app.icon.clone(),
+                category: app.category.clone(),
+                normalized,
+                access_count: app.access_count,
+            });
+        }
+
+        search_index
+    }
+
+    pub fn app_count(&self) -> usize {
+        self.entries.len()
+    }
+
+    // Keep ranking in sync with usage without a full rebuild
+    pub fn record_access(&mut self, app_id: &str) {
+        if let Some(position) = self.ids.get(app_id) {
+            self.entries[*position as usize].access_count += 1;
+        }
+    }
+
+    // Positions of the best matching apps together with their score
+    fn top_matches(&self, query: &str, limit: usize) -> Vec<(u32, u32)> {
+        let query = normalize(query);
+        if query.is_empty() || limit == 0 {
+            return Vec::new();
+        }
+
+        let chars: Vec<char> = query.chars().collect();
+        let mut hits: Vec<Hit> = if chars.len() <= MAX_GRAM {
+            // The posting list is already an exact substring match
+            self.grams
+                .get(&pack_gram(&chars))
+                .map(|postings| {
+                    postings
+                        .iter()
+                        .map(|posting| self.short_hit(posting, query.len()))
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            self.trigram_candidates(&chars)
+                .into_iter()
+                .filter(|position| self.entries[*position as usize].normalized.contains(&query))
+                .map(|position| self.hit(position, &query))
+                .collect()
+        };
+
+        // Only the top results need to be fully ordered
+        if hits.len() > limit {
+            hits.select_nth_unstable(limit - 1);
+            hits.truncate(limit);
+        }
+        hits.sort_unstable();
+
+        hits.into_iter()
+            .map(|hit| (hit.position, hit.score))
+            .collect()
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<AppSearchResult> {
+        self.top_matches(query, limit)
+            .into_iter()
+            .map(|(position, score)| {
+                let entry = &self.entries[position as usize];
+                AppSearchResult {
+                    id: entry.id.clone(),
+                    name: entry.name.clone(),
+                    icon: entry.icon.clone(),
+                    category: entry.category.clone(),
+                    score,
+                }
+            })
+            .collect()
+    }
+
+    // Intersect the posting lists of every trigram in the query, smallest first
+    fn trigram_candidates(&self, chars: &[char]) -> Vec<u32> {
+        let mut lists = Vec::new();
+        for window in chars.windows(MAX_GRAM) {
+            match self.grams.get(&pack_gram(window)) {
+                Some(postings) => lists.push(postings),
+                // A trigram nobody has means nothing can match
+                None => return Vec::new(),
+            }
+        }
+        lists.sort_by_key(|postings| postings.len());
+
+        let (smallest, rest) = match lists.split_first() {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+
+        smallest
+            .iter()
+            .map(|posting| posting.position)
+            .filter(|position| {
+                rest.iter().all(|postings| {
+                    postings
+                        .binary_search_by_key(position, |posting| posting.position)
+                        .is_ok()
+                })
+            })
+            .collect()
+    }
+
+    // Rank a match of a query short enough to be a single n-gram
+    fn short_hit(&self, posting: &Posting, query_len: usize) -> Hit {
+        let entry = &self.entries[posting.position as usize];
+
+        let score = match posting.class {
+            AT_START if entry.normalized.len() == query_len => 1000,
+            AT_START => 800,
+            AT_WORD_START => 600,
+            _ => 400,
+        };
+
+        Hit {
+            score,
+            access_count: entry.access_count,
+            name_len: entry.normalized.len() as u32,
+            position: posting.position,
+        }
+    }
+
+    fn hit(&self, position: u32, query: &str) -> Hit {
+        let entry = &self.entries[position as usize];
+        let normalized = entry.normalized.as_str();
+
+        let score = if normalized == query {
+            1000
+        } else if normalized.starts_with(query) {
+            800
+        } else if normalized
+            .match_indices(query)
+            .any(|(start, _)| normalized.as_bytes()[start - 1] == b' ')
+        {
+            600
+        } else {
+            400
+        };
+
+        Hit {
+            score,
+            access_count: entry.access_count,
+            name_len: normalized.len() as u32,
+            position,
+        }
+    }
+}
+
+// Ordering key of a match: higher score first, then most used, then shorter names
+#[derive(PartialEq, Eq)]
+struct Hit {
+    score: u32,
+    access_count: u32,
+    name_len: u32,
+    position: u32,
+}
+
+impl Ord for Hit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .score
+            .cmp(&self.score)
+            .then_with(|| other.access_count.cmp(&self.access_count))
+            .then_with(|| self.name_len.cmp(&other.name_len))
+            .then_with(|| self.position.cmp(&other.position))
+    }
+}
+
+impl PartialOrd for Hit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Built-in benchmark, run with `blazecast --bench-search`.
+// Returns true when every query stays under one millisecond on average.
+pub fn run_benchmark(entry_count: usize) -> bool {
+    const WORDS: [&str; 24] = [
+        "visual",
+        "studio",
+        "code",
+        "microsoft",
+        "word",
+        "excel",
+        "steam",
+        "discord",
+        "note",
+        "player",
+        "media",
+        "photo",
+        "editor",
+        "manager",
+        "terminal",
+        "chrome",
+        "fire",
+        "fox",
+        "spotify",
+        "zoom",
+        "teams",
+        "git",
+        "hub",
+        "desktop",
+    ];
+    const QUERIES: [&str; 8] = [
+        "c",
+        "co",
+        "cod",
+        "code",
+        "studio code",
+        "vis",
+        "microsoft word",
+        "zzzz",
+    ];
+    const ITERATIONS: u32 = 200;
+
+    // Deterministic pseudo-random names so runs are comparable
+    let mut seed: u64 = 0x2545_F491_4F6C_DD1D;
+    let mut next = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        seed
+    };
+
+    let apps: Vec<AppInfo> = (0..entry_count)
+        .map(|i| {
+            let word_count = 1 + (next() % 3) as usize;
+            let name = (0..word_count)
+                .map(|_| WORDS[(next() % WORDS.len() as u64) as usize])
+                .collect::<Vec<_>>()
+                .join(" ");
+            AppInfo {
+                id: format!("bench-{}", i),
+                name: format!("{} {}", name, i),
+                path: format!("C:\\Program Files\\Bench\\app{}.exe", i),
+                icon: String::new(),
+                category: "Applications".to_string(),
+                last_accessed: None,
+                access_count: (next() % 50) as u32,
+            }
+        })
+        .collect();
+
+    let build_start = Instant::now();
+    let search_index = SearchIndex::from_apps(apps.iter());
+    println!(
+        "Built search index for {} apps in {:.2} ms",
+        search_index.app_count(),
+        build_start.elapsed().as_secs_f64() * 1000.0
+    );
+
+    let mut all_fast = true;
+    for query in QUERIES {
+        let mut worst = 0f64;
+        let mut hit_count = 0;
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            let query_start = Instant::now();
+            hit_count = search_index.search(query, 10).len();
+            worst = worst.max(query_start.elapsed().as_secs_f64() * 1000.0);
+        }
+        let average = start.elapsed().as_secs_f64() * 1000.0 / ITERATIONS as f64;
+        let fast = average < 1.0;
+        all_fast &= fast;
+
+        println!(
+            "{:<16} avg {:>7.4} ms  worst {:>7.4} ms  {:>2} results  {}",
+            format!("\"{}\"", query),
+            average,
+            worst,
+            hit_count,
+            if fast { "ok" } else { "SLOW" }
+        );
+    }
+
+    all_fast
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str, name: &str, access_count: u32) -> AppInfo {
+        AppInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            path: format!("C:\\Apps\\{}.exe", id),
+            icon: String::new(),
+            category: "Applications".to_string(),
+            last_accessed: None,
+            access_count,
+        }
+    }
+
+    fn index(names: &[&str]) -> SearchIndex {
+        let apps: Vec<AppInfo> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| app(&i.to_string(), name, 0))
+            .collect();
+        SearchIndex::from_apps(apps.iter())
+    }
+
+    fn names(search_index: &SearchIndex, query: &str) -> Vec<String> {
+        search_index
+            .search(query, 10)
+            .into_iter()
+            .map(|result| result.name)
+            .collect()
+    }
+
+    fn candidates(search_index: &SearchIndex, query: &str) -> Vec<String> {
+        let chars: Vec<char> = normalize(query).chars().collect();
+        let mut names: Vec<String> = search_index
+            .trigram_candidates(&chars)
+            .into_iter()
+            .map(|position| search_index.entries[position as usize].name.clone())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn names_are_lowercased_and_split_on_punctuation() {
+        assert_eq!(normalize("Visual Studio Code"), "visual studio code");
+        assert_eq!(normalize("  Foo--Bar_baz (x64) "), "foo bar baz x64");
+        assert_eq!(normalize("ÄPFEL Café"), "äpfel café");
+        assert_eq!(normalize("--"), "");
+    }
+
+    #[test]
+    fn trigram_candidates_have_every_trigram_of_the_query() {
+        let search_index = index(&["Notepad", "Notepad++", "Note Taker", "Keynote", "Steam"]);
+        assert_eq!(
+            candidates(&search_index, "notepad"),
+            ["Notepad", "Notepad++"]
+        );
+        assert_eq!(
+            candidates(&search_index, "note"),
+            ["Keynote", "Note Taker", "Notepad", "Notepad++"]
+        );
+        // Nobody has "xyz", the intersection stops right away
+        assert!(candidates(&search_index, "notexyz").is_empty());
+    }
+
+    #[test]
+    fn candidates_that_only_share_trigrams_are_not_matches() {
+        let search_index = index(&["abc bcd", "abcd"]);
+        assert_eq!(candidates(&search_index, "abcd"), ["abc bcd", "abcd"]);
+        assert_eq!(names(&search_index, "abcd"), ["abcd"]);
+    }
+
+    #[test]
+    fn exact_before_prefix_before_word_start_before_substring() {
+        let search_index = index(&["Decoder", "Visual Studio Code", "Code Editor", "Code"]);
+        let expected = ["Code", "Code Editor", "Visual Studio Code", "Decoder"];
+        assert_eq!(names(&search_index, "code"), expected);
+        assert_eq!(names(&search_index, "Code"), expected);
+
+        // One to three characters are answered from the n-gram postings
+        let search_index = index(&["Decoder", "Visual Studio Code", "Code Editor", "Cod"]);
+        assert_eq!(
+            names(&search_index, "cod"),
+            ["Cod", "Code Editor", "Visual Studio Code", "Decoder"]
+        );
+
+        let scores: Vec<u32> = search_index
+            .search("cod", 10)
+            .into_iter()
+            .map(|result| result.score)
+            .collect();
+        assert_eq!(scores, [1000, 800, 600, 400]);
+    }
+
+    #[test]
+    fn queries_match_substrings_only() {
+        let search_index = index(&["Visual Studio Code", "Steam"]);
+        assert_eq!(names(&search_index, "studio code"), ["Visual Studio Code"]);
+        assert_eq!(names(&search_index, "studio-code"), ["Visual Studio Code"]);
+        assert!(names(&search_index, "vsc").is_empty());
+        assert!(names(&search_index, "code studio").is_empty());
+        assert!(names(&search_index, "  ").is_empty());
+    }
+
+    #[test]
+    fn ties_go_to_the_most_used_then_the_shortest_name() {
+        let apps = [
+            app("a", "Terminal Preview", 0),
+            app("b", "Terminal", 0),
+            app("c", "Terminal Beta", 5),
+        ];
+        let mut search_index = SearchIndex::from_apps(apps.iter());
+        assert_eq!(
+            names(&search_index, "term"),
+            ["Terminal Beta", "Terminal", "Terminal Preview"]
+        );
+
+        for _ in 0..6 {
+            search_index.record_access("a");
+        }
+        assert_eq!(
+            names(&search_index, "term"),
+            ["Terminal Preview", "Terminal Beta", "Terminal"]
+        );
+    }
+
+    #[test]
+    fn only_the_best_matches_up_to_the_limit_are_returned() {
+        let apps: Vec<AppInfo> = (0..50)
+            .map(|i| app(&i.to_string(), &format!("Tool {}", i), i))
+            .collect();
+        let search_index = SearchIndex::from_apps(apps.iter());
+
+        let results: Vec<String> = search_index
+            .search("tool", 3)
+            .into_iter()
+            .map(|result| result.id)
+            .collect();
+        assert_eq!(results, ["49", "48", "47"]);
+        assert!(search_index.search("tool", 0).is_empty());
+    }
+}
@@ -2,3 +2,5 @@ pub mod fetch_app;
 pub mod clip_board;
 pub mod window_resize;
 pub mod quick_link;
+pub mod search;
+pub mod snippets;
@@ -1,9 +1,19 @@
 use crate::commands::quick_link::executor::execute_command;
-use crate::commands::quick_link::models::{NewQuickLinkInput, QuickLink};
+use crate::commands::quick_link::import::{read_foreign_quick_links, BUNDLE_VERSION};
+use crate::commands::quick_link::models::{
+    NewQuickLinkInput, QuickLink, QuickLinkBundle, QuickLinkImportEntry, QuickLinkImportSummary,
+    QuickLinkUpdate,
+};
 use crate::commands::quick_link::state::QuickLinkState;
 use crate::commands::quick_link::storage::{
     delete_quick_link_from_disk, save_quick_link_to_disk, update_quick_link_usage,
 };
+use crate::commands::quick_link::template::{
+    render_command, template_arguments, Escaping, TemplateArgument,
+};
+use crate::persistence::files::write_atomic;
+use std::collections::HashMap;
+use std::path::Path;
 use tauri::{command, AppHandle, State};
 use uuid::Uuid;
 use winreg::enums::HKEY_CURRENT_USER;
@@ -21,14 +31,8 @@ pub async fn save_quick_link(
     let quick_link_input: NewQuickLinkInput = serde_json::from_value(quick_link)
         .map_err(|e| format!("Invalid quick link input data: {}", e))?;
 
-    let open_with = match quick_link_input.open_with.as_str() {
-        "browser" => OpenWith::Browser,
-        "terminal" => OpenWith::Terminal,
-        "app" => OpenWith::App,
-        "explorer" => OpenWith::Explorer,
-        "vscode" => OpenWith::VSCode,
-        _ => OpenWith::Browser, // Default to browser
-    };
+    // Default to browser
+    let open_with = OpenWith::parse(&quick_link_input.open_with).unwrap_or(OpenWith::Browser);
     let open_with_string = open_with.as_str().to_string();
     // Now manually build a full QuickLink
     let new_quick_link = QuickLink {
@@ -56,6 +60,59 @@ pub async fn save_quick_link(
     Ok(new_quick_link.id)
 }
 
+// Trimmed value of a field that must not be empty
+fn required(field: &str, value: String) -> Result<String, String> {
+    let value = value.trim().to_string();
+    if value.is_empty() {
+        return Err(format!("Quick link {} can't be empty", field));
+    }
+    Ok(value)
+}
+
+// Change some fields of a quick link. The ID and usage stats stay the same.
+#[command]
+pub async fn update_quick_link(
+    app_handle: AppHandle,
+    quick_link_state: State<'_, QuickLinkState>,
+    quick_link_id: String,
+    update: QuickLinkUpdate,
+) -> Result<QuickLink, String> {
+    let mut quick_links = quick_link_state
+        .quick_links
+        .lock()
+        .map_err(|_| "Failed to lock quick_links state".to_string())?;
+
+    let mut quick_link = quick_links
+        .get(&quick_link_id)
+        .cloned()
+        .ok_or_else(|| format!("Quick link not found: {}", quick_link_id))?;
+
+    if let Some(name) = update.name {
+        quick_link.name = required("name", name)?;
+    }
+    if let Some(command) = update.command {
+        quick_link.command = required("command", command)?;
+    }
+    if let Some(icon) = update.icon {
+        quick_link.icon = required("icon", icon)?;
+    }
+    if let Some(open_with) = update.open_with {
+        let open_with = OpenWith::parse(open_with.trim())
+            .ok_or_else(|| format!("Unknown way to open a quick link: {}", open_with))?;
+        quick_link.open_with = open_with.as_str().to_string();
+    }
+    if let Some(description) = update.description {
+        let description = description.trim();
+        quick_link.description = (!description.is_empty()).then(|| description.to_string());
+    }
+
+    // The file is written first so memory never holds a change that wasn't saved
+    save_quick_link_to_disk(&app_handle, &quick_link)?;
+    quick_links.insert(quick_link.id.clone(), quick_link.clone());
+
+    Ok(quick_link)
+}
+
 #[command]
 pub async fn delete_quick_link(
     app_handle: AppHandle,
@@ -104,28 +161,40 @@ pub async fn search_quick_links(
         .lock()
         .map_err(|_| "Failed to lock quick_links state".to_string())?;
 
-    // Convert to Vec and clone for processing
-    let all_quick_links: Vec<QuickLink> = quick_links_guard.values().cloned().collect();
+    Ok(filter_quick_links(&quick_links_guard, query, || false).unwrap_or_default())
+}
 
+// Filter quick links by name, description or command, most recently used first.
+// Returns None if `is_cancelled` reports that the caller no longer needs the results.
+pub fn filter_quick_links(
+    quick_links: &HashMap<String, QuickLink>,
+    query: &str,
+    is_cancelled: impl Fn() -> bool,
+) -> Option<Vec<QuickLink>> {
     // Case-insensitive filtering
     let query = query.to_lowercase();
-    let filtered_links: Vec<QuickLink> = all_quick_links
-        .into_iter()
-        .filter(|link| {
-            link.name.to_lowercase().contains(&query)
-                || link
-                    .description
-                    .as_ref()
-                    .map_or(false, |desc| desc.to_lowercase().contains(&query))
-                || link.command.to_lowercase().contains(&query)
-        })
-        .collect();
+    let mut filtered_links = Vec::new();
+
+    for link in quick_links.values() {
+        if is_cancelled() {
+            return None;
+        }
+
+        if link.name.to_lowercase().contains(&query)
+            || link
+                .description
+                .as_ref()
+                .map_or(false, |desc| desc.to_lowercase().contains(&query))
+            || link.command.to_lowercase().contains(&query)
+        {
+            filtered_links.push(link.clone());
+        }
+    }
 
     // Sort filtered results by last_used date
-    let mut sorted_filtered_links = filtered_links;
-    sorted_filtered_links.sort_by(|a, b| b.last_used.unwrap_or(0).cmp(&a.last_used.unwrap_or(0)));
+    filtered_links.sort_by(|a, b| b.last_used.unwrap_or(0).cmp(&a.last_used.unwrap_or(0)));
 
-    Ok(sorted_filtered_links)
+    Some(filtered_links)
 }
 
 // Command to get recent quick links
@@ -153,61 +222,217 @@ pub async fn get_recent_quick_links(
     Ok(quick_links.into_iter().take(5).collect())
 }
 
-// Execute a quick link with only its ID
+fn find_quick_link(
+    quick_link_state: &QuickLinkState,
+    quick_link_id: &str,
+) -> Result<QuickLink, String> {
+    let quick_links_guard = quick_link_state
+        .quick_links
+        .lock()
+        .map_err(|_| "Failed to lock quick_links state".to_string())?;
+
+    quick_links_guard
+        .get(quick_link_id)
+        .cloned()
+        .ok_or_else(|| format!("Quick link not found: {}", quick_link_id))
+}
+
+fn read_clipboard_text() -> Option<String> {
+    arboard::Clipboard::new().ok()?.get_text().ok()
+}
+
+// X11 keeps the selected text in the primary selection. Other platforms have
+// no such buffer, the clipboard is used instead.
+fn read_selection() -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        use arboard::{GetExtLinux, LinuxClipboardKind};
+        arboard::Clipboard::new()
+            .ok()?
+            .get()
+            .clipboard(LinuxClipboardKind::Primary)
+            .text()
+            .ok()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        read_clipboard_text()
+    }
+}
+
+fn render_quick_link(
+    quick_link: &QuickLink,
+    args: &HashMap<String, String>,
+) -> Result<String, String> {
+    render_command(
+        &quick_link.command,
+        args,
+        Escaping::for_open_with(&quick_link.open_with),
+        chrono::Local::now(),
+        read_clipboard_text,
+        read_selection,
+    )
+}
+
+async fn run_quick_link(
+    app_handle: &AppHandle,
+    quick_link_state: &State<'_, QuickLinkState>,
+    mut quick_link: QuickLink,
+    args: &HashMap<String, String>,
+) -> Result<(), String> {
+    quick_link.command = render_quick_link(&quick_link, args)?;
+
+    execute_command(app_handle, &quick_link).await?;
+    update_quick_link_usage(app_handle, quick_link_state, &quick_link.id).await?;
+
+    Ok(())
+}
+
+// Arguments the quick link asks for before it runs
+#[command]
+pub async fn get_quick_link_arguments(
+    quick_link_state: State<'_, QuickLinkState>,
+    quick_link_id: String,
+) -> Result<Vec<TemplateArgument>, String> {
+    let quick_link = find_quick_link(&quick_link_state, &quick_link_id)?;
+    Ok(template_arguments(&quick_link.command))
+}
+
+// The command as it would run with `args`, e.g. for a preview
+#[command]
+pub async fn preview_quick_link(
+    quick_link_state: State<'_, QuickLinkState>,
+    quick_link_id: String,
+    args: HashMap<String, String>,
+) -> Result<String, String> {
+    let quick_link = find_quick_link(&quick_link_state, &quick_link_id)?;
+    render_quick_link(&quick_link, &args)
+}
+
+// Execute a quick link with only its ID, arguments get their defaults
 #[command]
 pub async fn execute_quick_link(
     app_handle: AppHandle,
     quick_link_state: State<'_, QuickLinkState>,
     quick_link_id: String,
 ) -> Result<(), String> {
-    // Find the quick link BEFORE await
-    let quick_link = {
-        let quick_links_guard = quick_link_state
-            .quick_links
-            .lock()
-            .map_err(|_| "Failed to lock quick_links state".to_string())?;
-
-        quick_links_guard
-            .get(&quick_link_id)
-            .ok_or_else(|| format!("Quick link not found: {}", quick_link_id))?
-            .clone()
-    };
-
-    // Now after releasing lock, safely await
-    execute_command(&app_handle, &quick_link).await?;
-    update_quick_link_usage(&app_handle, &quick_link_state, &quick_link_id).await?;
-
-    Ok(())
+    let quick_link = find_quick_link(&quick_link_state, &quick_link_id)?;
+    run_quick_link(&app_handle, &quick_link_state, quick_link, &HashMap::new()).await
 }
 
+// Execute a quick link with values for its arguments, keyed by name
 #[command]
 pub async fn execute_quick_link_with_command(
     app_handle: AppHandle,
     quick_link_state: State<'_, QuickLinkState>,
     quick_link_id: String,
-    command: String,
+    args: HashMap<String, String>,
 ) -> Result<(), String> {
-    // Find and clone the quick link BEFORE await
-    let mut quick_link = {
-        let quick_links_guard = quick_link_state
-            .quick_links
-            .lock()
-            .map_err(|_| "Failed to lock quick_links state".to_string())?;
-
-        quick_links_guard
-            .get(&quick_link_id)
-            .ok_or_else(|| format!("Quick link not found: {}", quick_link_id))?
-            .clone()
+    let quick_link = find_quick_link(&quick_link_state, &quick_link_id)?;
+    run_quick_link(&app_handle, &quick_link_state, quick_link, &args).await
+}
+
+// Write all quick links, with their usage stats, to a versioned JSON bundle.
+// Returns the number of exported links.
+#[command]
+pub async fn export_quick_links(
+    quick_link_state: State<'_, QuickLinkState>,
+    path: String,
+) -> Result<usize, String> {
+    let mut quick_links: Vec<QuickLink> = quick_link_state
+        .quick_links
+        .lock()
+        .map_err(|_| "Failed to lock quick_links state".to_string())?
+        .values()
+        .cloned()
+        .collect();
+    quick_links.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+
+    let count = quick_links.len();
+    let bundle = QuickLinkBundle {
+        version: BUNDLE_VERSION,
+        exported_at: chrono::Utc::now().timestamp(),
+        quick_links,
     };
+    let json_string = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize quick links: {}", e))?;
 
-    // Override the command AFTER dropping the lock
-    quick_link.command = command;
+    write_atomic(Path::new(&path), json_string.as_bytes())
+        .map_err(|e| format!("Failed to write quick link export: {}", e))?;
 
-    // Now after releasing lock, safely await
-    execute_command(&app_handle, &quick_link).await?;
-    update_quick_link_usage(&app_handle, &quick_link_state, &quick_link_id).await?;
+    Ok(count)
+}
 
-    Ok(())
+// Links with the same target count as duplicates, regardless of their name
+fn same_command(a: &QuickLink, b: &QuickLink) -> bool {
+    let normalize = |command: &str| command.trim().trim_end_matches('/').to_lowercase();
+    a.open_with == b.open_with && normalize(&a.command) == normalize(&b.command)
+}
+
+// Import quick links from a bundle, Raycast, Alfred or a Chromium browser.
+// Duplicates of existing links are skipped. Links that run a command in a
+// terminal are skipped unless `allow_terminal` is set, the file may come from
+// anyone. With `dry_run` nothing is saved, the entries show what would be
+// imported and which links run in a terminal.
+#[command]
+pub async fn import_quick_links(
+    app_handle: AppHandle,
+    quick_link_state: State<'_, QuickLinkState>,
+    path: String,
+    dry_run: bool,
+    allow_terminal: bool,
+) -> Result<QuickLinkImportSummary, String> {
+    let imported = read_foreign_quick_links(Path::new(&path))?;
+
+    let mut quick_links = quick_link_state
+        .quick_links
+        .lock()
+        .map_err(|_| "Failed to lock quick_links state".to_string())?;
+
+    let mut summary = QuickLinkImportSummary {
+        added: 0,
+        skipped: 0,
+        entries: Vec::with_capacity(imported.len()),
+    };
+
+    for quick_link in imported {
+        let duplicate_of = quick_links
+            .values()
+            .chain(
+                summary
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.duplicate_of.is_none())
+                    .map(|entry| &entry.quick_link),
+            )
+            .find(|existing| same_command(existing, &quick_link))
+            .map(|existing| existing.id.clone());
+        let runs_in_terminal = matches!(
+            OpenWith::parse(&quick_link.open_with),
+            Some(OpenWith::Terminal)
+        );
+
+        if duplicate_of.is_some()
+            || quick_link.command.trim().is_empty()
+            || (runs_in_terminal && !allow_terminal)
+        {
+            summary.skipped += 1;
+        } else {
+            if !dry_run {
+                save_quick_link_to_disk(&app_handle, &quick_link)?;
+                quick_links.insert(quick_link.id.clone(), quick_link.clone());
+            }
+            summary.added += 1;
+        }
+
+        summary.entries.push(QuickLinkImportEntry {
+            quick_link,
+            duplicate_of,
+            runs_in_terminal,
+        });
+    }
+
+    Ok(summary)
 }
 
 pub fn get_vscode_path() -> Option<String> {
@@ -12,18 +12,31 @@ pub async fn execute_command(app_handle: &AppHandle, quick_link: &QuickLink) ->
         "terminal" => {
             #[cfg(target_os = "windows")]
             {
+                use std::os::windows::process::CommandExt;
+                use winapi::um::winbase::CREATE_NEW_CONSOLE;
+
                 let home_dir =
                     dirs::home_dir().ok_or_else(|| "Failed to find home directory".to_string())?;
 
                 let command_to_run =
                     format!("echo {} && {}", quick_link.command, quick_link.command);
 
+                // Values in the command are already escaped for cmd. Passed as
+                // is, without `start` in between, only this cmd parses the line.
                 std::process::Command::new("cmd")
                     .current_dir(home_dir)
-                    .args(&["/C", "start", "cmd", "/K", &command_to_run])
+                    .creation_flags(CREATE_NEW_CONSOLE)
+                    .raw_arg("/K")
+                    .raw_arg(&command_to_run)
                     .spawn()
                     .map_err(|e| format!("Failed to execute in terminal: {}", e))?;
             }
+            #[cfg(not(target_os = "windows"))]
+            {
+                return Err(
+                    "Running quick links in a terminal is only supported on Windows".to_string(),
+                );
+            }
         }
         "explorer" => {
             #[cfg(target_os = "windows")]
@@ -0,0 +1,279 @@
+use rusqlite::{Connection, OpenFlags};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::commands::quick_link::models::{OpenWith, QuickLink, QuickLinkBundle};
+
+pub const BUNDLE_VERSION: u32 = 1;
+
+const DEFAULT_ICON: &str = "🔗";
+const SQLITE_MAGIC: &[u8] = b"SQLite format 3\0";
+const ALFRED_WEBSEARCH_PREFS: &str = "preferences/features/websearch/prefs.plist";
+
+// Read quick links from one of:
+//   a bundle written by export_quick_links
+//   a Raycast quicklink export (JSON array of name, link and openWith)
+//   Alfred web search preferences (prefs.plist or the Alfred.alfredpreferences folder)
+//   the "Web Data" database of a Chromium browser, its search engine keywords
+// Every link gets a new ID, links from other apps also lose their usage stats.
+pub fn read_foreign_quick_links(path: &Path) -> Result<Vec<QuickLink>, String> {
+    let path = if path.is_dir() {
+        path.join(ALFRED_WEBSEARCH_PREFS)
+    } else {
+        path.to_path_buf()
+    };
+    let data = fs::read(&path).map_err(|e| format!("Failed to read quick link file: {}", e))?;
+
+    if data.starts_with(SQLITE_MAGIC) {
+        return read_chromium_keywords(&path);
+    }
+    if data.starts_with(b"bplist") || data.trim_ascii_start().starts_with(b"<") {
+        return read_alfred_websearch(&data);
+    }
+
+    let value: Value = serde_json::from_slice(&data)
+        .map_err(|e| format!("Failed to parse quick link file: {}", e))?;
+    match value {
+        Value::Object(ref object) if object.contains_key("quick_links") => read_bundle(value),
+        Value::Array(entries) => Ok(entries.iter().filter_map(parse_raycast_entry).collect()),
+        _ => Err("Unrecognized quick link file".to_string()),
+    }
+}
+
+fn new_quick_link(name: &str, command: String, description: Option<String>) -> QuickLink {
+    let open_with = if command.starts_with("http://") || command.starts_with("https://") {
+        OpenWith::Browser
+    } else if command.starts_with('/') || command.starts_with('~') || command.contains(":\\") {
+        OpenWith::Explorer
+    } else {
+        OpenWith::App
+    };
+
+    QuickLink {
+        id: Uuid::new_v4().to_string(),
+        name: name.trim().to_string(),
+        command,
+        icon: DEFAULT_ICON.to_string(),
+        open_with: open_with.as_str().to_string(),
+        description,
+        last_used: None,
+        use_count: 0,
+    }
+}
+
+fn read_bundle(value: Value) -> Result<Vec<QuickLink>, String> {
+    let bundle: QuickLinkBundle = serde_json::from_value(value)
+        .map_err(|e| format!("Failed to parse quick link bundle: {}", e))?;
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!(
+            "Bundle uses unsupported format version {}",
+            bundle.version
+        ));
+    }
+
+    Ok(bundle
+        .quick_links
+        .into_iter()
+        .map(|mut quick_link| {
+            // The ID names the link's file, a bundle can't be trusted with it
+            quick_link.id = Uuid::new_v4().to_string();
+            if OpenWith::parse(&quick_link.open_with).is_none() {
+                quick_link.open_with = OpenWith::Browser.as_str().to_string();
+            }
+            quick_link
+        })
+        .collect())
+}
+
+// The app in openWith is dropped, links open with the default handler
+fn parse_raycast_entry(entry: &Value) -> Option<QuickLink> {
+    let field = |name: &str| {
+        entry
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+    let link = field("link")?;
+    let name = field("name").unwrap_or(link);
+
+    Some(new_quick_link(
+        name,
+        convert_raycast_placeholders(link),
+        None,
+    ))
+}
+
+// Rewrite Raycast placeholders into ours. Modifiers like `| raw` are dropped,
+// values are escaped for the link type anyway.
+fn convert_raycast_placeholders(link: &str) -> String {
+    let mut converted = String::with_capacity(link.len());
+    let mut rest = link;
+
+    while let Some(start) = rest.find('{') {
+        converted.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let replacement = after
+            .find('}')
+            .and_then(|end| convert_raycast_placeholder(&after[..end]).map(|new| (end, new)));
+
+        match replacement {
+            Some((end, replacement)) => {
+                converted.push_str(&replacement);
+                rest = &after[end + 1..];
+            }
+            None => {
+                converted.push('{');
+                rest = after;
+            }
+        }
+    }
+    converted.push_str(rest);
+    converted
+}
+
+fn convert_raycast_placeholder(inner: &str) -> Option<String> {
+    let inner = inner.split('|').next()?.trim();
+    let name = inner.split_whitespace().next()?.to_lowercase();
+
+    let converted = match name.as_str() {
+        "clipboard" => "{clipboard}".to_string(),
+        "selection" => "{selection}".to_string(),
+        "date" | "time" | "datetime" => "{date}".to_string(),
+        // {argument name="Query" default="rust"}
+        "argument" => {
+            let name: String = attribute(inner, "name")
+                .unwrap_or("query")
+                .trim()
+                .replace(' ', "_")
+                .chars()
+                .filter(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+                .collect();
+            let name = if name.is_empty() {
+                "query".to_string()
+            } else {
+                name
+            };
+            match attribute(inner, "default") {
+                Some(default) => format!("{{{}:{}}}", name, default),
+                None => format!("{{{}}}", name),
+            }
+        }
+        _ => return None,
+    };
+    Some(converted)
+}
+
+fn attribute<'a>(placeholder: &'a str, name: &str) -> Option<&'a str> {
+    let start = placeholder.find(&format!("{}=\"", name))? + name.len() + 2;
+    let length = placeholder[start..].find('"')?;
+    Some(&placeholder[start..start + length]).filter(|value| !value.trim().is_empty())
+}
+
+// Alfred keeps custom web searches in `customSites`, keyed by UUID. Alfred
+// uses {query} like we do. The keyword goes into the description so it can
+// still be searched for.
+fn read_alfred_websearch(data: &[u8]) -> Result<Vec<QuickLink>, String> {
+    let prefs = plist::Value::from_reader(std::io::Cursor::new(data))
+        .map_err(|e| format!("Failed to parse Alfred preferences: {}", e))?;
+    let sites = prefs
+        .as_dictionary()
+        .and_then(|prefs| prefs.get("customSites"))
+        .and_then(plist::Value::as_dictionary)
+        .ok_or_else(|| "No custom web searches in Alfred preferences".to_string())?;
+
+    let mut quick_links = Vec::new();
+    for site in sites.values().filter_map(plist::Value::as_dictionary) {
+        let field = |name: &str| {
+            site.get(name)
+                .and_then(plist::Value::as_string)
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+        let Some(url) = field("url") else { continue };
+        if site.get("enabled").and_then(plist::Value::as_boolean) == Some(false) {
+            continue;
+        }
+
+        // "Search Google for '{query}'" becomes "Search Google"
+        let name = field("text")
+            .map(|text| {
+                let text = text.replace("'{query}'", "").replace("{query}", "");
+                text.trim().trim_end_matches(" for").trim().to_string()
+            })
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| url.to_string());
+        let description = field("keyword").map(|keyword| format!("Alfred keyword: {}", keyword));
+
+        quick_links.push(new_quick_link(&name, url.to_string(), description));
+    }
+    Ok(quick_links)
+}
+
+// Chromium search engines use {searchTerms} for the query plus a number of
+// browser specific parameters, those are dropped.
+fn convert_chromium_url(url: &str) -> String {
+    let mut converted = String::with_capacity(url.len());
+    let mut rest = url;
+
+    while let Some(start) = rest.find('{') {
+        converted.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            converted.push('{');
+            rest = after;
+            continue;
+        };
+
+        match &after[..end] {
+            "searchTerms" => converted.push_str("{query}"),
+            "inputEncoding" | "outputEncoding" => converted.push_str("UTF-8"),
+            "google:baseURL" => converted.push_str("https://www.google.com/"),
+            _ => {}
+        }
+        rest = &after[end + 1..];
+    }
+    converted.push_str(rest);
+    converted
+}
+
+// The browser keeps its database locked while it runs, so a copy is read
+fn read_chromium_keywords(path: &Path) -> Result<Vec<QuickLink>, String> {
+    let copy: PathBuf = std::env::temp_dir().join(format!("quick-links-{}.db", Uuid::new_v4()));
+    fs::copy(path, &copy).map_err(|e| format!("Failed to copy browser database: {}", e))?;
+    let result = query_chromium_keywords(&copy);
+    let _ = fs::remove_file(&copy);
+    result
+}
+
+fn query_chromium_keywords(path: &Path) -> Result<Vec<QuickLink>, String> {
+    let connection = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| format!("Failed to open browser database: {}", e))?;
+    let mut statement = connection
+        .prepare("SELECT short_name, keyword, url FROM keywords")
+        .map_err(|e| format!("Failed to read search engines: {}", e))?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to read search engines: {}", e))?;
+
+    let mut quick_links = Vec::new();
+    for (name, keyword, url) in rows.flatten() {
+        let url = convert_chromium_url(&url);
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            continue;
+        }
+        let description = Some(keyword)
+            .filter(|keyword| !keyword.trim().is_empty())
+            .map(|keyword| format!("Browser keyword: {}", keyword.trim()));
+        quick_links.push(new_quick_link(&name, url, description));
+    }
+    Ok(quick_links)
+}
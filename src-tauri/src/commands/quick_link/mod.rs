@@ -2,15 +2,19 @@
 mod command_input;
 mod commands;
 mod executor;
+mod import;
 mod models;
 mod state;
 mod storage;
+mod template;
 
 // Public re-exports
 pub use command_input::get_open_with_suggestions;
 pub use commands::{
     check_vscode_path, delete_quick_link, execute_quick_link, execute_quick_link_with_command,
-    get_default_browser, get_quick_links, get_recent_quick_links, save_quick_link,
-    search_quick_links,
+    export_quick_links, filter_quick_links, get_default_browser, get_quick_link_arguments,
+    get_quick_links, get_recent_quick_links, import_quick_links, preview_quick_link,
+    save_quick_link, search_quick_links, update_quick_link,
 };
-pub use state::init;
+pub use models::QuickLink;
+pub use state::{init, QuickLinkState};
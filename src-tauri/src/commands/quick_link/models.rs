@@ -5,7 +5,18 @@ pub struct NewQuickLinkInput {
     pub name: String,
     pub command: String,
     pub icon: String,
-    pub open_with: String,  // Changed from OpenWith enum to String
+    pub open_with: String, // Changed from OpenWith enum to String
+    pub description: Option<String>,
+}
+
+// Fields to change in update_quick_link, missing ones are kept. An empty
+// description removes it.
+#[derive(Debug, Deserialize)]
+pub struct QuickLinkUpdate {
+    pub name: Option<String>,
+    pub command: Option<String>,
+    pub icon: Option<String>,
+    pub open_with: Option<String>,
     pub description: Option<String>,
 }
 
@@ -29,7 +40,7 @@ pub enum OpenWith {
     Browser,
     App,
     Explorer,
-    VSCode, 
+    VSCode,
 }
 
 // Implement the as_str method for OpenWith
@@ -43,16 +54,40 @@ impl OpenWith {
             OpenWith::VSCode => "vscode",
         }
     }
-    
-    // Add a from_str method for convenience
-    // pub fn from_str(s: &str) -> Option<Self> {
-    //     match s {
-    //         "terminal" => Some(Self::Terminal),
-    //         "browser" => Some(Self::Browser),
-    //         "app" => Some(Self::App),
-    //         "explorer" => Some(Self::Explorer),
-    //         "vscode" => Some(Self::VSCode),
-    //         _ => None,
-    //     }
-    // }
-}
\ No newline at end of file
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "terminal" => Some(Self::Terminal),
+            "browser" => Some(Self::Browser),
+            "app" => Some(Self::App),
+            "explorer" => Some(Self::Explorer),
+            "vscode" => Some(Self::VSCode),
+            _ => None,
+        }
+    }
+}
+
+// Versioned file written by export_quick_links
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuickLinkBundle {
+    pub version: u32,
+    pub exported_at: i64,
+    pub quick_links: Vec<QuickLink>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuickLinkImportEntry {
+    #[serde(flatten)]
+    pub quick_link: QuickLink,
+    // Existing or earlier imported link with the same command
+    pub duplicate_of: Option<String>,
+    // Runs its command in a terminal, only imported when the user allows it
+    pub runs_in_terminal: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuickLinkImportSummary {
+    pub added: usize,
+    pub skipped: usize,
+    pub entries: Vec<QuickLinkImportEntry>,
+}
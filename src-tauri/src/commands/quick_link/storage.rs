@@ -1,9 +1,11 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::AppHandle;
+use uuid::Uuid;
 
 use crate::commands::quick_link::models::QuickLink;
 use crate::commands::quick_link::state::QuickLinkState;
+use crate::persistence::files::write_atomic;
 
 // Helper function to get quick links directory
 pub fn get_quick_links_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
@@ -16,6 +18,14 @@ pub fn get_quick_links_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
     Ok(quick_links_dir)
 }
 
+// Files are named after the quick link id, which is always a UUID. Anything
+// else could point outside the quick links directory.
+fn quick_link_file(quick_links_dir: &Path, quick_link_id: &str) -> Result<PathBuf, String> {
+    Uuid::parse_str(quick_link_id)
+        .map_err(|_| format!("Invalid quick link id: {}", quick_link_id))?;
+    Ok(quick_links_dir.join(format!("{}.json", quick_link_id)))
+}
+
 // Save a quick link to disk
 pub fn save_quick_link_to_disk(
     app_handle: &AppHandle,
@@ -27,11 +37,11 @@ pub fn save_quick_link_to_disk(
             .map_err(|e| format!("Failed to create quick links directory: {}", e))?;
     }
 
-    let file_path = quick_links_dir.join(format!("{}.json", quick_link.id));
+    let file_path = quick_link_file(&quick_links_dir, &quick_link.id)?;
     let json_string = serde_json::to_string_pretty(&quick_link)
         .map_err(|e| format!("Failed to serialize quick link: {}", e))?;
 
-    fs::write(&file_path, json_string)
+    write_atomic(&file_path, json_string.as_bytes())
         .map_err(|e| format!("Failed to write quick link file: {}", e))?;
 
     Ok(())
@@ -43,7 +53,7 @@ pub fn delete_quick_link_from_disk(
     quick_link_id: &str,
 ) -> Result<(), String> {
     let quick_links_dir = get_quick_links_dir(app_handle)?;
-    let file_path = quick_links_dir.join(format!("{}.json", quick_link_id));
+    let file_path = quick_link_file(&quick_links_dir, quick_link_id)?;
 
     if file_path.exists() {
         fs::remove_file(&file_path)
@@ -71,14 +81,8 @@ pub async fn update_quick_link_usage(
         quick_link.use_count += 1;
 
         // Save to disk
-        let quick_links_dir = get_quick_links_dir(app_handle)?;
-        let file_path = quick_links_dir.join(format!("{}.json", quick_link_id));
-        let json_string = serde_json::to_string_pretty(&quick_link)
-            .map_err(|e| format!("Failed to serialize quick link: {}", e))?;
-
-        fs::write(&file_path, json_string)
-            .map_err(|e| format!("Failed to update quick link file: {}", e))?;
+        save_quick_link_to_disk(app_handle, quick_link)?;
     }
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,373 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Placeholders a quick link command can contain:
+//   {project} {q:default}   argument asked for before running, with an optional default
+//   {clipboard}             current clipboard text
+//   {selection}             selected text, the primary selection on Linux
+//   {date} {date:%d.%m.%Y}  current date, chrono strftime format
+//   {env:VAR}               environment variable
+enum Placeholder<'a> {
+    Argument {
+        name: &'a str,
+        default: Option<&'a str>,
+    },
+    Clipboard,
+    Selection,
+    Date(Option<&'a str>),
+    Env(&'a str),
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Placeholder(Placeholder<'a>),
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TemplateArgument {
+    pub name: String,
+    pub default: Option<String>,
+}
+
+// How substituted values are escaped, depends on what runs the command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Escaping {
+    Url,
+    Shell,
+    Raw,
+}
+
+impl Escaping {
+    pub fn for_open_with(open_with: &str) -> Self {
+        match open_with {
+            "browser" => Escaping::Url,
+            "terminal" => Escaping::Shell,
+            _ => Escaping::Raw,
+        }
+    }
+}
+
+fn is_argument_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+fn parse_placeholder(inner: &str) -> Option<Placeholder<'_>> {
+    let (name, argument) = match inner.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (inner, None),
+    };
+
+    match (name, argument) {
+        ("clipboard", None) => Some(Placeholder::Clipboard),
+        ("selection", None) => Some(Placeholder::Selection),
+        ("date", argument) => Some(Placeholder::Date(argument)),
+        ("env", Some(variable)) if !variable.trim().is_empty() => {
+            Some(Placeholder::Env(variable.trim()))
+        }
+        (name, default) if is_argument_name(name) => Some(Placeholder::Argument { name, default }),
+        _ => None,
+    }
+}
+
+// Braces that don't form a placeholder are kept as they are, e.g. JSON in a
+// curl command
+fn tokenize(template: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let placeholder = after
+            .find(['{', '}'])
+            .filter(|&end| after.as_bytes()[end] == b'}')
+            .and_then(|end| parse_placeholder(&after[..end]).map(|placeholder| (end, placeholder)));
+
+        match placeholder {
+            Some((end, placeholder)) => {
+                if start > 0 {
+                    tokens.push(Token::Text(&rest[..start]));
+                }
+                tokens.push(Token::Placeholder(placeholder));
+                rest = &after[end + 1..];
+            }
+            None => {
+                tokens.push(Token::Text(&rest[..start + 1]));
+                rest = after;
+            }
+        }
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+
+    tokens
+}
+
+// Arguments a command asks for, in order of appearance. The first default
+// given for a name wins.
+pub fn template_arguments(template: &str) -> Vec<TemplateArgument> {
+    let mut arguments: Vec<TemplateArgument> = Vec::new();
+    for token in tokenize(template) {
+        if let Token::Placeholder(Placeholder::Argument { name, default }) = token {
+            match arguments.iter_mut().find(|argument| argument.name == name) {
+                Some(existing) => {
+                    if existing.default.is_none() {
+                        existing.default = default.map(str::to_string);
+                    }
+                }
+                None => arguments.push(TemplateArgument {
+                    name: name.to_string(),
+                    default: default.map(str::to_string),
+                }),
+            }
+        }
+    }
+    arguments
+}
+
+// Characters cmd acts on outside of quotes
+const CMD_SPECIAL_CHARS: &[char] = &['&', '|', '<', '>', '^', '%', '"'];
+
+fn posix_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+// The value is quoted for the program's own argument parsing, then every
+// character cmd acts on is escaped with a caret, the quotes included. cmd
+// never sees an open quote, so no value can end the command or start another
+// one. A caret in front of a percent sign also stops %VAR% from expanding.
+fn cmd_quote(value: &str) -> String {
+    // A line break ends the command line
+    let value = value.replace(['\r', '\n'], " ");
+
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in value.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes + 1));
+                backslashes = 0;
+            }
+            _ => backslashes = 0,
+        }
+        quoted.push(c);
+    }
+    quoted.push_str(&"\\".repeat(backslashes));
+    quoted.push('"');
+
+    let mut escaped = String::with_capacity(quoted.len() * 2);
+    for c in quoted.chars() {
+        if CMD_SPECIAL_CHARS.contains(&c) {
+            escaped.push('^');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// On Windows terminal commands run through cmd, everywhere else through a
+// POSIX shell
+fn shell_quote(value: &str) -> String {
+    if cfg!(target_os = "windows") {
+        cmd_quote(value)
+    } else {
+        posix_quote(value)
+    }
+}
+
+fn escape(value: &str, escaping: Escaping, at_start: bool) -> String {
+    match escaping {
+        // A value that starts the command is the URL itself, e.g. {clipboard}
+        Escaping::Url if at_start => value.to_string(),
+        Escaping::Url => urlencoding::encode(value).into_owned(),
+        Escaping::Shell => shell_quote(value),
+        Escaping::Raw => value.to_string(),
+    }
+}
+
+fn format_now(now: &DateTime<Local>, format: &str) -> Result<String, String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid date format in quick link: {}", format));
+    }
+    Ok(now
+        .format_with_items(StrftimeItems::new(format))
+        .to_string())
+}
+
+// Fill in every placeholder, escaping the values for `escaping`. An argument
+// left empty falls back to its default. The clipboard and the selection are
+// only read if the command uses them.
+pub fn render_command(
+    template: &str,
+    arguments: &HashMap<String, String>,
+    escaping: Escaping,
+    now: DateTime<Local>,
+    read_clipboard: impl FnOnce() -> Option<String>,
+    read_selection: impl FnOnce() -> Option<String>,
+) -> Result<String, String> {
+    let defaults = template_arguments(template);
+    let mut command = String::with_capacity(template.len());
+    let mut read_clipboard = Some(read_clipboard);
+    let mut read_selection = Some(read_selection);
+    let mut clipboard: Option<String> = None;
+    let mut selection: Option<String> = None;
+
+    for token in tokenize(template) {
+        let value = match token {
+            Token::Text(literal) => {
+                command.push_str(literal);
+                continue;
+            }
+            Token::Placeholder(Placeholder::Argument { name, .. }) => {
+                let default = defaults
+                    .iter()
+                    .find(|argument| argument.name == name)
+                    .and_then(|argument| argument.default.clone());
+                match (arguments.get(name), default) {
+                    (Some(value), Some(default)) if value.is_empty() => default,
+                    (Some(value), _) => value.clone(),
+                    (None, Some(default)) => default,
+                    (None, None) => return Err(format!("Missing value for {{{}}}", name)),
+                }
+            }
+            Token::Placeholder(Placeholder::Clipboard) => clipboard
+                .get_or_insert_with(|| {
+                    read_clipboard
+                        .take()
+                        .and_then(|read| read())
+                        .unwrap_or_default()
+                })
+                .clone(),
+            Token::Placeholder(Placeholder::Selection) => selection
+                .get_or_insert_with(|| {
+                    read_selection
+                        .take()
+                        .and_then(|read| read())
+                        .unwrap_or_default()
+                })
+                .clone(),
+            Token::Placeholder(Placeholder::Date(format)) => {
+                format_now(&now, format.unwrap_or(DEFAULT_DATE_FORMAT))?
+            }
+            Token::Placeholder(Placeholder::Env(variable)) => std::env::var(variable)
+                .map_err(|_| format!("Environment variable not set: {}", variable))?,
+        };
+        command.push_str(&escape(&value, escaping, command.is_empty()));
+    }
+
+    Ok(command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTILE_VALUES: &[&str] = &[
+        "x\" & calc & \"",
+        "x'; touch pwned; echo '",
+        "$(touch pwned) `touch pwned`",
+        "%PATH% ^& | < > \"\"",
+        "a\ncalc",
+        "C:\\dir\\",
+        "{clipboard} {env:HOME}",
+    ];
+
+    fn render(template: &str, value: &str, escaping: Escaping) -> String {
+        let arguments = HashMap::from([("q".to_string(), value.to_string())]);
+        render_command(
+            template,
+            &arguments,
+            escaping,
+            Local::now(),
+            || Some(value.to_string()),
+            || None,
+        )
+        .unwrap()
+    }
+
+    // Every character cmd acts on has a caret in front of it
+    fn is_inert_for_cmd(quoted: &str) -> bool {
+        let mut escaped = false;
+        for c in quoted.chars() {
+            if escaped {
+                escaped = false;
+            } else if c == '^' {
+                escaped = true;
+            } else if CMD_SPECIAL_CHARS.contains(&c) || c == '\n' || c == '\r' {
+                return false;
+            }
+        }
+        !escaped
+    }
+
+    #[test]
+    fn cmd_quote_escapes_everything_cmd_acts_on() {
+        assert_eq!(
+            cmd_quote("x\" & calc & \""),
+            "^\"x\\^\" ^& calc ^& \\^\"^\""
+        );
+        assert_eq!(cmd_quote("%PATH%"), "^\"^%PATH^%^\"");
+        assert_eq!(cmd_quote("a\ncalc"), "^\"a calc^\"");
+        // Backslashes before the closing quote are doubled so it still closes
+        assert_eq!(cmd_quote("C:\\dir\\"), "^\"C:\\dir\\\\^\"");
+
+        for value in HOSTILE_VALUES {
+            assert!(is_inert_for_cmd(&cmd_quote(value)), "{}", value);
+        }
+    }
+
+    #[test]
+    fn posix_quote_keeps_the_value_one_word() {
+        assert_eq!(
+            posix_quote("x'; touch pwned; echo '"),
+            "'x'\\''; touch pwned; echo '\\'''"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn posix_quoted_values_reach_the_shell_unchanged() {
+        for value in HOSTILE_VALUES {
+            let command = format!("printf %s {}", posix_quote(value));
+            let output = std::process::Command::new("sh")
+                .args(["-c", &command])
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8(output.stdout).unwrap(), *value);
+        }
+    }
+
+    #[test]
+    fn shell_values_are_quoted_wherever_they_appear() {
+        for value in HOSTILE_VALUES {
+            assert_eq!(
+                render("echo {q} {clipboard}", value, Escaping::Shell),
+                format!("echo {} {}", shell_quote(value), shell_quote(value))
+            );
+        }
+    }
+
+    #[test]
+    fn values_are_not_expanded_again() {
+        assert_eq!(
+            render("echo {q}", "{env:HOME}", Escaping::Raw),
+            "echo {env:HOME}"
+        );
+    }
+
+    #[test]
+    fn url_values_are_encoded() {
+        assert_eq!(
+            render("https://example.com/?q={q}", "a&b=c d#e", Escaping::Url),
+            "https://example.com/?q=a%26b%3Dc%20d%23e"
+        );
+    }
+}
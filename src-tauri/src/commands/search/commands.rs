@@ -0,0 +1,68 @@
+use std::thread;
+use tauri::{AppHandle, Manager, State};
+
+use super::providers::{
+    emit_provider, SearchFinishedPayload, SearchProvider, SEARCH_FINISHED_EVENT,
+};
+use super::state::SearchState;
+
+// Slower providers, each runs on its own thread once apps have answered
+const BACKGROUND_PROVIDERS: [SearchProvider; 2] =
+    [SearchProvider::QuickLinks, SearchProvider::Snippets];
+
+// Start a search for the given generation. Results are delivered as
+// `search-results` events, followed by `search-finished` once every provider is done.
+#[tauri::command]
+pub fn start_search(
+    query: String,
+    generation: u64,
+    app_handle: AppHandle,
+    search_state: State<'_, SearchState>,
+) -> Result<(), String> {
+    let token = match search_state.begin(generation) {
+        Some(token) => token,
+        // A newer search is already running, nothing to do
+        None => return Ok(()),
+    };
+
+    let query = query.trim().to_string();
+
+    // Apps are answered straight from memory before anything else
+    emit_provider(SearchProvider::Apps, &app_handle, &query, &token);
+
+    let handles: Vec<_> = BACKGROUND_PROVIDERS
+        .iter()
+        .map(|provider| {
+            let provider = *provider;
+            let app_handle = app_handle.clone();
+            let query = query.clone();
+            let token = token.clone();
+            thread::spawn(move || emit_provider(provider, &app_handle, &query, &token))
+        })
+        .collect();
+
+    // Report completion without blocking the command
+    thread::spawn(move || {
+        for handle in handles {
+            let _ = handle.join();
+        }
+
+        if !token.is_cancelled() {
+            let _ = app_handle.emit_all(
+                SEARCH_FINISHED_EVENT,
+                SearchFinishedPayload {
+                    generation: token.generation,
+                },
+            );
+        }
+    });
+
+    Ok(())
+}
+
+// Cancel a running search, e.g. when the launcher is closed
+#[tauri::command]
+pub fn cancel_search(generation: u64, search_state: State<'_, SearchState>) -> Result<(), String> {
+    search_state.cancel(generation);
+    Ok(())
+}
@@ -0,0 +1,7 @@
+pub mod commands;
+pub mod providers;
+pub mod state;
+
+// Re-export functions that are used in main.rs
+pub use commands::{cancel_search, start_search};
+pub use state::SearchState;
@@ -0,0 +1,103 @@
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::fetch_app::models::AppIndexState;
+use crate::commands::fetch_app::search_index::AppSearchResult;
+use crate::commands::quick_link::{filter_quick_links, QuickLink, QuickLinkState};
+use crate::commands::snippets::{filter_snippets, SnippetMatch, SnippetState};
+
+use super::state::SearchToken;
+
+pub const SEARCH_RESULTS_EVENT: &str = "search-results";
+pub const SEARCH_FINISHED_EVENT: &str = "search-finished";
+
+// Providers in the order they answer. Apps are served from memory and always go first.
+#[derive(Debug, Clone, Copy)]
+pub enum SearchProvider {
+    Apps,
+    QuickLinks,
+    Snippets,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(tag = "provider", content = "results", rename_all = "snake_case")]
+pub enum ProviderResults {
+    Apps(Vec<AppSearchResult>),
+    QuickLinks(Vec<QuickLink>),
+    Snippets(Vec<SnippetMatch>),
+}
+
+#[derive(Serialize, Clone)]
+pub struct SearchResultsPayload {
+    pub generation: u64,
+    pub query: String,
+    #[serde(flatten)]
+    pub results: ProviderResults,
+}
+
+#[derive(Serialize, Clone)]
+pub struct SearchFinishedPayload {
+    pub generation: u64,
+}
+
+// Run a single provider. Returns None when the search was cancelled mid-way.
+pub fn run_provider(
+    provider: SearchProvider,
+    app_handle: &AppHandle,
+    query: &str,
+    token: &SearchToken,
+) -> Option<ProviderResults> {
+    if token.is_cancelled() {
+        return None;
+    }
+
+    let results = match provider {
+        SearchProvider::Apps => {
+            let app_index_state = app_handle.state::<AppIndexState>();
+            let search_index = app_index_state.search_index.read().unwrap();
+            ProviderResults::Apps(search_index.search(query, 10))
+        }
+        SearchProvider::QuickLinks => {
+            let quick_link_state = app_handle.state::<QuickLinkState>();
+            let quick_links = quick_link_state.quick_links.lock().ok()?;
+            let links = filter_quick_links(&quick_links, query, || token.is_cancelled())?;
+            ProviderResults::QuickLinks(links)
+        }
+        SearchProvider::Snippets => {
+            let snippet_state = app_handle.state::<SnippetState>();
+            let snippets = snippet_state
+                .with_snippets(app_handle, |snippets| {
+                    filter_snippets(snippets, query, || token.is_cancelled())
+                })
+                .ok()??;
+            ProviderResults::Snippets(snippets)
+        }
+    };
+
+    // Don't let a slow provider overwrite results of a newer query
+    if token.is_cancelled() {
+        return None;
+    }
+
+    Some(results)
+}
+
+// Run a provider and stream its results to the webview
+pub fn emit_provider(
+    provider: SearchProvider,
+    app_handle: &AppHandle,
+    query: &str,
+    token: &SearchToken,
+) {
+    if let Some(results) = run_provider(provider, app_handle, query, token) {
+        let payload = SearchResultsPayload {
+            generation: token.generation,
+            query: query.to_string(),
+            results,
+        };
+
+        if let Err(e) = app_handle.emit_all(SEARCH_RESULTS_EVENT, payload) {
+            eprintln!("Failed to emit {:?} search results: {}", provider, e);
+        }
+    }
+}
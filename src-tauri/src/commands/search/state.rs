@@ -0,0 +1,46 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+// Tracks the newest search generation requested by the webview
+#[derive(Default)]
+pub struct SearchState {
+    pub generation: Arc<AtomicU64>,
+}
+
+impl SearchState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Register a new generation. Returns None if a newer search already started,
+    // otherwise a token that providers use to check for cancellation.
+    pub fn begin(&self, generation: u64) -> Option<SearchToken> {
+        let previous = self.generation.fetch_max(generation, Ordering::SeqCst);
+        if previous > generation {
+            return None;
+        }
+
+        Some(SearchToken {
+            generation,
+            current: self.generation.clone(),
+        })
+    }
+
+    // Cancel everything up to and including the given generation
+    pub fn cancel(&self, generation: u64) {
+        self.generation.fetch_max(generation + 1, Ordering::SeqCst);
+    }
+}
+
+// Handed to every provider of a search so it can stop once a newer query arrives
+#[derive(Clone)]
+pub struct SearchToken {
+    pub generation: u64,
+    current: Arc<AtomicU64>,
+}
+
+impl SearchToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.current.load(Ordering::SeqCst) != self.generation
+    }
+}
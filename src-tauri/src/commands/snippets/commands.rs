@@ -0,0 +1,286 @@
+use std::collections::HashMap;
+use std::path::Path;
+use tauri::{command, AppHandle, Manager, State};
+use uuid::Uuid;
+
+use crate::commands::clip_board::paste::paste_text;
+use crate::commands::snippets::import::read_foreign_snippets;
+use crate::commands::snippets::models::{
+    Snippet, SnippetImportSummary, SnippetInput, SnippetMatch,
+};
+use crate::commands::snippets::placeholders::{expand_placeholders, input_names, ExpandedSnippet};
+use crate::commands::snippets::state::SnippetState;
+
+fn snippet_match(snippet: &Snippet) -> SnippetMatch {
+    SnippetMatch {
+        snippet: snippet.clone(),
+        inputs: input_names(&snippet.text),
+    }
+}
+
+fn most_recent_first(a: &Snippet, b: &Snippet) -> std::cmp::Ordering {
+    b.last_used
+        .unwrap_or(0)
+        .cmp(&a.last_used.unwrap_or(0))
+        .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+}
+
+// Keywords are single words so they can be told apart from a search
+fn normalize_keyword(keyword: Option<String>) -> Result<Option<String>, String> {
+    let keyword = match keyword.map(|keyword| keyword.trim().to_string()) {
+        Some(keyword) if !keyword.is_empty() => keyword,
+        _ => return Ok(None),
+    };
+    if keyword.chars().any(char::is_whitespace) {
+        return Err("Snippet keywords can't contain spaces".to_string());
+    }
+    Ok(Some(keyword))
+}
+
+fn keyword_owner<'a>(snippets: &'a [Snippet], keyword: &str, except: &str) -> Option<&'a Snippet> {
+    snippets.iter().find(|snippet| {
+        snippet.id != except
+            && snippet
+                .keyword
+                .as_deref()
+                .is_some_and(|existing| existing.eq_ignore_ascii_case(keyword))
+    })
+}
+
+#[command]
+pub fn get_snippets(
+    app_handle: AppHandle,
+    snippet_state: State<'_, SnippetState>,
+) -> Result<Vec<SnippetMatch>, String> {
+    snippet_state.with_snippets(&app_handle, |snippets| {
+        let mut snippets: Vec<&Snippet> = snippets.iter().collect();
+        snippets.sort_by(|a, b| most_recent_first(a, b));
+        snippets.into_iter().map(snippet_match).collect()
+    })
+}
+
+#[command]
+pub fn search_snippets(
+    query: &str,
+    app_handle: AppHandle,
+    snippet_state: State<'_, SnippetState>,
+) -> Result<Vec<SnippetMatch>, String> {
+    snippet_state.with_snippets(&app_handle, |snippets| {
+        filter_snippets(snippets, query, || false).unwrap_or_default()
+    })
+}
+
+// Filter snippets by keyword, name or text. A snippet whose keyword is the
+// whole query comes first, then keyword prefixes, then everything else by
+// last use. Returns None if `is_cancelled` reports that the caller no longer
+// needs the results.
+pub fn filter_snippets(
+    snippets: &[Snippet],
+    query: &str,
+    is_cancelled: impl Fn() -> bool,
+) -> Option<Vec<SnippetMatch>> {
+    let query = query.trim().to_lowercase();
+    let mut ranked = Vec::new();
+
+    for snippet in snippets {
+        if is_cancelled() {
+            return None;
+        }
+
+        let keyword = snippet.keyword.as_deref().map(str::to_lowercase);
+        let rank = match keyword {
+            Some(keyword) if keyword == query => 0,
+            Some(keyword) if keyword.starts_with(&query) => 1,
+            _ if snippet.name.to_lowercase().contains(&query) => 2,
+            _ if snippet.text.to_lowercase().contains(&query) => 3,
+            _ => continue,
+        };
+        ranked.push((rank, snippet));
+    }
+
+    ranked.sort_by(|(a_rank, a), (b_rank, b)| {
+        a_rank.cmp(b_rank).then_with(|| most_recent_first(a, b))
+    });
+    Some(
+        ranked
+            .into_iter()
+            .map(|(_, snippet)| snippet_match(snippet))
+            .collect(),
+    )
+}
+
+// Create a snippet, or replace the one with `snippet_id`
+#[command]
+pub fn save_snippet(
+    snippet: SnippetInput,
+    snippet_id: Option<String>,
+    app_handle: AppHandle,
+    snippet_state: State<'_, SnippetState>,
+) -> Result<SnippetMatch, String> {
+    let name = snippet.name.trim().to_string();
+    if name.is_empty() {
+        return Err("Snippet name can't be empty".to_string());
+    }
+    if snippet.text.is_empty() {
+        return Err("Snippet text can't be empty".to_string());
+    }
+    let keyword = normalize_keyword(snippet.keyword)?;
+
+    snippet_state.update(&app_handle, |snippets| {
+        let id = snippet_id.unwrap_or_default();
+        if let Some(keyword) = &keyword {
+            if let Some(owner) = keyword_owner(snippets, keyword, &id) {
+                return Err(format!(
+                    "Keyword {} is already used by {}",
+                    keyword, owner.name
+                ));
+            }
+        }
+
+        let saved = match snippets.iter_mut().find(|existing| existing.id == id) {
+            Some(existing) => {
+                existing.name = name;
+                existing.text = snippet.text;
+                existing.keyword = keyword;
+                existing.clone()
+            }
+            None if !id.is_empty() => return Err(format!("Snippet not found: {}", id)),
+            None => {
+                let created = Snippet {
+                    id: Uuid::new_v4().to_string(),
+                    name,
+                    text: snippet.text,
+                    keyword,
+                    created_at: chrono::Utc::now().timestamp(),
+                    last_used: None,
+                    use_count: 0,
+                };
+                snippets.push(created.clone());
+                created
+            }
+        };
+        Ok(snippet_match(&saved))
+    })
+}
+
+#[command]
+pub fn delete_snippet(
+    snippet_id: String,
+    app_handle: AppHandle,
+    snippet_state: State<'_, SnippetState>,
+) -> Result<(), String> {
+    snippet_state.update(&app_handle, |snippets| {
+        let count = snippets.len();
+        snippets.retain(|snippet| snippet.id != snippet_id);
+        if snippets.len() == count {
+            return Err(format!("Snippet not found: {}", snippet_id));
+        }
+        Ok(())
+    })
+}
+
+pub(super) fn expand(
+    app_handle: &AppHandle,
+    snippet_id: &str,
+    inputs: Option<HashMap<String, String>>,
+) -> Result<ExpandedSnippet, String> {
+    let snippet_state = app_handle.state::<SnippetState>();
+    let text = snippet_state.with_snippets(app_handle, |snippets| {
+        snippets
+            .iter()
+            .find(|snippet| snippet.id == snippet_id)
+            .map(|snippet| snippet.text.clone())
+    })?;
+    let text = text.ok_or_else(|| format!("Snippet not found: {}", snippet_id))?;
+
+    expand_placeholders(
+        &text,
+        &inputs.unwrap_or_default(),
+        chrono::Local::now(),
+        || arboard::Clipboard::new().ok()?.get_text().ok(),
+    )
+}
+
+pub(super) fn mark_used(app_handle: &AppHandle, snippet_id: &str) -> Result<(), String> {
+    app_handle
+        .state::<SnippetState>()
+        .update(app_handle, |snippets| {
+            if let Some(snippet) = snippets.iter_mut().find(|snippet| snippet.id == snippet_id) {
+                snippet.last_used = Some(chrono::Utc::now().timestamp());
+                snippet.use_count += 1;
+            }
+            Ok(())
+        })
+}
+
+// Fill in the placeholders without pasting, e.g. for a preview
+#[command]
+pub fn expand_snippet(
+    snippet_id: String,
+    inputs: Option<HashMap<String, String>>,
+    app_handle: AppHandle,
+) -> Result<ExpandedSnippet, String> {
+    expand(&app_handle, &snippet_id, inputs)
+}
+
+// Fill in the placeholders and paste the result into the window that was
+// active before the launcher opened
+#[command]
+pub async fn paste_snippet(
+    snippet_id: String,
+    inputs: Option<HashMap<String, String>>,
+    app_handle: AppHandle,
+) -> Result<ExpandedSnippet, String> {
+    let expanded = expand(&app_handle, &snippet_id, inputs)?;
+    if let Err(e) = mark_used(&app_handle, &snippet_id) {
+        eprintln!("Failed to update snippet usage: {}", e);
+    }
+
+    paste_text(&app_handle, &expanded.text, expanded.cursor_back)?;
+    Ok(expanded)
+}
+
+// Import snippets exported from Raycast or Alfred. Keywords that are already
+// taken are dropped, the snippet itself is still imported.
+#[command]
+pub fn import_snippets(
+    path: String,
+    app_handle: AppHandle,
+    snippet_state: State<'_, SnippetState>,
+) -> Result<SnippetImportSummary, String> {
+    let imported = read_foreign_snippets(Path::new(&path))?;
+
+    snippet_state.update(&app_handle, |snippets| {
+        let mut summary = SnippetImportSummary {
+            added: 0,
+            skipped: 0,
+        };
+
+        for input in imported {
+            let exists = snippets
+                .iter()
+                .any(|snippet| snippet.name == input.name && snippet.text == input.text);
+            if exists || input.text.is_empty() {
+                summary.skipped += 1;
+                continue;
+            }
+
+            let keyword = normalize_keyword(input.keyword)
+                .ok()
+                .flatten()
+                .filter(|keyword| keyword_owner(snippets, keyword, "").is_none());
+            snippets.push(Snippet {
+                id: Uuid::new_v4().to_string(),
+                name: input.name,
+                text: input.text,
+                keyword,
+                created_at: chrono::Utc::now().timestamp(),
+                last_used: None,
+                use_count: 0,
+            });
+            summary.added += 1;
+        }
+
+        Ok(summary)
+    })
+}
@@ -0,0 +1,457 @@
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::clip_board::paste::replace_typed_text;
+use crate::commands::clip_board::source_app::{foreground_window, source_for_window};
+use crate::commands::snippets::commands::{expand, mark_used};
+use crate::commands::snippets::placeholders::input_names;
+use crate::commands::snippets::state::SnippetState;
+
+// Only the end of what was typed can complete a keyword
+const MAX_TYPED_CHARS: usize = 64;
+
+// Keys sent for an expansion come back through the keyboard watcher, they
+// are dropped once this has passed
+const EXPANSION_SETTLE_DELAY: Duration = Duration::from_millis(150);
+
+// Snippets that can't be read, e.g. while the history key is locked, aren't
+// asked for again on every key
+const LOCKED_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+// What a key press did to the text in the focused window
+pub enum Keystroke {
+    Char(char),
+    Backspace,
+    // Enter, arrows, shortcuts and the like, what was typed so far is done
+    Reset,
+}
+
+// A snippet whose keyword was just typed
+struct TypedKeyword {
+    snippet_id: String,
+    keyword_chars: usize,
+}
+
+// Watch what is typed in other apps and replace snippet keywords with their
+// snippet as soon as they are complete. Keywords only count at the start of a
+// word, and snippets that ask for `{input:Name}` values are left alone.
+pub fn start_snippet_expander(app_handle: AppHandle) {
+    let (keystrokes, received) = mpsc::channel();
+
+    let spawned = thread::Builder::new()
+        .name("snippet-expander".to_string())
+        .spawn(move || expand_keywords(&app_handle, received));
+    if let Err(e) = spawned {
+        eprintln!("Failed to start snippet expander: {}", e);
+        return;
+    }
+
+    let spawned = thread::Builder::new()
+        .name("snippet-keyboard".to_string())
+        .spawn(move || {
+            if let Err(e) = native::watch_keyboard(keystrokes) {
+                eprintln!("Snippet keywords are not expanded: {}", e);
+            }
+        });
+    if let Err(e) = spawned {
+        eprintln!("Failed to watch the keyboard for snippet keywords: {}", e);
+    }
+}
+
+fn expand_keywords(app_handle: &AppHandle, keystrokes: Receiver<Keystroke>) {
+    let own_path = std::env::current_exe().ok();
+    let mut typed = String::new();
+    let mut window = None;
+    let mut in_own_window = false;
+    let mut locked_since: Option<Instant> = None;
+
+    while let Ok(keystroke) = keystrokes.recv() {
+        // Typing in another window starts over
+        let foreground = foreground_window();
+        if foreground != window {
+            typed.clear();
+            window = foreground;
+            in_own_window =
+                window.is_some_and(|window| is_own_window(app_handle, own_path.as_deref(), window));
+        }
+
+        match keystroke {
+            Keystroke::Char(c) => typed.push(c),
+            Keystroke::Backspace => {
+                typed.pop();
+                continue;
+            }
+            Keystroke::Reset => {
+                typed.clear();
+                continue;
+            }
+        }
+        if typed.chars().count() > MAX_TYPED_CHARS {
+            typed.remove(0);
+        }
+
+        if in_own_window
+            || locked_since.is_some_and(|since| since.elapsed() < LOCKED_RETRY_INTERVAL)
+        {
+            continue;
+        }
+
+        let keyword = match typed_keyword(app_handle, &typed) {
+            Ok(Some(keyword)) => keyword,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Snippet keywords are unavailable: {}", e);
+                locked_since = Some(Instant::now());
+                continue;
+            }
+        };
+        locked_since = None;
+        typed.clear();
+
+        if let Err(e) = expand_keyword(app_handle, &keyword) {
+            eprintln!("Failed to expand snippet keyword: {}", e);
+        }
+        thread::sleep(EXPANSION_SETTLE_DELAY);
+        while keystrokes.try_recv().is_ok() {}
+    }
+}
+
+// The launcher ranks snippets by keyword itself. Looking up the window's app
+// takes a while, it is only done when another window gets the focus.
+fn is_own_window(app_handle: &AppHandle, own_path: Option<&Path>, window: u64) -> bool {
+    let own_path = match own_path {
+        Some(own_path) => own_path,
+        None => return false,
+    };
+    source_for_window(app_handle, window).is_some_and(|source| Path::new(&source.path) == own_path)
+}
+
+// Snippet whose keyword `typed` ends with, if the keyword starts a word
+fn typed_keyword(app_handle: &AppHandle, typed: &str) -> Result<Option<TypedKeyword>, String> {
+    let snippet_state = match app_handle.try_state::<SnippetState>() {
+        Some(snippet_state) => snippet_state,
+        None => return Ok(None),
+    };
+
+    snippet_state.with_snippets(app_handle, |snippets| {
+        snippets.iter().find_map(|snippet| {
+            let keyword = snippet.keyword.as_deref()?;
+            let start = typed.len().checked_sub(keyword.len())?;
+            if !typed.is_char_boundary(start) || !typed[start..].eq_ignore_ascii_case(keyword) {
+                return None;
+            }
+            let at_word_start = typed[..start]
+                .chars()
+                .next_back()
+                .map_or(true, |before| !before.is_alphanumeric());
+            if !at_word_start || !input_names(&snippet.text).is_empty() {
+                return None;
+            }
+
+            Some(TypedKeyword {
+                snippet_id: snippet.id.clone(),
+                keyword_chars: keyword.chars().count(),
+            })
+        })
+    })
+}
+
+fn expand_keyword(app_handle: &AppHandle, keyword: &TypedKeyword) -> Result<(), String> {
+    let expanded = expand(app_handle, &keyword.snippet_id, None)?;
+    replace_typed_text(
+        app_handle,
+        keyword.keyword_chars,
+        &expanded.text,
+        expanded.cursor_back,
+    )?;
+
+    if let Err(e) = mark_used(app_handle, &keyword.snippet_id) {
+        eprintln!("Failed to update snippet usage: {}", e);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+mod native {
+    use std::mem;
+    use std::ptr;
+    use std::sync::mpsc::Sender;
+    use std::sync::Mutex;
+    use winapi::shared::minwindef::{LPARAM, LRESULT, WPARAM};
+    use winapi::um::winuser::{
+        CallNextHookEx, GetAsyncKeyState, GetForegroundWindow, GetKeyState, GetKeyboardLayout,
+        GetMessageW, GetWindowThreadProcessId, SetWindowsHookExW, ToUnicodeEx, UnhookWindowsHookEx,
+        HC_ACTION, KBDLLHOOKSTRUCT, LLKHF_INJECTED, MSG, VK_BACK, VK_CAPITAL, VK_CONTROL,
+        VK_LCONTROL, VK_LMENU, VK_LSHIFT, VK_LWIN, VK_MENU, VK_RCONTROL, VK_RMENU, VK_RSHIFT,
+        VK_RWIN, VK_SHIFT, WH_KEYBOARD_LL, WM_KEYDOWN, WM_SYSKEYDOWN,
+    };
+
+    use super::Keystroke;
+
+    // Leaves the dead key state of the focused app alone
+    const TO_UNICODE_NO_STATE_CHANGE: u32 = 4;
+
+    // The hook procedure gets no user data, so the sender lives here
+    static KEYSTROKES: Mutex<Option<Sender<Keystroke>>> = Mutex::new(None);
+
+    pub fn watch_keyboard(keystrokes: Sender<Keystroke>) -> Result<(), String> {
+        *KEYSTROKES
+            .lock()
+            .map_err(|_| "Keyboard watcher lock poisoned".to_string())? = Some(keystrokes);
+
+        let hook =
+            unsafe { SetWindowsHookExW(WH_KEYBOARD_LL, Some(hook_proc), ptr::null_mut(), 0) };
+        if hook.is_null() {
+            return Err("Failed to install the keyboard hook".to_string());
+        }
+
+        // Low level hooks are called from this thread's message loop
+        unsafe {
+            let mut message: MSG = mem::zeroed();
+            while GetMessageW(&mut message, ptr::null_mut(), 0, 0) > 0 {}
+            UnhookWindowsHookEx(hook);
+        }
+        Ok(())
+    }
+
+    unsafe extern "system" fn hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        let key_down = wparam as u32 == WM_KEYDOWN || wparam as u32 == WM_SYSKEYDOWN;
+        if code == HC_ACTION && key_down {
+            let info = &*(lparam as *const KBDLLHOOKSTRUCT);
+            // Keys sent by an app, like our own expansions, aren't typing
+            if info.flags & LLKHF_INJECTED == 0 {
+                if let Some(keystroke) = keystroke(info.vkCode as i32, info.scanCode) {
+                    if let Ok(keystrokes) = KEYSTROKES.lock() {
+                        if let Some(keystrokes) = keystrokes.as_ref() {
+                            let _ = keystrokes.send(keystroke);
+                        }
+                    }
+                }
+            }
+        }
+        CallNextHookEx(ptr::null_mut(), code, wparam, lparam)
+    }
+
+    fn is_down(virtual_key: i32) -> bool {
+        unsafe { GetAsyncKeyState(virtual_key) < 0 }
+    }
+
+    unsafe fn keystroke(virtual_key: i32, scan_code: u32) -> Option<Keystroke> {
+        match virtual_key {
+            VK_BACK => return Some(Keystroke::Backspace),
+            VK_SHIFT | VK_LSHIFT | VK_RSHIFT | VK_CAPITAL | VK_CONTROL | VK_LCONTROL
+            | VK_RCONTROL | VK_MENU | VK_LMENU | VK_RMENU | VK_LWIN | VK_RWIN => return None,
+            _ => {}
+        }
+
+        // Shortcuts aren't typing. AltGr shows up as Control and Alt together.
+        let control = is_down(VK_CONTROL);
+        let alt = is_down(VK_MENU);
+        if control != alt || is_down(VK_LWIN) || is_down(VK_RWIN) {
+            return Some(Keystroke::Reset);
+        }
+
+        let mut state = [0u8; 256];
+        if is_down(VK_SHIFT) {
+            state[VK_SHIFT as usize] = 0x80;
+        }
+        if control && alt {
+            state[VK_CONTROL as usize] = 0x80;
+            state[VK_MENU as usize] = 0x80;
+        }
+        if GetKeyState(VK_CAPITAL) & 1 != 0 {
+            state[VK_CAPITAL as usize] = 0x01;
+        }
+
+        // The focused app's layout, not ours
+        let layout = GetKeyboardLayout(GetWindowThreadProcessId(
+            GetForegroundWindow(),
+            ptr::null_mut(),
+        ));
+        let mut buffer = [0u16; 8];
+        let written = ToUnicodeEx(
+            virtual_key as u32,
+            scan_code,
+            state.as_ptr(),
+            buffer.as_mut_ptr(),
+            buffer.len() as i32,
+            TO_UNICODE_NO_STATE_CHANGE,
+            layout,
+        );
+
+        // Navigation keys, Enter and dead keys end the word
+        let typed = match written {
+            1 => char::from_u32(buffer[0] as u32).filter(|c| !c.is_control()),
+            _ => None,
+        };
+        Some(typed.map_or(Keystroke::Reset, Keystroke::Char))
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod native {
+    use std::collections::HashSet;
+    use std::sync::mpsc::Sender;
+    use x11rb::connection::{Connection, RequestConnection};
+    use x11rb::protocol::record::{self, ConnectionExt as _, Range, Range8, CS};
+    use x11rb::protocol::xproto::{ConnectionExt as _, Keycode, Keysym, KEY_PRESS_EVENT};
+
+    use super::Keystroke;
+
+    const XK_BACKSPACE: Keysym = 0xff08;
+    const XK_CAPS_LOCK: Keysym = 0xffe5;
+    const SHIFT_KEYSYMS: &[Keysym] = &[0xffe1, 0xffe2];
+    // Control, Meta, Alt and Super, left and right
+    const SHORTCUT_KEYSYMS: &[Keysym] = &[
+        0xffe3, 0xffe4, 0xffe7, 0xffe8, 0xffe9, 0xffea, 0xffeb, 0xffec,
+    ];
+
+    // Recorded events are raw wire events of 32 bytes
+    const EVENT_LEN: usize = 32;
+    // Replies of other categories only mark the start and end of recording
+    const RECORD_FROM_SERVER: u8 = 0;
+
+    // The RECORD extension reports key presses of every client without grabbing
+    // the keyboard. It needs a connection of its own for the recorded data.
+    pub fn watch_keyboard(keystrokes: Sender<Keystroke>) -> Result<(), String> {
+        let (control, _) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let (data, _) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X server: {}", e))?;
+        let has_record = data
+            .extension_information(record::X11_EXTENSION_NAME)
+            .map_err(|e| e.to_string())?
+            .is_some();
+        if !has_record {
+            return Err("The X server has no RECORD extension".to_string());
+        }
+
+        let min_keycode = control.setup().min_keycode;
+        let count = control.setup().max_keycode - min_keycode + 1;
+        let mapping = control
+            .get_keyboard_mapping(min_keycode, count)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| format!("Failed to read keyboard mapping: {}", e))?;
+        let per_keycode = mapping.keysyms_per_keycode.max(1) as usize;
+        let keysyms = |keycode: Keycode| -> &[Keysym] {
+            let start = keycode.saturating_sub(min_keycode) as usize * per_keycode;
+            mapping
+                .keysyms
+                .get(start..start + per_keycode)
+                .unwrap_or(&[])
+        };
+
+        let context = control.generate_id().map_err(|e| e.to_string())?;
+        let range = Range {
+            device_events: Range8 {
+                first: KEY_PRESS_EVENT,
+                last: KEY_PRESS_EVENT + 1,
+            },
+            ..Default::default()
+        };
+        control
+            .record_create_context(context, 0, &[CS::ALL_CLIENTS.into()], &[range])
+            .map_err(|e| e.to_string())?
+            .check()
+            .map_err(|e| format!("Failed to record key presses: {}", e))?;
+
+        let mut shift: HashSet<Keycode> = HashSet::new();
+        let mut shortcut: HashSet<Keycode> = HashSet::new();
+        let mut caps_lock = false;
+
+        let replies = data
+            .record_enable_context(context)
+            .map_err(|e| e.to_string())?;
+        for reply in replies {
+            let reply = reply.map_err(|e| format!("Recording key presses failed: {}", e))?;
+            if reply.category != RECORD_FROM_SERVER {
+                continue;
+            }
+
+            for event in reply.data.chunks_exact(EVENT_LEN) {
+                let pressed = event[0] & 0x7f == KEY_PRESS_EVENT;
+                let keycode = event[1];
+                let keysyms = keysyms(keycode);
+                let base = keysyms.first().copied().unwrap_or(0);
+
+                // Recorded device events don't carry the modifier state
+                if SHIFT_KEYSYMS.contains(&base) || SHORTCUT_KEYSYMS.contains(&base) {
+                    let held = if SHIFT_KEYSYMS.contains(&base) {
+                        &mut shift
+                    } else {
+                        &mut shortcut
+                    };
+                    if pressed {
+                        held.insert(keycode);
+                    } else {
+                        held.remove(&keycode);
+                    }
+                    continue;
+                }
+                if !pressed {
+                    continue;
+                }
+                if base == XK_CAPS_LOCK {
+                    caps_lock = !caps_lock;
+                    continue;
+                }
+
+                let keystroke = if !shortcut.is_empty() {
+                    Keystroke::Reset
+                } else if base == XK_BACKSPACE {
+                    Keystroke::Backspace
+                } else {
+                    char_for(keysyms, !shift.is_empty(), caps_lock)
+                        .map_or(Keystroke::Reset, Keystroke::Char)
+                };
+                if keystrokes.send(keystroke).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn char_for(keysyms: &[Keysym], shift: bool, caps_lock: bool) -> Option<char> {
+        let base = keysyms.first().copied().unwrap_or(0);
+        let shifted = keysyms
+            .get(1)
+            .copied()
+            .filter(|&keysym| keysym != 0)
+            .unwrap_or(base);
+        let keysym = if shift { shifted } else { base };
+
+        // Latin-1 keysyms equal their code points, the rest are offset
+        let c = match keysym {
+            0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+            0x0100_0000..=0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+            _ => None,
+        }?;
+
+        // Caps Lock only changes letters
+        if caps_lock && c.is_alphabetic() {
+            let flipped = if c.is_lowercase() {
+                c.to_uppercase().next()
+            } else {
+                c.to_lowercase().next()
+            };
+            return flipped;
+        }
+        Some(c)
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+mod native {
+    use std::sync::mpsc::Sender;
+
+    use super::Keystroke;
+
+    pub fn watch_keyboard(_keystrokes: Sender<Keystroke>) -> Result<(), String> {
+        Err("Watching the keyboard is not supported on this platform".to_string())
+    }
+}
@@ -0,0 +1,126 @@
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+use crate::commands::snippets::models::SnippetInput;
+
+// Read snippets exported by Raycast (a JSON array of name, text and keyword)
+// or Alfred (one `alfredsnippet` object per file). A directory is read as an
+// unzipped Alfred collection.
+pub fn read_foreign_snippets(path: &Path) -> Result<Vec<SnippetInput>, String> {
+    if !path.is_dir() {
+        return parse_file(path);
+    }
+
+    let entries =
+        fs::read_dir(path).map_err(|e| format!("Failed to read snippet directory: {}", e))?;
+    let mut snippets = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().unwrap_or_default() != "json" {
+            continue;
+        }
+        match parse_file(&path) {
+            Ok(parsed) => snippets.extend(parsed),
+            Err(e) => eprintln!("Skipping snippet file {}: {}", path.display(), e),
+        }
+    }
+    Ok(snippets)
+}
+
+fn parse_file(path: &Path) -> Result<Vec<SnippetInput>, String> {
+    let data =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read snippet file: {}", e))?;
+    let value: Value =
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse snippet file: {}", e))?;
+
+    let entries = match value {
+        Value::Array(entries) => entries,
+        Value::Object(ref object) => match object.get("snippets") {
+            Some(Value::Array(entries)) => entries.clone(),
+            _ => vec![value],
+        },
+        _ => return Err("Unrecognized snippet file".to_string()),
+    };
+
+    Ok(entries.iter().filter_map(parse_entry).collect())
+}
+
+fn parse_entry(entry: &Value) -> Option<SnippetInput> {
+    let entry = entry.get("alfredsnippet").unwrap_or(entry);
+    let text = entry
+        .get("text")
+        .or_else(|| entry.get("snippet"))?
+        .as_str()?;
+    let field = |name: &str| {
+        entry
+            .get(name)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    };
+
+    Some(SnippetInput {
+        name: field("name")
+            .map(str::to_string)
+            .unwrap_or_else(|| text.lines().next().unwrap_or_default().to_string()),
+        text: convert_placeholders(text),
+        keyword: field("keyword").map(str::to_string),
+    })
+}
+
+// Rewrite Raycast and Alfred placeholders into ours. Both format dates with
+// Unicode patterns, those get the default format instead.
+fn convert_placeholders(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('{') {
+        converted.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let replacement = after
+            .find('}')
+            .and_then(|end| convert_placeholder(&after[..end]).map(|new| (end, new)));
+
+        match replacement {
+            Some((end, replacement)) => {
+                converted.push_str(&replacement);
+                rest = &after[end + 1..];
+            }
+            None => {
+                converted.push('{');
+                rest = after;
+            }
+        }
+    }
+    converted.push_str(rest);
+    converted
+}
+
+fn convert_placeholder(inner: &str) -> Option<String> {
+    let inner = inner.trim();
+    let name = inner
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .next()?
+        .to_lowercase();
+
+    let converted = match name.as_str() {
+        "datetime" => "{date} {time}".to_string(),
+        "date" => "{date}".to_string(),
+        "time" => "{time}".to_string(),
+        "clipboard" => "{clipboard}".to_string(),
+        "cursor" => "{cursor}".to_string(),
+        "uuid" => "{uuid}".to_string(),
+        "random" if inner.eq_ignore_ascii_case("random:uuid") => "{uuid}".to_string(),
+        // Raycast: {argument name="Recipient" default="..."}
+        "argument" => format!("{{input:{}}}", attribute(inner, "name")?),
+        _ => return None,
+    };
+    Some(converted)
+}
+
+fn attribute<'a>(placeholder: &'a str, name: &str) -> Option<&'a str> {
+    let start = placeholder.find(&format!("{}=\"", name))? + name.len() + 2;
+    let length = placeholder[start..].find('"')?;
+    Some(&placeholder[start..start + length]).filter(|value| !value.trim().is_empty())
+}
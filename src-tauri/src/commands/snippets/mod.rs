@@ -0,0 +1,16 @@
+mod commands;
+mod expander;
+mod import;
+mod models;
+mod placeholders;
+mod state;
+mod storage;
+
+pub use commands::{
+    delete_snippet, expand_snippet, filter_snippets, get_snippets, import_snippets, paste_snippet,
+    save_snippet, search_snippets,
+};
+pub use expander::start_snippet_expander;
+pub use models::{Snippet, SnippetMatch};
+pub use state::SnippetState;
+pub(crate) use storage::{get_snippets_path, get_snippets_rotation_path};
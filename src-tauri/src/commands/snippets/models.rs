@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Snippet {
+    pub id: String,
+    pub name: String,
+    // Template text, see `placeholders` for what gets filled in
+    pub text: String,
+    // Typed in another app it is replaced by the snippet, typed in the
+    // launcher it puts the snippet first
+    #[serde(default)]
+    pub keyword: Option<String>,
+    pub created_at: i64,
+    pub last_used: Option<i64>,
+    pub use_count: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnippetInput {
+    pub name: String,
+    pub text: String,
+    pub keyword: Option<String>,
+}
+
+// Search result, with the `{input:Name}` values the snippet asks for
+#[derive(Debug, Serialize, Clone)]
+pub struct SnippetMatch {
+    #[serde(flatten)]
+    pub snippet: Snippet,
+    pub inputs: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct SnippetImportSummary {
+    pub added: usize,
+    // Snippets with the same name and text already existed
+    pub skipped: usize,
+}
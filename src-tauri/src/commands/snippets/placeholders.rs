@@ -0,0 +1,160 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+// Placeholders a snippet can contain:
+//   {date} {date:%d.%m.%Y}  current date, chrono strftime format
+//   {time} {time:%H:%M:%S}  current time
+//   {clipboard}             current clipboard text
+//   {uuid}                  random UUID v4
+//   {cursor}                where the caret ends up after pasting
+//   {input:Name}            value asked for before pasting
+enum Placeholder<'a> {
+    Date(Option<&'a str>),
+    Time(Option<&'a str>),
+    Clipboard,
+    Uuid,
+    Cursor,
+    Input(&'a str),
+}
+
+enum Token<'a> {
+    Text(&'a str),
+    Placeholder(Placeholder<'a>),
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ExpandedSnippet {
+    pub text: String,
+    // Characters after the {cursor} position, the caret is moved back by this much
+    pub cursor_back: usize,
+}
+
+fn parse_placeholder(inner: &str) -> Option<Placeholder<'_>> {
+    let (name, argument) = match inner.split_once(':') {
+        Some((name, argument)) => (name, Some(argument)),
+        None => (inner, None),
+    };
+
+    match (name, argument) {
+        ("date", argument) => Some(Placeholder::Date(argument)),
+        ("time", argument) => Some(Placeholder::Time(argument)),
+        ("clipboard", None) => Some(Placeholder::Clipboard),
+        ("uuid", None) => Some(Placeholder::Uuid),
+        ("cursor", None) => Some(Placeholder::Cursor),
+        ("input", Some(name)) if !name.trim().is_empty() => Some(Placeholder::Input(name.trim())),
+        _ => None,
+    }
+}
+
+// Braces that don't form a known placeholder are kept as they are, so code
+// and JSON snippets need no escaping
+fn tokenize(template: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let placeholder = after
+            .find(['{', '}'])
+            .filter(|&end| after.as_bytes()[end] == b'}')
+            .and_then(|end| parse_placeholder(&after[..end]).map(|placeholder| (end, placeholder)));
+
+        match placeholder {
+            Some((end, placeholder)) => {
+                if start > 0 {
+                    tokens.push(Token::Text(&rest[..start]));
+                }
+                tokens.push(Token::Placeholder(placeholder));
+                rest = &after[end + 1..];
+            }
+            None => {
+                tokens.push(Token::Text(&rest[..start + 1]));
+                rest = after;
+            }
+        }
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+
+    tokens
+}
+
+// Names of the {input:Name} values a snippet needs, in order of appearance
+pub fn input_names(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for token in tokenize(template) {
+        if let Token::Placeholder(Placeholder::Input(name)) = token {
+            if !names.iter().any(|existing| existing == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+fn format_now(now: &DateTime<Local>, format: &str) -> Result<String, String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("Invalid date format in snippet: {}", format));
+    }
+    Ok(now
+        .format_with_items(StrftimeItems::new(format))
+        .to_string())
+}
+
+// Fill in every placeholder. The clipboard is only read if the snippet uses it.
+pub fn expand_placeholders(
+    template: &str,
+    inputs: &HashMap<String, String>,
+    now: DateTime<Local>,
+    read_clipboard: impl FnOnce() -> Option<String>,
+) -> Result<ExpandedSnippet, String> {
+    let mut text = String::with_capacity(template.len());
+    let mut read_clipboard = Some(read_clipboard);
+    let mut clipboard: Option<String> = None;
+    let mut cursor: Option<usize> = None;
+
+    for token in tokenize(template) {
+        match token {
+            Token::Text(literal) => text.push_str(literal),
+            Token::Placeholder(Placeholder::Date(format)) => {
+                text.push_str(&format_now(&now, format.unwrap_or(DEFAULT_DATE_FORMAT))?)
+            }
+            Token::Placeholder(Placeholder::Time(format)) => {
+                text.push_str(&format_now(&now, format.unwrap_or(DEFAULT_TIME_FORMAT))?)
+            }
+            Token::Placeholder(Placeholder::Clipboard) => {
+                let clipboard = clipboard.get_or_insert_with(|| {
+                    read_clipboard
+                        .take()
+                        .and_then(|read| read())
+                        .unwrap_or_default()
+                });
+                text.push_str(clipboard);
+            }
+            Token::Placeholder(Placeholder::Uuid) => text.push_str(&Uuid::new_v4().to_string()),
+            // Only the first one counts, the caret can't be in two places
+            Token::Placeholder(Placeholder::Cursor) => {
+                cursor.get_or_insert_with(|| caret_len(&text));
+            }
+            Token::Placeholder(Placeholder::Input(name)) => match inputs.get(name) {
+                Some(value) => text.push_str(value),
+                None => return Err(format!("Missing value for {{input:{}}}", name)),
+            },
+        }
+    }
+
+    let cursor_back = cursor.map_or(0, |cursor| caret_len(&text) - cursor);
+    Ok(ExpandedSnippet { text, cursor_back })
+}
+
+// Caret steps needed to cross the text, a CRLF line break is a single step
+fn caret_len(text: &str) -> usize {
+    text.chars().filter(|&c| c != '\r').count()
+}
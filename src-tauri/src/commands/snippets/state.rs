@@ -0,0 +1,151 @@
+use serde::Serialize;
+use std::fs;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::commands::clip_board::key_protector::{get_encryption_key, HistoryKey};
+use crate::commands::snippets::models::Snippet;
+use crate::commands::snippets::storage::{
+    get_snippets_path, get_snippets_rotation_path, read_snippets, write_snippets,
+    write_snippets_to, ReadSnippetsError,
+};
+
+pub const SNIPPETS_RECOVERED_EVENT: &str = "snippets-recovered";
+
+// Payload of the `snippets-recovered` event
+#[derive(Serialize, Clone)]
+pub struct SnippetsRecoveryNotice {
+    // Why the snippets file couldn't be read
+    pub reason: String,
+    pub moved_to: String,
+}
+
+// A snippets file that is sealed with the current key but doesn't open would
+// fail every snippet command, so it is moved aside like an unreadable history
+// and the snippets start empty
+fn move_unreadable_aside(app_handle: &AppHandle, reason: &str) -> Result<(), String> {
+    let snippets_path = get_snippets_path(app_handle)?;
+    let moved_to = snippets_path.with_extension(format!(
+        "encrypted.unreadable-{}",
+        chrono::Utc::now().timestamp_millis()
+    ));
+    fs::rename(&snippets_path, &moved_to)
+        .map_err(|e| format!("Failed to move unreadable snippets aside: {}", e))?;
+    eprintln!(
+        "Snippets could not be read ({}), moved to {}",
+        reason,
+        moved_to.display()
+    );
+
+    let notice = SnippetsRecoveryNotice {
+        reason: reason.to_string(),
+        moved_to: moved_to.to_string_lossy().to_string(),
+    };
+    if let Err(e) = app_handle.emit_all(SNIPPETS_RECOVERED_EVENT, notice) {
+        eprintln!("Failed to emit snippets recovery: {}", e);
+    }
+    Ok(())
+}
+
+// Snippets are read on first use, the history key may still be locked at startup
+#[derive(Default)]
+pub struct SnippetState {
+    snippets: Mutex<Option<Vec<Snippet>>>,
+}
+
+impl SnippetState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_loaded<T>(
+        &self,
+        app_handle: &AppHandle,
+        f: impl FnOnce(&mut Vec<Snippet>) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut snippets = self
+            .snippets
+            .lock()
+            .map_err(|_| "Failed to lock snippets state".to_string())?;
+
+        if snippets.is_none() {
+            // Without the key, or with another one, the file stays where it
+            // is. The right key may turn up later.
+            let key = get_encryption_key(app_handle)?;
+            *snippets = Some(match read_snippets(app_handle, &key) {
+                Ok(snippets) => snippets,
+                Err(ReadSnippetsError::Unreadable(e)) => {
+                    move_unreadable_aside(app_handle, &e)?;
+                    Vec::new()
+                }
+                Err(ReadSnippetsError::Failed(e)) => return Err(e),
+            });
+        }
+
+        match snippets.as_mut() {
+            Some(snippets) => f(snippets),
+            None => Err("Snippets are not loaded".to_string()),
+        }
+    }
+
+    pub fn with_snippets<T>(
+        &self,
+        app_handle: &AppHandle,
+        f: impl FnOnce(&[Snippet]) -> T,
+    ) -> Result<T, String> {
+        self.with_loaded(app_handle, |snippets| Ok(f(snippets)))
+    }
+
+    // Change the snippets and write them back. Nothing is kept in memory if
+    // the write fails.
+    pub fn update<T>(
+        &self,
+        app_handle: &AppHandle,
+        f: impl FnOnce(&mut Vec<Snippet>) -> Result<T, String>,
+    ) -> Result<T, String> {
+        self.with_loaded(app_handle, |snippets| {
+            let mut changed = snippets.clone();
+            let result = f(&mut changed)?;
+
+            let key = get_encryption_key(app_handle)?;
+            write_snippets(app_handle, &changed, &key)?;
+            *snippets = changed;
+            Ok(result)
+        })
+    }
+
+    // Seal the snippets with `new_key` next to the current file, run `commit`
+    // to replace the key and only then swap the files. Nothing changes if
+    // either fails, see `finish_interrupted_rotation`.
+    pub fn rotate<T>(
+        &self,
+        app_handle: &AppHandle,
+        old_key: &HistoryKey,
+        new_key: &HistoryKey,
+        commit: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let _snippets = self
+            .snippets
+            .lock()
+            .map_err(|_| "Failed to lock snippets state".to_string())?;
+
+        let snippets_path = get_snippets_path(app_handle)?;
+        if !snippets_path.exists() {
+            return commit();
+        }
+
+        let rotation_path = get_snippets_rotation_path(app_handle)?;
+        let snippets =
+            read_snippets(app_handle, old_key).map_err(ReadSnippetsError::into_message)?;
+        let committed =
+            write_snippets_to(&rotation_path, &snippets, new_key).and_then(|_| commit());
+        if committed.is_err() {
+            let _ = fs::remove_file(&rotation_path);
+            return committed;
+        }
+
+        fs::rename(&rotation_path, &snippets_path)
+            .map_err(|e| format!("Failed to replace snippets: {}", e))?;
+        committed
+    }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::commands::clip_board::envelope;
+use crate::commands::clip_board::key_protector::HistoryKey;
+use crate::commands::snippets::models::Snippet;
+use crate::persistence::files::write_atomic;
+
+#[derive(Serialize, Deserialize, Default)]
+struct SnippetsFile {
+    snippets: Vec<Snippet>,
+}
+
+// Snippets are sealed with the clipboard history key, they often hold
+// addresses and replies that shouldn't sit on disk in plain text
+pub(crate) fn get_snippets_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path_resolver()
+        .app_data_dir()
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+
+    Ok(app_data_dir.join("snippets.encrypted"))
+}
+
+// Written during a key rotation, see `SnippetState::rotate`
+pub(crate) fn get_snippets_rotation_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    Ok(get_snippets_path(app_handle)?.with_extension("encrypted.rotating"))
+}
+
+// Only a file sealed with the given key that still doesn't open is known to be
+// broken. Anything else, e.g. a read error or another key, may go away again.
+pub enum ReadSnippetsError {
+    Unreadable(String),
+    Failed(String),
+}
+
+impl ReadSnippetsError {
+    pub fn into_message(self) -> String {
+        match self {
+            ReadSnippetsError::Unreadable(message) | ReadSnippetsError::Failed(message) => message,
+        }
+    }
+}
+
+pub fn read_snippets(
+    app_handle: &AppHandle,
+    key: &HistoryKey,
+) -> Result<Vec<Snippet>, ReadSnippetsError> {
+    let snippets_path = get_snippets_path(app_handle).map_err(ReadSnippetsError::Failed)?;
+    if !snippets_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let data = fs::read_to_string(&snippets_path)
+        .map_err(|e| ReadSnippetsError::Failed(format!("Failed to read snippets: {}", e)))?;
+    let sealed_with_key = envelope::parse(&data)
+        .map_err(ReadSnippetsError::Failed)?
+        .key_id
+        == envelope::key_id(key);
+
+    let snippets = envelope::open(key, &data).and_then(|json| {
+        serde_json::from_slice::<SnippetsFile>(&json)
+            .map_err(|e| format!("Failed to parse snippets: {}", e))
+    });
+    match snippets {
+        Ok(snippets) => Ok(snippets.snippets),
+        Err(e) if sealed_with_key => Err(ReadSnippetsError::Unreadable(e)),
+        Err(e) => Err(ReadSnippetsError::Failed(e)),
+    }
+}
+
+pub fn write_snippets(
+    app_handle: &AppHandle,
+    snippets: &[Snippet],
+    key: &HistoryKey,
+) -> Result<(), String> {
+    write_snippets_to(&get_snippets_path(app_handle)?, snippets, key)
+}
+
+pub fn write_snippets_to(
+    path: &Path,
+    snippets: &[Snippet],
+    key: &HistoryKey,
+) -> Result<(), String> {
+    let json = serde_json::to_vec(&SnippetsFile {
+        snippets: snippets.to_vec(),
+    })
+    .map_err(|e| format!("Failed to serialize snippets: {}", e))?;
+
+    write_atomic(path, envelope::seal(key, &json)?.as_bytes())
+}
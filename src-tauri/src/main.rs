@@ -1,17 +1,32 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
-use commands::fetch_app::models::AppIndex;
 use commands::fetch_app::models::AppIndexState;
 use tauri::GlobalShortcutManager;
 use tauri::Manager;
 mod auto;
-use std::sync::{Arc, Mutex};
 mod commands;
+mod persistence;
 use auto::auto_start::{disable_autostart, enable_autostart};
 use commands::clip_board::{
-    clear_system_clipboard, delete_from_clipboard, get_clipboard, get_clipboard_image,
-    load_clipboard_history, pin_clipboard_item, save_clipboard_history, set_clipboard,
-    set_clipboard_image,load_clipboard_image_bytes,delete_clipboard_image_file
+    add_clipboard_item, clear_clipboard_history, clear_system_clipboard,
+    delete_and_clear_clipboard_item, delete_clipboard_image_file, delete_clipboard_item,
+    delete_from_clipboard, export_clipboard_bundle, export_clipboard_text, get_clipboard,
+    get_clipboard_excluded_apps, get_clipboard_image, get_clipboard_key_error,
+    get_clipboard_key_protector, get_clipboard_retention, get_clipboard_sensitive_rules,
+    get_clipboard_sync_status, get_clipboard_typed_paste_apps, get_paste_stack,
+    import_clipboard_bundle, list_clipboard_items, load_clipboard_history,
+    load_clipboard_image_bytes, mark_clipboard_item_copied, open_clipboard_item,
+    pair_clipboard_sync_device, paste_clipboard_item, paste_next_from_stack, pin_clipboard_item,
+    remember_paste_target, remove_clipboard_sync_device, reset_clipboard_key,
+    restore_clipboard_item, rotate_clipboard_key, search_clipboard_items, set_clipboard,
+    set_clipboard_excluded_apps, set_clipboard_image, set_clipboard_key_protector,
+    set_clipboard_retention, set_clipboard_sensitive_rules, set_clipboard_sync,
+    set_clipboard_typed_paste_apps, start_clipboard_monitor, start_clipboard_sync,
+    start_clipboard_sync_pairing, start_expiry_sweeper, start_history_maintenance,
+    start_paste_stack, stop_paste_stack, sync_clipboard_now, take_clipboard_recovery_notice,
+    toggle_paste_stack, transform_clipboard_text, unlock_clipboard_history, update_clipboard_item,
+    ClipboardHistoryStore, ClipboardKeyState, ClipboardSyncState, OwnClipboardWrites,
+    PasteStackState, PasteTargetState,
 };
 use commands::fetch_app::{
     add_manual_application, get_index_status, get_recent_apps, hide_window, init_app_index,
@@ -19,30 +34,50 @@ use commands::fetch_app::{
 };
 use commands::quick_link::{
     check_vscode_path, delete_quick_link, execute_quick_link, execute_quick_link_with_command,
-    get_default_browser, get_open_with_suggestions, get_quick_links, get_recent_quick_links,
-    save_quick_link, search_quick_links,
+    export_quick_links, get_default_browser, get_open_with_suggestions, get_quick_link_arguments,
+    get_quick_links, get_recent_quick_links, import_quick_links, preview_quick_link,
+    save_quick_link, search_quick_links, update_quick_link,
+};
+use commands::search::{cancel_search, start_search, SearchState};
+use commands::snippets::{
+    delete_snippet, expand_snippet, get_snippets, import_snippets, paste_snippet, save_snippet,
+    search_snippets, start_snippet_expander, SnippetState,
 };
 use commands::window_resize::resize_window;
 
-fn schedule_index_updates(app_index_state: Arc<Mutex<AppIndex>>) {
+fn schedule_index_updates(app_index_state: AppIndexState) {
     std::thread::spawn(move || {
         loop {
             // Sleep for 6 hours before refreshing (adjust as needed)
             std::thread::sleep(std::time::Duration::from_secs(6 * 60 * 60));
 
-            // Create temporary AppIndexState for refresh
-            let temp_state = AppIndexState {
-                index: app_index_state.clone(),
-            };
             // Refresh the index
-            commands::fetch_app::app_index::refresh_app_index(&temp_state);
+            commands::fetch_app::app_index::refresh_app_index(&app_index_state);
         }
     });
 }
 
+// Make sure pending index writes reach the disk before the process goes away
+fn flush_pending_writes(app_handle: &tauri::AppHandle) {
+    if let Some(app_index_state) = app_handle.try_state::<AppIndexState>() {
+        if let Err(e) = app_index_state.index_writer.flush() {
+            eprintln!("Failed to flush app index on shutdown: {}", e);
+        }
+    }
+    if let Some(history_store) = app_handle.try_state::<ClipboardHistoryStore>() {
+        if let Err(e) = history_store.flush() {
+            eprintln!("Failed to flush clipboard history on shutdown: {}", e);
+        }
+    }
+}
+
 fn main() {
     let tray_menu = tauri::SystemTrayMenu::new()
         .add_item(tauri::CustomMenuItem::new("show", "Show"))
+        .add_item(tauri::CustomMenuItem::new(
+            "paste_stack",
+            "Start paste stack",
+        ))
         .add_native_item(tauri::SystemTrayMenuItem::Separator)
         .add_item(tauri::CustomMenuItem::new("quit", "Quit"));
     let system_tray = tauri::SystemTray::new().with_menu(tray_menu);
@@ -54,18 +89,27 @@ fn main() {
         return;
     }
 
+    // Measure app search latency against a synthetic index
+    if args.iter().any(|arg| arg == "--bench-search") {
+        let fast = commands::fetch_app::search_index::run_benchmark(20_000);
+        std::process::exit(if fast { 0 } else { 1 });
+    }
+
     tauri::Builder::default()
         .system_tray(system_tray)
         .on_system_tray_event(|app, event| match event {
             tauri::SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
                 "quit" => {
+                    flush_pending_writes(app);
                     std::process::exit(0);
                 }
                 "show" => {
+                    remember_paste_target(app);
                     let window = app.get_window("main").unwrap();
                     window.show().unwrap();
                     window.set_focus().unwrap();
                 }
+                "paste_stack" => toggle_paste_stack(app),
                 _ => {}
             },
             _ => {}
@@ -79,27 +123,80 @@ fn main() {
             get_clipboard,
             set_clipboard,
             load_clipboard_history,
-            save_clipboard_history,
             resize_window,
             clear_system_clipboard,
             delete_from_clipboard,
+            delete_and_clear_clipboard_item,
             pin_clipboard_item,
+            add_clipboard_item,
+            update_clipboard_item,
+            mark_clipboard_item_copied,
+            restore_clipboard_item,
+            paste_clipboard_item,
+            open_clipboard_item,
+            start_paste_stack,
+            stop_paste_stack,
+            get_paste_stack,
+            paste_next_from_stack,
+            get_clipboard_sync_status,
+            set_clipboard_sync,
+            start_clipboard_sync_pairing,
+            pair_clipboard_sync_device,
+            remove_clipboard_sync_device,
+            sync_clipboard_now,
+            delete_clipboard_item,
+            list_clipboard_items,
+            search_clipboard_items,
+            transform_clipboard_text,
+            clear_clipboard_history,
+            export_clipboard_bundle,
+            import_clipboard_bundle,
+            export_clipboard_text,
+            get_clipboard_key_protector,
+            set_clipboard_key_protector,
+            get_clipboard_key_error,
+            reset_clipboard_key,
+            unlock_clipboard_history,
+            rotate_clipboard_key,
+            take_clipboard_recovery_notice,
+            get_clipboard_sensitive_rules,
+            set_clipboard_sensitive_rules,
+            get_clipboard_excluded_apps,
+            set_clipboard_excluded_apps,
+            get_clipboard_retention,
+            set_clipboard_retention,
+            get_clipboard_typed_paste_apps,
+            set_clipboard_typed_paste_apps,
             get_quick_links,
             search_quick_links,
             get_recent_quick_links,
             save_quick_link,
+            update_quick_link,
             execute_quick_link,
             execute_quick_link_with_command,
+            get_quick_link_arguments,
+            preview_quick_link,
+            export_quick_links,
+            import_quick_links,
             delete_quick_link,
             get_open_with_suggestions,
             check_vscode_path,
             get_default_browser,
+            get_snippets,
+            search_snippets,
+            save_snippet,
+            delete_snippet,
+            expand_snippet,
+            paste_snippet,
+            import_snippets,
             refresh_app_index,
             add_manual_application,
             get_clipboard_image,
             set_clipboard_image,
             load_clipboard_image_bytes,
-            delete_clipboard_image_file
+            delete_clipboard_image_file,
+            start_search,
+            cancel_search
         ])
         .setup(|app| {
             // Initialize quick links
@@ -110,38 +207,82 @@ fn main() {
             // Make the app index state available to all commands
             app.manage(app_index_state);
 
+            // Load the clipboard history before anything can add to it
+            app.manage(ClipboardKeyState::new());
+            app.manage(ClipboardHistoryStore::load(&app.handle()));
+            // Where a selected history item gets pasted
+            app.manage(PasteTargetState::new());
+            // Clipboard writes made only to paste, the monitor doesn't record them
+            app.manage(OwnClipboardWrites::new());
+            app.manage(PasteStackState::new());
+            app.manage(ClipboardSyncState::new());
+            // Sealed with the history key, read on first use
+            app.manage(SnippetState::new());
+
+            // Encrypt images left in plain files by earlier versions
+            let history_store = app.state::<ClipboardHistoryStore>().inner().clone();
+            let migration_handle = app.handle();
+            std::thread::spawn(move || {
+                if let Err(e) = history_store.encrypt_legacy_images(&migration_handle) {
+                    eprintln!("Failed to encrypt clipboard images: {}", e);
+                }
+            });
+
+            // Watch the system clipboard and push changes to the webview
+            start_clipboard_monitor(app.handle());
+            // Remove sensitive items once their time is up
+            start_expiry_sweeper(app.handle());
+            // Apply retention limits and delete unreferenced image files
+            start_history_maintenance(app.handle());
+            // Serve and sync with paired devices, if turned on
+            start_clipboard_sync(app.handle());
+            // Replace snippet keywords typed in other apps
+            start_snippet_expander(app.handle());
+
+            // Track search generations so stale results are dropped
+            app.manage(SearchState::new());
+
             // Schedule periodic index updates
-            let app_index = app.state::<AppIndexState>().index.clone();
+            let app_index = app.state::<AppIndexState>().inner().clone();
             schedule_index_updates(app_index);
 
             // Register global shortcut (Alt+Space by default)
             let app_handle = app.handle();
-            app_handle
-                .global_shortcut_manager()
-                .register("Alt+Space", move || {
-                    let window = app_handle.get_window("main").unwrap();
-                    if window.is_visible().unwrap() {
-                        window.hide().unwrap();
-                    } else {
-                        window.show().unwrap();
-                        window.set_focus().unwrap();
-                    }
-                })
-                .unwrap();
+            let registered =
+                app_handle
+                    .global_shortcut_manager()
+                    .register("Alt+Space", move || {
+                        let window = app_handle.get_window("main").unwrap();
+                        if window.is_visible().unwrap() {
+                            window.hide().unwrap();
+                        } else {
+                            remember_paste_target(&app_handle);
+                            window.show().unwrap();
+                            window.set_focus().unwrap();
+                        }
+                    });
+            // Another instance may hold the shortcut, e.g. when testing sync locally
+            if let Err(e) = registered {
+                eprintln!("Failed to register Alt+Space: {}", e);
+            }
 
             // Register new global shortcut for clipboard mode (Alt+Shift+C)
             let clipboard_app_handle = app.handle();
-            clipboard_app_handle
-                .global_shortcut_manager()
-                .register("Alt+Shift+C", move || {
-                    let window = clipboard_app_handle.get_window("main").unwrap();
+            let registered =
+                clipboard_app_handle
+                    .global_shortcut_manager()
+                    .register("Alt+Shift+C", move || {
+                        let window = clipboard_app_handle.get_window("main").unwrap();
 
-                    // Show window and notify frontend to switch to clipboard mode
-                    window.show().unwrap();
-                    window.set_focus().unwrap();
-                    window.emit("switch-to-clipboard", {}).unwrap();
-                })
-                .unwrap();
+                        // Show window and notify frontend to switch to clipboard mode
+                        remember_paste_target(&clipboard_app_handle);
+                        window.show().unwrap();
+                        window.set_focus().unwrap();
+                        window.emit("switch-to-clipboard", {}).unwrap();
+                    });
+            if let Err(e) = registered {
+                eprintln!("Failed to register Alt+Shift+C: {}", e);
+            }
 
             // Enable auto-start during setup
             enable_autostart();
@@ -159,6 +300,11 @@ fn main() {
             }
             _ => {}
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                flush_pending_writes(app_handle);
+            }
+        });
 }
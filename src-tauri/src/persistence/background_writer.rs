@@ -0,0 +1,125 @@
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long to wait for things to settle before writing to disk
+#[derive(Clone, Copy)]
+pub struct DebounceTiming {
+    // Flush once no update arrived for this long
+    pub quiet: Duration,
+    // Flush at the latest this long after the first pending update
+    pub max_delay: Duration,
+}
+
+enum WriterMessage<U> {
+    Update(U),
+    Flush(Sender<()>),
+}
+
+// Applies updates on a dedicated thread and persists them in debounced batches,
+// so callers never wait on disk I/O
+pub struct BackgroundWriter<U> {
+    sender: Sender<WriterMessage<U>>,
+}
+
+impl<U> Clone for BackgroundWriter<U> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+        }
+    }
+}
+
+impl<U: Send + 'static> BackgroundWriter<U> {
+    // `apply` receives every batch of updates as soon as it arrives,
+    // `persist` runs once the batch has settled
+    pub fn spawn<A, P>(name: &str, timing: DebounceTiming, mut apply: A, mut persist: P) -> Self
+    where
+        A: FnMut(Vec<U>) + Send + 'static,
+        P: FnMut() -> Result<(), String> + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<WriterMessage<U>>();
+        let name = name.to_string();
+
+        thread::Builder::new()
+            .name(format!("{}-writer", name))
+            .spawn(move || {
+                let mut first_pending: Option<Instant> = None;
+                let mut last_update = Instant::now();
+
+                let mut write = |first_pending: &mut Option<Instant>| {
+                    if first_pending.take().is_some() {
+                        if let Err(e) = persist() {
+                            eprintln!("Failed to persist {}: {}", name, e);
+                        }
+                    }
+                };
+
+                loop {
+                    let message = match first_pending {
+                        None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                        Some(first) => {
+                            let quiet_left = timing.quiet.saturating_sub(last_update.elapsed());
+                            let max_left = timing.max_delay.saturating_sub(first.elapsed());
+                            receiver.recv_timeout(quiet_left.min(max_left))
+                        }
+                    };
+
+                    match message {
+                        Ok(WriterMessage::Update(update)) => {
+                            // Drain whatever else is queued so it's applied as one batch
+                            let mut batch = vec![update];
+                            let mut flush_requests = Vec::new();
+                            for queued in receiver.try_iter() {
+                                match queued {
+                                    WriterMessage::Update(update) => batch.push(update),
+                                    WriterMessage::Flush(ack) => flush_requests.push(ack),
+                                }
+                            }
+
+                            apply(batch);
+                            last_update = Instant::now();
+                            first_pending.get_or_insert(last_update);
+
+                            if !flush_requests.is_empty() {
+                                write(&mut first_pending);
+                                for ack in flush_requests {
+                                    let _ = ack.send(());
+                                }
+                            }
+                        }
+                        Ok(WriterMessage::Flush(ack)) => {
+                            write(&mut first_pending);
+                            let _ = ack.send(());
+                        }
+                        Err(RecvTimeoutError::Timeout) => write(&mut first_pending),
+                        Err(RecvTimeoutError::Disconnected) => {
+                            write(&mut first_pending);
+                            break;
+                        }
+                    }
+                }
+            })
+            .expect("failed to spawn background writer thread");
+
+        Self { sender }
+    }
+
+    pub fn send(&self, update: U) {
+        if self.sender.send(WriterMessage::Update(update)).is_err() {
+            eprintln!("Background writer is no longer running, update dropped");
+        }
+    }
+
+    // Write everything pending and wait until it is on disk (used on shutdown)
+    pub fn flush(&self) -> Result<(), String> {
+        let (ack_sender, ack_receiver) = mpsc::channel();
+        self.sender
+            .send(WriterMessage::Flush(ack_sender))
+            .map_err(|_| "Background writer is no longer running".to_string())?;
+
+        ack_receiver
+            .recv_timeout(Duration::from_secs(5))
+            .map_err(|_| "Timed out waiting for background writer to flush".to_string())
+    }
+}
@@ -0,0 +1,31 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Makes the temp file of every write unique within the process
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Write a file by writing a sibling temp file first and renaming it over the target,
+// so a crash mid-write never leaves a truncated file behind. Every write has a
+// temp file of its own, concurrent writers of one file never share it.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+    }
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    fs::write(&tmp_path, contents)
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("Failed to replace {}: {}", path.display(), e)
+    })
+}
@@ -0,0 +1,2 @@
+pub mod background_writer;
+pub mod files;
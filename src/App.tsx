@@ -4,15 +4,19 @@ import { CommandInput } from "./components/commandInput";
 import { SuggestionList } from "./components/suggestionList";
 import { AppInfo } from "./types";
 import { ClipboardHistory } from "./components/clipBoard/clipBoardHistory";
+import { PasteStackBanner } from "./components/clipBoard/pasteStackBanner";
 import { QuickLinkCreator } from "./components/quickLink/quickLinkCreator";
 import { ManualAppEntry } from "./components/manualAppEntry/ManualAppEntry";
 import { listen } from "@tauri-apps/api/event";
 import { useAppNavigation } from "./hooks/useAppNavigation";
-import { useClipboardHistory } from "./hooks/useClipboard";
+import { ClipboardItem, useClipboardHistory } from "./hooks/useClipboard";
 import { useCalculator } from "./hooks/useCalculator";
 import { useQuickLinks } from "./hooks/useQuickLinks";
 import { useCategorizedSuggestions } from "./hooks/useCategorizedSuggestions";
 import { QuickLinkModal } from "./components/quickLink/QuickLinkModalProps";
+import { useSnippets } from "./hooks/useSnippets";
+import { SnippetCreator } from "./components/snippets/snippetCreator";
+import { SnippetInputModal } from "./components/snippets/snippetInputModal";
 import { CalculatorFooter } from "./components/calculator/CalculatorFooterProps";
 
 function App() {
@@ -30,18 +34,38 @@ function App() {
   const {
     clipboardHistory,
     copyToClipboard,
+    copyAsPlainText,
+    pasteHistoryItem,
     clearHistory,
-    copyImageAndHide,
     deleteHistoryItem,
     refreshClipboardHistory,
-    getFilteredHistory,
+    searchResults,
+    searchHistory,
+    clearSearch,
+    recoveryNotice,
+    dismissRecoveryNotice,
+    keyError,
+    dismissKeyError,
+    resetClipboardKey,
+    pasteStack,
+    startPasteStack,
+    stopPasteStack,
   } = useClipboardHistory();
 
   const filteredClipboardHistory =
-    mode === "clipboard" && query
-      ? getFilteredHistory(query)
+    mode === "clipboard" && query && searchResults
+      ? searchResults.items
       : clipboardHistory;
 
+  // Clipboard search runs in the backend, again whenever the history changes
+  useEffect(() => {
+    if (mode === "clipboard" && query) {
+      searchHistory(query);
+    } else {
+      clearSearch();
+    }
+  }, [mode, query, clipboardHistory]);
+
   const {
     calculatorResult,
     showCalculatorCopied,
@@ -56,6 +80,9 @@ function App() {
     executeQuickLinkWithQuery,
   } = useQuickLinks(setQuery);
 
+  const { snippetInputData, setSnippetInputData, pasteSnippetWithInputs } =
+    useSnippets(setQuery);
+
   // Fetch recent apps function
   const fetchRecentApps = async (force = false) => {
     try {
@@ -81,7 +108,8 @@ function App() {
   // Process suggestions
   const processedSuggestions = useCategorizedSuggestions.processSuggestions(
     query,
-    setQuickLinkQueryData
+    setQuickLinkQueryData,
+    setSnippetInputData
   );
 
   // Initial setup
@@ -294,6 +322,12 @@ function App() {
           return;
         }
 
+        if (selectedItem.id === "create_snippet") {
+          setMode("create_snippet");
+          resizeWindowForMode("create_snippet");
+          return;
+        }
+
         if (selectedItem.id === "add_manual_app") {
           setMode("add_manual_app");
           resizeWindowForMode("add_manual_app");
@@ -313,16 +347,19 @@ function App() {
       }
     } else if (mode === "clipboard" && filteredClipboardHistory.length > 0) {
       const selectedItem = filteredClipboardHistory[selectedIndex];
-      if (selectedItem.type === "text") {
-        handleCopyFromHistory(selectedItem.text);
-      } else if (selectedItem.type === "image") {
-        // Directly handle image copy for Enter press
-        await copyImageAndHide(selectedItem.imageData.filePath);
-      }
+      await pasteHistoryItem(selectedItem.id);
     }
   };
 
   const handleCopyFromHistory = async (text: string) => {
+    const historyItem = clipboardHistory.find(
+      (item: ClipboardItem) => item.type !== "image" && item.text === text
+    );
+    if (historyItem) {
+      await pasteHistoryItem(historyItem.id);
+      return;
+    }
+
     const success = await copyToClipboard(text);
     if (success) {
       invoke("hide_window");
@@ -344,6 +381,14 @@ function App() {
           return;
         }
 
+        // Special handling for create snippet
+        if (suggestion.id === "create_snippet") {
+          setMode("create_snippet");
+          resizeWindowForMode("create_snippet");
+          isExecuting = false;
+          return;
+        }
+
         // Special handling for add manual app
         if (suggestion.id === "add_manual_app") {
           setMode("add_manual_app");
@@ -411,19 +456,21 @@ function App() {
 
   return (
     <div className="flex flex-col w-full h-full rounded-xl overflow-hidden border border-gray-700">
-      {mode !== "create_quick_link" && mode !== "add_manual_app" && (
-        <CommandInput
-          query={query}
-          onQueryChange={handleQueryChange}
-          onSubmit={handleSubmit}
-          onArrowUp={handleArrowUp}
-          onArrowDown={handleArrowDown}
-          onEscape={handleEscape}
-          resetTrigger={resetTrigger}
-          showBackButton={mode === "clipboard"}
-          onBackClick={handleBackToApps}
-        />
-      )}
+      {mode !== "create_quick_link" &&
+        mode !== "create_snippet" &&
+        mode !== "add_manual_app" && (
+          <CommandInput
+            query={query}
+            onQueryChange={handleQueryChange}
+            onSubmit={handleSubmit}
+            onArrowUp={handleArrowUp}
+            onArrowDown={handleArrowDown}
+            onEscape={handleEscape}
+            resetTrigger={resetTrigger}
+            showBackButton={mode === "clipboard"}
+            onBackClick={handleBackToApps}
+          />
+        )}
       <div className="flex-grow overflow-hidden">
         {mode === "apps" ? (
           <SuggestionList
@@ -440,20 +487,79 @@ function App() {
             onResultAvailable={handleCalculatorResult}
           />
         ) : mode === "clipboard" ? (
-          <ClipboardHistory
-            history={filteredClipboardHistory}
-            onCopy={handleCopyFromHistory}
-            onDelete={deleteHistoryItem}
-            onClear={handleClearHistory}
-            selectedIndex={selectedIndex}
-            setSelectedIndex={setSelectedIndex}
-            onPinSuccess={handlePinSuccess}
-          />
+          <>
+            {recoveryNotice?.key_unavailable && (
+              <div className="px-4 py-2 text-xs text-red-300 bg-red-900/30">
+                Clipboard history can't be unlocked: {recoveryNotice.reason}.
+                New copies are not saved until the key is back.{" "}
+                <button
+                  className="underline"
+                  onClick={() => {
+                    if (
+                      window.confirm(
+                        "Move the locked history aside and start fresh with a new key?"
+                      )
+                    ) {
+                      resetClipboardKey();
+                    }
+                  }}
+                >
+                  Start fresh
+                </button>{" "}
+                <button className="underline" onClick={dismissRecoveryNotice}>
+                  Dismiss
+                </button>
+              </div>
+            )}
+            {recoveryNotice && !recoveryNotice.key_unavailable && (
+              <div
+                className="px-4 py-2 text-xs text-yellow-300 bg-yellow-900/30 cursor-pointer"
+                onClick={dismissRecoveryNotice}
+              >
+                {recoveryNotice.restored_from
+                  ? `Clipboard history couldn't be read and was restored from a backup (${recoveryNotice.restored_items} items).`
+                  : "Clipboard history couldn't be read and was started fresh."}{" "}
+                The old file was kept at {recoveryNotice.moved_to}.
+              </div>
+            )}
+            {keyError && !recoveryNotice?.key_unavailable && (
+              <div
+                className="px-4 py-2 text-xs text-red-300 bg-red-900/30 cursor-pointer"
+                onClick={dismissKeyError}
+              >
+                Clipboard history is locked: {keyError}
+              </div>
+            )}
+            {pasteStack?.active && (
+              <PasteStackBanner
+                status={pasteStack}
+                onChangeOrder={startPasteStack}
+                onStop={stopPasteStack}
+              />
+            )}
+            <ClipboardHistory
+              history={filteredClipboardHistory}
+              highlights={searchResults?.highlights}
+              onCopy={handleCopyFromHistory}
+              onCopyPlainText={copyAsPlainText}
+              onDelete={deleteHistoryItem}
+              onClear={handleClearHistory}
+              selectedIndex={selectedIndex}
+              setSelectedIndex={setSelectedIndex}
+              onPinSuccess={handlePinSuccess}
+              onItemReplaced={refreshClipboardHistory}
+            />
+          </>
         ) : mode === "create_quick_link" ? (
           <QuickLinkCreator
             onClose={handleBackToApps}
             onSave={handleQuickLinkSave}
           />
+        ) : mode === "create_snippet" ? (
+          <SnippetCreator
+            onClose={handleBackToApps}
+            onSave={handleQuickLinkSave}
+          />
         ) : mode === "add_manual_app" ? (
           <ManualAppEntry
             onClose={handleBackToApps}
@@ -471,6 +577,16 @@ function App() {
         />
       )}
 
+      {/* Snippet Input Modal */}
+      {snippetInputData && (
+        <SnippetInputModal
+          snippetName={snippetInputData.name}
+          inputs={snippetInputData.inputs}
+          onClose={() => setSnippetInputData(null)}
+          onPaste={pasteSnippetWithInputs}
+        />
+      )}
+
       {/* Calculator Footer */}
       {showCalculatorFooter && (
         <CalculatorFooter showCopied={showCalculatorCopied} />
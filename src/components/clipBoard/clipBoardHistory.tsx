@@ -1,26 +1,39 @@
 import React, { useState, useEffect, useRef } from "react";
-import { ClipboardItem, useClipboardHistory } from "../../hooks/useClipboard";
+import {
+  ClipboardHighlight,
+  ClipboardItem,
+  useClipboardHistory,
+} from "../../hooks/useClipboard";
 import { invoke } from "@tauri-apps/api/tauri";
 import { ClipboardImage } from "./clipBoardimg";
+import { TextTransforms } from "./textTransforms";
+import { ContentActions } from "./contentActions";
 
 interface ClipboardHistoryProps {
   history: ClipboardItem[];
+  // Search matches per item id
+  highlights?: Record<number, ClipboardHighlight[]>;
   onCopy: (text: string) => void;
+  onCopyPlainText: (id: number) => void;
   onDelete: (id: number) => void;
   onClear: () => void;
   selectedIndex: number;
   setSelectedIndex: React.Dispatch<React.SetStateAction<number>>;
   onPinSuccess: () => void;
+  onItemReplaced: () => void;
 }
 
 export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
   history,
+  highlights,
   onCopy,
+  onCopyPlainText,
   onDelete,
   onClear,
   selectedIndex,
   setSelectedIndex,
   onPinSuccess,
+  onItemReplaced,
 }) => {
   const pinnedItems = history.filter((item) => item.pinned);
   const [showActionMenu, setShowActionMenu] = useState<boolean>(false);
@@ -144,6 +157,20 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
         }
       }
 
+      // CTRL+SHIFT+V to paste without formatting
+      if (
+        e.ctrlKey &&
+        e.shiftKey &&
+        e.key.toLowerCase() === "v" &&
+        selectedIndex >= 0
+      ) {
+        e.preventDefault();
+        const selectedItem = history[selectedIndex];
+        if (selectedItem && selectedItem.type !== "image") {
+          onCopyPlainText(selectedItem.id);
+        }
+      }
+
       // ALT+SHIFT+D to delete all
       if (e.altKey && e.shiftKey && e.key.toLowerCase() === "d") {
         e.preventDefault();
@@ -158,7 +185,7 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
           const pinnedIndex = numKey - 1;
           if (pinnedIndex < pinnedItems.length) {
             const pinnedItem = pinnedItems[pinnedIndex];
-            if (pinnedItem.type === "text" && pinnedItem.text) {
+            if (pinnedItem.type !== "image" && pinnedItem.text) {
               onCopy(pinnedItem.text);
             } else if (
               pinnedItem.type === "image" &&
@@ -180,6 +207,7 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
     selectedIndex,
     history,
     onCopy,
+    onCopyPlainText,
     onDelete,
     onClear,
     canAddPin,
@@ -209,7 +237,7 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
 
     switch (index) {
       case 0: // Copy
-        if (selectedItem.type === "text" && selectedItem.text) {
+        if (selectedItem.type !== "image" && selectedItem.text) {
           onCopy(selectedItem.text);
         } else if (
           selectedItem.type === "image" &&
@@ -244,7 +272,7 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
       e.preventDefault();
       if (selectedIndex >= 0 && selectedIndex < history.length) {
         const selectedItem = history[selectedIndex];
-        if (selectedItem.type === "text" && selectedItem.text) {
+        if (selectedItem.type !== "image" && selectedItem.text) {
           onCopy(selectedItem.text);
           // Close window after copying text
           invoke("close_main_window").catch((error) => {
@@ -278,20 +306,59 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
       ? history[selectedIndex]
       : null;
 
+  // Mark the search matches inside the first `maxLength` characters of a text
+  const renderHighlightedText = (
+    text: string,
+    ranges: ClipboardHighlight[] | undefined,
+    maxLength: number
+  ) => {
+    const visible = text.substring(0, maxLength);
+    const suffix = text.length > maxLength ? "..." : "";
+    if (!ranges || ranges.length === 0) return visible + suffix;
+
+    const parts: React.ReactNode[] = [];
+    let position = 0;
+    for (const { start, end } of ranges) {
+      if (start >= visible.length) break;
+      parts.push(visible.substring(position, start));
+      parts.push(
+        <mark key={start} className="bg-yellow-600 text-white rounded-sm">
+          {visible.substring(start, Math.min(end, visible.length))}
+        </mark>
+      );
+      position = Math.min(end, visible.length);
+    }
+    parts.push(visible.substring(position) + suffix);
+    return parts;
+  };
+
   // Function to render item content preview in history list
   const renderItemPreview = (item: ClipboardItem) => {
     if (item.type === "text" && item.text) {
       return (
         <div className="mt-1 overflow-hidden overflow-ellipsis whitespace-nowrap">
-          {item.text.length > 100
-            ? `${item.text.substring(0, 100)}...`
-            : item.text}
+          {renderHighlightedText(item.text, highlights?.[item.id], 100)}
+        </div>
+      );
+    } else if (item.type === "files" && item.text) {
+      const paths = item.text.split("\n");
+      return (
+        <div className="mt-1 overflow-hidden overflow-ellipsis whitespace-nowrap">
+          {paths.length === 1
+            ? paths[0]
+            : `${paths.length} files: ${paths[0]}, ...`}
         </div>
       );
     } else if (item.type === "image" && item.imageData) {
       return (
-        <div className="mt-1 text-xs text-gray-400">
-          [Image {item.imageData.width}x{item.imageData.height}]
+        <div className="mt-1 flex items-center text-xs text-gray-400">
+          <ClipboardImage
+            filePath={item.imageData.thumbnailPath || item.imageData.filePath}
+            compact
+          />
+          <span className="ml-2">
+            [Image {item.imageData.width}x{item.imageData.height}]
+          </span>
         </div>
       );
     }
@@ -390,9 +457,25 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
                               📌
                             </span>
                           )}
+                          {item.sensitive && (
+                            <span
+                              className="text-xs text-red-400 mr-2"
+                              title="Sensitive content"
+                            >
+                              🔒
+                            </span>
+                          )}
                           {item.type === "image" && (
                             <span className="text-xs text-blue-400">Image</span>
                           )}
+                          {item.type === "files" && (
+                            <span className="text-xs text-blue-400">Files</span>
+                          )}
+                          {item.formats?.html && (
+                            <span className="text-xs text-blue-400 ml-2">
+                              Rich
+                            </span>
+                          )}
                         </div>
                       </div>
                       {renderItemPreview(item)}
@@ -410,7 +493,7 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
               <div className="border-gray-700 mb-2">
                 <h3 className="font-medium mb-2">Content</h3>
                 <div className="bg-gray-900 p-3 rounded overflow-y-auto max-h-52">
-                  {selectedItem.type === "text" && selectedItem.text ? (
+                  {selectedItem.type !== "image" && selectedItem.text ? (
                     <pre className="text-sm whitespace-pre-wrap break-words">
                       {selectedItem.text}
                     </pre>
@@ -425,12 +508,39 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
                 </div>
               </div>
 
+              {selectedItem.type === "text" && selectedItem.kind && (
+                <ContentActions item={selectedItem} />
+              )}
+
+              {selectedItem.type === "text" && selectedItem.text && (
+                <TextTransforms
+                  item={selectedItem}
+                  onReplaced={onItemReplaced}
+                />
+              )}
+
               <div className="font-medium mb-2">Information</div>
               <div className="text-sm mb-4">
                 <div className="flex justify-between mb-1">
                   <span className="text-gray-400">Type</span>
                   <span>{selectedItem.type}</span>
                 </div>
+                {selectedItem.source && (
+                  <div className="flex justify-between mb-1">
+                    <span className="text-gray-400">Copied from</span>
+                    <span className="truncate ml-4" title={selectedItem.source.path}>
+                      {selectedItem.source.name}
+                    </span>
+                  </div>
+                )}
+                {selectedItem.formats?.source_url && (
+                  <div className="flex justify-between mb-1">
+                    <span className="text-gray-400">Source</span>
+                    <span className="truncate ml-4">
+                      {selectedItem.formats.source_url}
+                    </span>
+                  </div>
+                )}
                 <div className="flex justify-between mb-1">
                   <span className="text-gray-400">Times copied</span>
                   <span>{selectedItem.copy_count || 1}</span>
@@ -451,6 +561,18 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
                   <span className="text-gray-400">Pinned</span>
                   <span>{selectedItem.pinned ? "Yes" : "No"}</span>
                 </div>
+                {selectedItem.sensitive && (
+                  <div className="flex justify-between mt-1">
+                    <span className="text-gray-400">Sensitive</span>
+                    <span className="text-red-400">
+                      {selectedItem.sensitive.replace(/_/g, " ")}
+                      {selectedItem.expires_at &&
+                        `, removed at ${formatTimestamp(
+                          selectedItem.expires_at
+                        )}`}
+                    </span>
+                  </div>
+                )}
               </div>
             </>
           ) : (
@@ -469,6 +591,10 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
               <span className="bg-gray-800 px-2 py-1 rounded">ENT</span>
               <span>Copy</span>
             </div>
+            <div className="flex items-center space-x-2">
+              <span className="bg-gray-800 px-2 py-1 rounded">CTRL+SHIFT+V</span>
+              <span>Plain Text</span>
+            </div>
             <div className="flex items-center space-x-2">
               <span className="bg-gray-800 px-2 py-1 rounded">SHIFT+P</span>
               <span>Pin/Unpin</span>
@@ -530,7 +656,7 @@ export const ClipboardHistory: React.FC<ClipboardHistoryProps> = ({
               }`}
               onClick={() => {
                 const selectedItem = history[selectedIndex];
-                if (selectedItem.type === "text" && selectedItem.text) {
+                if (selectedItem.type !== "image" && selectedItem.text) {
                   onCopy(selectedItem.text);
                 } else if (
                   selectedItem.type === "image" &&
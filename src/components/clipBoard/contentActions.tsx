@@ -0,0 +1,132 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { useEffect, useState } from "react";
+import { ClipboardItem, ContentKind } from "../../hooks/useClipboard";
+
+const KIND_LABELS: Record<ContentKind["type"], string> = {
+  url: "Link",
+  email: "Email address",
+  file_path: "File path",
+  color: "Color",
+  json: "JSON",
+  code: "Code",
+  phone: "Phone number",
+  number: "Number",
+};
+
+const COLOR_FORMATS: { value: string; label: string }[] = [
+  { value: "color_hex", label: "Hex" },
+  { value: "color_rgb", label: "RGB" },
+  { value: "color_hsl", label: "HSL" },
+];
+
+interface ContentActionsProps {
+  item: ClipboardItem;
+}
+
+// Shortcuts for what the text item was detected to be
+export const ContentActions = ({ item }: ContentActionsProps) => {
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    setError(null);
+  }, [item.id]);
+
+  const kind = item.kind;
+  if (!kind) {
+    return null;
+  }
+
+  const openItem = async (reveal = false) => {
+    try {
+      await invoke("open_clipboard_item", { itemId: item.id, reveal });
+      setError(null);
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const copyTransformed = async (transform: string) => {
+    try {
+      await invoke("transform_clipboard_text", {
+        transform,
+        target: "copy",
+        itemId: item.id,
+      });
+      setError(null);
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  const buttonClass = "px-2 py-1 rounded bg-gray-700 hover:bg-gray-600 mr-2";
+
+  return (
+    <div className="mb-4">
+      <div className="font-medium mb-2">
+        {KIND_LABELS[kind.type]}
+        {kind.type === "code" && kind.language && (
+          <span className="ml-2 text-xs text-blue-400">{kind.language}</span>
+        )}
+      </div>
+      <div className="flex items-center text-sm">
+        {(kind.type === "url" ||
+          kind.type === "email" ||
+          kind.type === "phone") && (
+          <button className={buttonClass} onClick={() => openItem()}>
+            {kind.type === "url"
+              ? "Open in browser"
+              : kind.type === "email"
+                ? "Compose email"
+                : "Call"}
+          </button>
+        )}
+        {kind.type === "file_path" && (
+          <>
+            <button className={buttonClass} onClick={() => openItem()}>
+              Open
+            </button>
+            <button className={buttonClass} onClick={() => openItem(true)}>
+              Show in folder
+            </button>
+          </>
+        )}
+        {kind.type === "color" && (
+          <>
+            <span
+              className="inline-block w-6 h-6 rounded border border-gray-600 mr-2"
+              style={{ backgroundColor: item.text?.trim() }}
+            />
+            {COLOR_FORMATS.filter(
+              ({ value }) => value !== `color_${kind.format}`,
+            ).map(({ value, label }) => (
+              <button
+                key={value}
+                className={buttonClass}
+                onClick={() => copyTransformed(value)}
+              >
+                Copy as {label}
+              </button>
+            ))}
+          </>
+        )}
+        {kind.type === "json" && (
+          <>
+            <button
+              className={buttonClass}
+              onClick={() => copyTransformed("json_pretty")}
+            >
+              Copy pretty-printed
+            </button>
+            <button
+              className={buttonClass}
+              onClick={() => copyTransformed("json_minify")}
+            >
+              Copy minified
+            </button>
+          </>
+        )}
+      </div>
+      {error && <div className="text-xs text-red-400 mt-1">{error}</div>}
+    </div>
+  );
+};
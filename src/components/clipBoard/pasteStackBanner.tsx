@@ -0,0 +1,38 @@
+import { PasteStackOrder, PasteStackStatus } from "../../hooks/useClipboard";
+
+interface PasteStackBannerProps {
+  status: PasteStackStatus;
+  onChangeOrder: (order: PasteStackOrder) => void;
+  onStop: () => void;
+}
+
+// Shown while copies are being collected for sequential pasting
+export const PasteStackBanner = ({
+  status,
+  onChangeOrder,
+  onStop,
+}: PasteStackBannerProps) => {
+  const nextOrder = status.order === "fifo" ? "lifo" : "fifo";
+
+  return (
+    <div className="flex items-center px-4 py-2 text-xs text-blue-200 bg-blue-900/30">
+      <span className="flex-grow">
+        Paste stack: {status.remaining}{" "}
+        {status.remaining === 1 ? "item" : "items"} left. Press Alt+Shift+V to
+        paste the {status.order === "fifo" ? "oldest" : "newest"} one.
+      </span>
+      <button
+        className="px-2 py-1 rounded bg-gray-700 hover:bg-gray-600 mr-2"
+        onClick={() => onChangeOrder(nextOrder)}
+      >
+        {status.order === "fifo" ? "First in, first out" : "Last in, first out"}
+      </button>
+      <button
+        className="px-2 py-1 rounded bg-gray-700 hover:bg-gray-600"
+        onClick={onStop}
+      >
+        Stop
+      </button>
+    </div>
+  );
+};
@@ -0,0 +1,102 @@
+import { invoke } from "@tauri-apps/api/tauri";
+import { useEffect, useState } from "react";
+import { ClipboardItem } from "../../hooks/useClipboard";
+
+const TRANSFORMS: { value: string; label: string }[] = [
+  { value: "uppercase", label: "UPPERCASE" },
+  { value: "lowercase", label: "lowercase" },
+  { value: "title_case", label: "Title Case" },
+  { value: "sentence_case", label: "Sentence case" },
+  { value: "camel_case", label: "camelCase" },
+  { value: "snake_case", label: "snake_case" },
+  { value: "kebab_case", label: "kebab-case" },
+  { value: "trim", label: "Trim" },
+  { value: "dedent", label: "Dedent" },
+  { value: "sort_lines", label: "Sort lines" },
+  { value: "dedupe_lines", label: "Remove duplicate lines" },
+  { value: "json_pretty", label: "JSON pretty-print" },
+  { value: "json_minify", label: "JSON minify" },
+  { value: "xml_pretty", label: "XML pretty-print" },
+  { value: "xml_minify", label: "XML minify" },
+  { value: "base64_encode", label: "Base64 encode" },
+  { value: "base64_decode", label: "Base64 decode" },
+  { value: "url_encode", label: "URL encode" },
+  { value: "url_decode", label: "URL decode" },
+  { value: "sha256", label: "SHA-256" },
+  { value: "sha512", label: "SHA-512" },
+  { value: "escape_json", label: "Escape (JSON string)" },
+  { value: "unescape_json", label: "Unescape (JSON string)" },
+  { value: "escape_html", label: "Escape HTML" },
+  { value: "unescape_html", label: "Unescape HTML" },
+  { value: "color_hex", label: "Color to hex" },
+  { value: "color_rgb", label: "Color to rgb()" },
+  { value: "color_hsl", label: "Color to hsl()" },
+];
+
+interface TransformResult {
+  text: string;
+  item: ClipboardItem | null;
+}
+
+interface TextTransformsProps {
+  item: ClipboardItem;
+  onReplaced: () => void;
+}
+
+// Reformat a text history item and copy the result or replace the item with it
+export const TextTransforms = ({ item, onReplaced }: TextTransformsProps) => {
+  const [transform, setTransform] = useState<string>(TRANSFORMS[0].value);
+  const [error, setError] = useState<string | null>(null);
+
+  useEffect(() => {
+    setError(null);
+  }, [item.id, transform]);
+
+  const runTransform = async (target: "copy" | "replace") => {
+    try {
+      await invoke<TransformResult>("transform_clipboard_text", {
+        transform,
+        target,
+        itemId: item.id,
+      });
+      setError(null);
+      if (target === "replace") {
+        onReplaced();
+      }
+    } catch (e) {
+      setError(String(e));
+    }
+  };
+
+  return (
+    <div className="mb-4">
+      <div className="font-medium mb-2">Transform</div>
+      <div className="flex items-center text-sm">
+        <select
+          className="flex-grow bg-gray-900 border border-gray-700 rounded px-2 py-1 mr-2"
+          value={transform}
+          onChange={(e) => setTransform(e.target.value)}
+        >
+          {TRANSFORMS.map(({ value, label }) => (
+            <option key={value} value={value}>
+              {label}
+            </option>
+          ))}
+        </select>
+        <button
+          className="px-2 py-1 rounded bg-gray-700 hover:bg-gray-600 mr-2"
+          onClick={() => runTransform("copy")}
+        >
+          Copy result
+        </button>
+        <button
+          className="px-2 py-1 rounded bg-gray-700 hover:bg-gray-600"
+          onClick={() => runTransform("replace")}
+        >
+          Replace
+        </button>
+      </div>
+      {error && <div className="text-xs text-red-400 mt-1">{error}</div>}
+    </div>
+  );
+};
@@ -1,3 +1,4 @@
+import { QuickLinkArgument } from "../../types";
 import { QuickLinkQueryExecutor } from "./quickLinkQueryExe";
 
 interface QuickLinkModalProps {
@@ -5,9 +6,10 @@ interface QuickLinkModalProps {
     id: string;
     name: string;
     command: string;
+    arguments: QuickLinkArgument[];
   };
   onClose: () => void;
-  onExecute: (finalCommand: string) => Promise<void>;
+  onExecute: (args: Record<string, string>) => Promise<void>;
 }
 
 export function QuickLinkModal({
@@ -20,6 +22,7 @@ export function QuickLinkModal({
       quickLinkId={quickLinkData.id}
       quickLinkName={quickLinkData.name}
       commandTemplate={quickLinkData.command}
+      templateArguments={quickLinkData.arguments}
       onClose={onClose}
       onExecute={onExecute}
     />
@@ -266,7 +266,9 @@ export function QuickLinkCreator({
             <div className="mt-2 text-sm text-gray-400">
               Include an argument by inserting {"{query}"} in the URL. The word
               "query" can be changed to anything and will be used as the
-              placeholder text.
+              placeholder text, {"{query:default}"} gives it a default. Also
+              available: {"{clipboard}"}, {"{selection}"},{" "}
+              {"{date:%Y-%m-%d}"} and {"{env:VAR}"}.
               <button
                 onClick={insertQueryPlaceholder}
                 className="ml-2 text-blue-400 hover:text-blue-300"
@@ -1,41 +1,50 @@
 import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/tauri";
+import { QuickLinkArgument } from "../../types";
 
 interface QuickLinkExecutorProps {
   quickLinkId: string;
   quickLinkName: string;
   commandTemplate: string;
+  templateArguments: QuickLinkArgument[];
   onClose: () => void;
-  onExecute: (finalCommand: string) => Promise<void>;
+  onExecute: (args: Record<string, string>) => Promise<void>;
 }
 
 export function QuickLinkQueryExecutor({
-  //   quickLinkId,
-  // quickLinkName,
+  quickLinkId,
+  quickLinkName,
   commandTemplate,
+  templateArguments,
   onClose,
   onExecute,
 }: QuickLinkExecutorProps) {
-  const [queryValue, setQueryValue] = useState("");
+  const [values, setValues] = useState<Record<string, string>>(() =>
+    Object.fromEntries(templateArguments.map((arg) => [arg.name, ""]))
+  );
+  const [preview, setPreview] = useState(commandTemplate);
   const [error, setError] = useState<string | null>(null);
 
-  // Extract the placeholder parts to show what will be replaced
-  const parts = commandTemplate.split("{query}");
-  const preQueryPart = parts[0];
-  const postQueryPart = parts.length > 1 ? parts[1] : "";
+  // The backend fills in the placeholders and escapes the values
+  useEffect(() => {
+    invoke<string>("preview_quick_link", { quickLinkId, args: values })
+      .then(setPreview)
+      .catch(() => setPreview(commandTemplate));
+  }, [quickLinkId, commandTemplate, values]);
 
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
 
-    if (!queryValue.trim() && commandTemplate.includes("{query}")) {
-      setError("Please enter a value for the query");
+    const missing = templateArguments.find(
+      (arg) => arg.default === null && !values[arg.name]?.trim()
+    );
+    if (missing) {
+      setError(`Please enter a value for ${missing.name}`);
       return;
     }
 
-    // Replace {query} with the actual value
-    const finalCommand = commandTemplate.replace("{query}", queryValue);
-
     try {
-      await onExecute(finalCommand);
+      await onExecute(values);
       onClose();
     } catch (err) {
       setError(`Failed to execute command: ${err}`);
@@ -54,8 +63,8 @@ export function QuickLinkQueryExecutor({
   };
 
   useEffect(() => {
-    // Focus on the query input when component mounts
-    const queryInput = document.getElementById("quick-link-query-input");
+    // Focus the first argument when the modal opens
+    const queryInput = document.getElementById("quick-link-query-input-0");
     if (queryInput) {
       queryInput.focus();
     }
@@ -72,6 +81,8 @@ export function QuickLinkQueryExecutor({
           }
         }}
       >
+        <h3 className="text-white font-medium mb-4">{quickLinkName}</h3>
+
         {error && (
           <div className="bg-red-500 bg-opacity-20 border border-red-500 text-red-300 px-4 py-2 rounded mb-4">
             {error}
@@ -79,31 +90,41 @@ export function QuickLinkQueryExecutor({
         )}
 
         <form onSubmit={handleSubmit} className="space-y-4">
-          <div>
-            <label
-              htmlFor="quick-link-query-input"
-              className="block text-sm text-gray-400 mb-2"
-            >
-              Enter value for {"{query}"}
-            </label>
-            <input
-              id="quick-link-query-input"
-              type="text"
-              value={queryValue}
-              onChange={(e) => setQueryValue(e.target.value)}
-              className="w-full bg-gray-700 text-white px-3 py-2 rounded focus:outline-none focus:ring-2 focus:ring-blue-500"
-              placeholder="Parameter value"
-            />
-          </div>
+          {templateArguments.map((arg, index) => (
+            <div key={arg.name}>
+              <label
+                htmlFor={`quick-link-query-input-${index}`}
+                className="block text-sm text-gray-400 mb-2"
+              >
+                {arg.name}
+              </label>
+              <input
+                id={`quick-link-query-input-${index}`}
+                type="text"
+                value={values[arg.name] ?? ""}
+                onChange={(e) =>
+                  setValues((prev) => ({
+                    ...prev,
+                    [arg.name]: e.target.value,
+                  }))
+                }
+                className="w-full bg-gray-700 text-white px-3 py-2 rounded focus:outline-none focus:ring-2 focus:ring-blue-500"
+                placeholder={arg.default ?? "Parameter value"}
+              />
+            </div>
+          ))}
 
           <div className="bg-gray-900 p-3 rounded">
             <div className="text-sm text-gray-400 mb-1">Command Preview:</div>
-            <div className="font-mono text-white break-all">
-              {preQueryPart}
-              <span className="text-yellow-400">{queryValue || "{query}"}</span>
-              {postQueryPart}
-            </div>
+            <div className="font-mono text-white break-all">{preview}</div>
           </div>
+
+          <button
+            type="submit"
+            className="w-full px-4 py-2 rounded bg-blue-600 hover:bg-blue-500 text-white"
+          >
+            Run
+          </button>
         </form>
       </div>
     </div>
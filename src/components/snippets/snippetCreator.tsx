@@ -0,0 +1,181 @@
+import { useState, useEffect } from "react";
+import { invoke } from "@tauri-apps/api/tauri";
+
+interface SnippetCreatorProps {
+  onClose: () => void;
+  onSave?: () => void;
+}
+
+const PLACEHOLDERS = [
+  "{date}",
+  "{time}",
+  "{clipboard}",
+  "{uuid}",
+  "{cursor}",
+  "{input:Name}",
+];
+
+export function SnippetCreator({ onClose, onSave }: SnippetCreatorProps) {
+  const [formData, setFormData] = useState({
+    name: "",
+    keyword: "",
+    text: "",
+  });
+  const [error, setError] = useState<string | null>(null);
+
+  const isFormValid = formData.name.trim() !== "" && formData.text !== "";
+
+  const handleChange = (
+    e: React.ChangeEvent<HTMLInputElement | HTMLTextAreaElement>
+  ) => {
+    const { name, value } = e.target;
+    setFormData((prev) => ({ ...prev, [name]: value }));
+  };
+
+  const insertPlaceholder = (placeholder: string) => {
+    setFormData((prev) => ({ ...prev, text: prev.text + placeholder }));
+  };
+
+  const saveSnippet = async () => {
+    try {
+      await invoke("save_snippet", {
+        snippet: {
+          name: formData.name,
+          text: formData.text,
+          keyword: formData.keyword || null,
+        },
+      });
+      if (onSave) {
+        onSave();
+      } else {
+        onClose();
+      }
+    } catch (err) {
+      setError(`Failed to save snippet: ${err}`);
+    }
+  };
+
+  useEffect(() => {
+    // Focus on the name input when component mounts
+    const nameInput = document.getElementById("snippet-name");
+    if (nameInput) {
+      nameInput.focus();
+    }
+  }, []);
+
+  return (
+    <div className="flex flex-col w-full h-full bg-gray-900 rounded-xl">
+      {/* Header - Fixed at top */}
+      <div className="flex items-center p-4 border-b border-gray-700">
+        <button onClick={onClose} className="p-2 mr-3 bg-gray-700 rounded-full">
+          <svg
+            xmlns="http://www.w3.org/2000/svg"
+            width="20"
+            height="20"
+            viewBox="0 0 24 24"
+            fill="none"
+            stroke="currentColor"
+            strokeWidth="2"
+            strokeLinecap="round"
+            strokeLinejoin="round"
+          >
+            <path d="M19 12H5M12 19l-7-7 7-7" />
+          </svg>
+        </button>
+        <h2 className="text-xl font-medium text-white">Create Snippet</h2>
+      </div>
+
+      {/* Content - Scrollable */}
+      <div className="flex-1 overflow-y-auto p-4">
+        {error && (
+          <div className="bg-red-500 bg-opacity-20 border border-red-500 text-red-300 px-4 py-2 rounded mb-4">
+            {error}
+          </div>
+        )}
+
+        <div className="space-y-4">
+          <div className="flex flex-col mb-4">
+            <label htmlFor="snippet-name" className="text-gray-400 mb-2">
+              Name
+            </label>
+            <input
+              id="snippet-name"
+              name="name"
+              type="text"
+              value={formData.name}
+              onChange={handleChange}
+              className="bg-gray-700 text-white px-4 py-3 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+              placeholder="Email signature"
+            />
+          </div>
+
+          <div className="flex flex-col mb-4">
+            <label htmlFor="snippet-keyword" className="text-gray-400 mb-2">
+              Keyword
+            </label>
+            <input
+              id="snippet-keyword"
+              name="keyword"
+              type="text"
+              value={formData.keyword}
+              onChange={handleChange}
+              className="bg-gray-700 text-white px-4 py-3 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500"
+              placeholder="!sig"
+            />
+            <div className="mt-2 text-sm text-gray-400">
+              Typing the keyword in the launcher puts this snippet first.
+            </div>
+          </div>
+
+          <div className="flex flex-col mb-2">
+            <label htmlFor="snippet-text" className="text-gray-400 mb-2">
+              Snippet
+            </label>
+            <textarea
+              id="snippet-text"
+              name="text"
+              rows={6}
+              value={formData.text}
+              onChange={handleChange}
+              className="bg-gray-700 text-white px-4 py-3 rounded-md font-mono text-sm focus:outline-none focus:ring-2 focus:ring-blue-500"
+              placeholder={"Best regards,\n{input:Name}"}
+            />
+            <div className="mt-2 text-sm text-gray-400">
+              Insert:
+              {PLACEHOLDERS.map((placeholder) => (
+                <button
+                  key={placeholder}
+                  onClick={() => insertPlaceholder(placeholder)}
+                  className="ml-2 text-blue-400 hover:text-blue-300"
+                >
+                  {placeholder}
+                </button>
+              ))}
+            </div>
+          </div>
+        </div>
+      </div>
+
+      {/* Footer - Fixed at bottom */}
+      <div className="flex justify-end p-4 border-t border-gray-700">
+        <button
+          onClick={onClose}
+          className="mr-2 px-4 py-2 text-white bg-gray-800 hover:bg-gray-700 rounded-md"
+        >
+          Cancel
+        </button>
+        <button
+          onClick={saveSnippet}
+          disabled={!isFormValid}
+          className={`px-4 py-2 ${
+            isFormValid
+              ? "bg-blue-600 hover:bg-blue-500"
+              : "bg-blue-600/50 cursor-not-allowed"
+          } text-white rounded-md flex items-center`}
+        >
+          Create Snippet
+        </button>
+      </div>
+    </div>
+  );
+}
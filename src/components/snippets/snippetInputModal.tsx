@@ -0,0 +1,100 @@
+import { useState, useEffect } from "react";
+
+interface SnippetInputModalProps {
+  snippetName: string;
+  inputs: string[];
+  onClose: () => void;
+  onPaste: (inputs: Record<string, string>) => Promise<void>;
+}
+
+// Ask for the {input:Name} values of a snippet before it is pasted
+export function SnippetInputModal({
+  snippetName,
+  inputs,
+  onClose,
+  onPaste,
+}: SnippetInputModalProps) {
+  const [values, setValues] = useState<Record<string, string>>(() =>
+    Object.fromEntries(inputs.map((name) => [name, ""]))
+  );
+  const [error, setError] = useState<string | null>(null);
+
+  const handleSubmit = async (e: React.FormEvent) => {
+    e.preventDefault();
+
+    try {
+      await onPaste(values);
+    } catch (err) {
+      setError(`Failed to paste snippet: ${err}`);
+    }
+  };
+
+  const handleClose = () => {
+    onClose();
+    // Focus the command input after closing
+    setTimeout(() => {
+      const commandInput = document.getElementById("command-input");
+      if (commandInput) {
+        (commandInput as HTMLInputElement).focus();
+      }
+    }, 0);
+  };
+
+  useEffect(() => {
+    // Focus the first field when the modal opens
+    const firstInput = document.getElementById("snippet-input-0");
+    if (firstInput) {
+      firstInput.focus();
+    }
+  }, []);
+
+  return (
+    <div className="fixed inset-0 bg-black bg-opacity-70 rounded-xl flex items-center justify-center z-50">
+      <div
+        className="bg-gray-800 rounded-lg w-full max-w-md p-6 shadow-xl"
+        onKeyDown={(e) => {
+          if (e.key === "Escape") {
+            e.preventDefault();
+            handleClose();
+          }
+        }}
+      >
+        <h3 className="text-white font-medium mb-4">{snippetName}</h3>
+
+        {error && (
+          <div className="bg-red-500 bg-opacity-20 border border-red-500 text-red-300 px-4 py-2 rounded mb-4">
+            {error}
+          </div>
+        )}
+
+        <form onSubmit={handleSubmit} className="space-y-4">
+          {inputs.map((name, index) => (
+            <div key={name}>
+              <label
+                htmlFor={`snippet-input-${index}`}
+                className="block text-sm text-gray-400 mb-2"
+              >
+                {name}
+              </label>
+              <input
+                id={`snippet-input-${index}`}
+                type="text"
+                value={values[name] ?? ""}
+                onChange={(e) =>
+                  setValues((prev) => ({ ...prev, [name]: e.target.value }))
+                }
+                className="w-full bg-gray-700 text-white px-3 py-2 rounded focus:outline-none focus:ring-2 focus:ring-blue-500"
+              />
+            </div>
+          ))}
+          <button
+            type="submit"
+            className="w-full px-4 py-2 rounded bg-blue-600 hover:bg-blue-500 text-white"
+          >
+            Paste
+          </button>
+        </form>
+      </div>
+    </div>
+  );
+}
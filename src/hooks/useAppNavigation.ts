@@ -1,12 +1,18 @@
 import { useState } from "react";
 import { invoke } from "@tauri-apps/api/tauri";
 
-type AppMode = "apps" | "clipboard" | "create_quick_link" | "add_manual_app";
+type AppMode =
+  | "apps"
+  | "clipboard"
+  | "create_quick_link"
+  | "create_snippet"
+  | "add_manual_app";
 
 const WINDOW_SIZES = {
   apps: { width: 750, height: 500 },
   clipboard: { width: 900, height: 700 },
   create_quick_link: { width: 750, height: 600 },
+  create_snippet: { width: 750, height: 600 },
   add_manual_app: { width: 750, height: 400 },
 };
 
@@ -24,7 +30,11 @@ export function useAppNavigation() {
   };
 
   const handleEscape = () => {
-    if (mode === "create_quick_link" || mode === "add_manual_app") {
+    if (
+      mode === "create_quick_link" ||
+      mode === "create_snippet" ||
+      mode === "add_manual_app"
+    ) {
       setMode("apps");
       resizeWindowForMode("apps");
       return true;
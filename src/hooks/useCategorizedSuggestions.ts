@@ -1,6 +1,11 @@
 import { useMemo } from "react";
 import { invoke } from "@tauri-apps/api/tauri";
-import { AppInfo, appToSuggestion, Suggestion } from "../types";
+import {
+  AppInfo,
+  appToSuggestion,
+  QuickLinkArgument,
+  Suggestion,
+} from "../types";
 import { useSuggestions } from "./useSuggestion";
 
 export const useCategorizedSuggestions = {
@@ -35,7 +40,11 @@ export const useCategorizedSuggestions = {
     };
   },
 
-  processSuggestions: (query: string, setQuickLinkQueryData: Function) => {
+  processSuggestions: (
+    query: string,
+    setQuickLinkQueryData: Function,
+    setSnippetInputData: Function
+  ) => {
     // Get raw suggestions
     const rawSuggestions: Suggestion[] = useSuggestions(query);
 
@@ -49,12 +58,17 @@ export const useCategorizedSuggestions = {
             action: async () => {
               // Extract the command from the subtitle if available
               const command = suggestion.subtitle || "";
+              const args = await invoke<QuickLinkArgument[]>(
+                "get_quick_link_arguments",
+                { quickLinkId: suggestion.id }
+              );
 
-              if (command.includes("{query}")) {
+              if (args.length > 0) {
                 setQuickLinkQueryData({
                   id: suggestion.id,
                   name: suggestion.title,
                   command: command,
+                  arguments: args,
                 });
                 return true; // Return true to indicate we're showing a modal
               } else {
@@ -67,6 +81,24 @@ export const useCategorizedSuggestions = {
             },
           };
         }
+        // Snippets with {input:Name} placeholders ask for the values first
+        if (
+          suggestion.category === "Snippets" &&
+          suggestion.snippetInputs &&
+          suggestion.snippetInputs.length > 0
+        ) {
+          return {
+            ...suggestion,
+            action: async () => {
+              setSnippetInputData({
+                id: suggestion.id,
+                name: suggestion.title,
+                inputs: suggestion.snippetInputs,
+              });
+              return true; // Return true to indicate we're showing a modal
+            },
+          };
+        }
         return suggestion;
       });
 
@@ -87,6 +119,6 @@ export const useCategorizedSuggestions = {
         flat: processed,
         categorized: categorized,
       };
-    }, [rawSuggestions, setQuickLinkQueryData]);
+    }, [rawSuggestions, setQuickLinkQueryData, setSnippetInputData]);
   },
 };
\ No newline at end of file
@@ -1,218 +1,202 @@
-import { useState, useEffect, useRef } from "react";
+import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/tauri";
+import { listen } from "@tauri-apps/api/event";
+
+// Rich formats captured alongside the text, restored when the item is copied
+export interface ClipboardFormats {
+  html?: string;
+  rtf?: string;
+  files?: string[];
+  source_url?: string;
+}
 
 export interface ClipboardItem {
   id: number;
-  type: "text" | "image";
+  // "files" items keep the copied paths newline separated in `text`
+  type: "text" | "image" | "files";
   text?: string;
   imageData?: {
     width: number;
     height: number;
     hash: string;
+    // Full size original
     filePath: string;
+    // Small version for the list, missing on older items
+    thumbnailPath?: string;
   };
   pinned: boolean;
   timestamp: number;
   last_copied: number;
   copy_count: number;
+  formats?: ClipboardFormats;
+  // Set when the content looked like a password, token or card number
+  sensitive?:
+    | "password_manager"
+    | "private_key"
+    | "api_key"
+    | "jwt"
+    | "card_number"
+    | "high_entropy";
+  // Sensitive items are removed by the backend after this time
+  expires_at?: number;
+  // App in the foreground when the content was copied
+  source?: {
+    app_id?: string;
+    name: string;
+    path: string;
+  };
+  // What the text looks like, decides the actions offered for it
+  kind?: ContentKind;
+}
+
+export type ContentKind =
+  | { type: "url" }
+  | { type: "email" }
+  | { type: "file_path" }
+  | { type: "color"; format: "hex" | "rgb" | "hsl" }
+  | { type: "json" }
+  | { type: "code"; language: string | null }
+  | { type: "phone" }
+  | { type: "number" };
+
+// A page of history returned by list_clipboard_items
+export interface ClipboardPage {
+  items: ClipboardItem[];
+  total: number;
+  offset: number;
+  limit: number;
+}
+
+// Sent when an unreadable history file had to be moved aside, or couldn't be
+// read at all because the key is missing
+export interface ClipboardRecoveryNotice {
+  reason: string;
+  key_unavailable: boolean;
+  moved_to: string | null;
+  restored_from: string | null;
+  restored_items: number;
+}
+
+export type PasteStackOrder = "fifo" | "lifo";
+
+// Sent whenever the paste stack is started, stopped, pushed to or pasted from
+export interface PasteStackStatus {
+  active: boolean;
+  order: PasteStackOrder;
+  remaining: number;
+  next: number | null;
+}
+
+// Matched part of an item's text, as string offsets
+export interface ClipboardHighlight {
+  start: number;
+  end: number;
+}
+
+export interface ClipboardSearchRequest {
+  query: string;
+  types?: string[];
+  from?: number;
+  to?: number;
+  pinned?: boolean;
+  source_app?: string;
+  sort?: "relevance" | "recent" | "copy_count";
+  offset?: number;
+  limit?: number;
 }
+
+interface ClipboardSearchResults {
+  hits: {
+    item: ClipboardItem;
+    score: number;
+    highlights: ClipboardHighlight[];
+  }[];
+  total: number;
+  offset: number;
+  limit: number;
+}
+
 export const useClipboardHistory = () => {
   const [clipboardHistory, setClipboardHistory] = useState<any>([]);
-  const [lastClipboardContent, setLastClipboardContent] = useState("");
-  const clipboardIntervalRef = useRef<number | null>(null);
-  const isInClearing = useRef(false);
-  const isDeleting = useRef(false);
-
-  // Dynamic interval management
-  const noChangeCountRef = useRef(0);
-  const baseInterval = 1000; // Start with 1 second
-  const maxInterval = 5000; // Maximum interval of 5 seconds
-  const currentIntervalRef = useRef(baseInterval);
-
-  // Save history to file system
-  const saveHistoryToStorage = async (history: ClipboardItem[]) => {
-    try {
-      const historyData = JSON.stringify({ items: history });
-      await invoke("save_clipboard_history", { historyData });
-    } catch (error) {
-      console.error("Failed to save history to storage:", error);
-    }
-  };
-
-  // Load history from file system
+  const [searchResults, setSearchResults] = useState<{
+    items: ClipboardItem[];
+    highlights: Record<number, ClipboardHighlight[]>;
+  } | null>(null);
+  const [recoveryNotice, setRecoveryNotice] =
+    useState<ClipboardRecoveryNotice | null>(null);
+  // Why the history key can't be loaded, the history stays unreadable until fixed
+  const [keyError, setKeyError] = useState<string | null>(null);
+  const [pasteStack, setPasteStack] = useState<PasteStackStatus | null>(null);
+
+  // Load history from the backend store, page by page. The backend decides
+  // how much history is kept.
   const loadHistoryFromStorage = async () => {
     try {
-      const historyData = await invoke<string>("load_clipboard_history");
-      const parsed = JSON.parse(historyData);
-      const migratedItems = (parsed.items || []).map((item: any) => ({
-        ...item,
-        pinned: item.pinned ?? false,
-        copy_count: item.copy_count ?? 1,
-        last_copied: item.last_copied ?? item.timestamp,
-      }));
-      setClipboardHistory(migratedItems);
+      const items: ClipboardItem[] = [];
+      let page: ClipboardPage;
+      do {
+        page = await invoke<ClipboardPage>("list_clipboard_items", {
+          offset: items.length,
+          limit: 100,
+        });
+        items.push(...page.items);
+      } while (page.items.length > 0 && items.length < page.total);
+      setClipboardHistory(items);
     } catch (error) {
       console.error("Failed to load history from storage:", error);
     }
   };
 
-  const getFilteredHistory = (query: string) => {
-    if (!query) return clipboardHistory;
-
-    const loweredQuery = query.toLowerCase();
-
-    return clipboardHistory.filter(
-      (item: ClipboardItem) =>
-        item.type === "text" && item.text?.toLowerCase().includes(loweredQuery)
-    );
-  };
-
-  // Check clipboard content
-  const checkClipboard = async () => {
-    // Clear any existing scheduled checks
-    if (clipboardIntervalRef.current) {
-      clearTimeout(clipboardIntervalRef.current);
-      clipboardIntervalRef.current = null;
-    }
-
-    if (isInClearing.current || isDeleting.current) {
-      // Re-schedule next check even when skipping the current one
-      clipboardIntervalRef.current = window.setTimeout(
-        checkClipboard,
-        currentIntervalRef.current
-      );
-      return;
+  // Search the whole history in the backend. Besides text the query takes
+  // "from:<app>", "type:<text|image|files>", "is:pinned" and
+  // "sort:<recent|count>" tokens.
+  const searchHistory = async (query: string) => {
+    const request: ClipboardSearchRequest = { query: "", types: [] };
+    const words: string[] = [];
+
+    for (const word of query.split(/\s+/).filter(Boolean)) {
+      const [key, ...rest] = word.split(":");
+      const value = rest.join(":").toLowerCase();
+      if (key === "from" && value) {
+        request.source_app = value;
+      } else if (key === "type" && value) {
+        request.types!.push(value);
+      } else if (key === "is" && value === "pinned") {
+        request.pinned = true;
+      } else if (key === "sort" && (value === "recent" || value === "count")) {
+        request.sort = value === "recent" ? "recent" : "copy_count";
+      } else {
+        words.push(word);
+      }
     }
+    request.query = words.join(" ");
 
     try {
-      // First try to get text from clipboard
-      const currentTextContent = await invoke<string>("get_clipboard").catch(
-        () => null
+      const results = await invoke<ClipboardSearchResults>(
+        "search_clipboard_items",
+        { request: { ...request, offset: 0, limit: 200 } }
       );
-
-      // Then try to get image from clipboard
-      const currentImageContent = await invoke<{
-        width: number;
-        height: number;
-        hash: string;
-        file_path: string;
-      } | null>("get_clipboard_image").catch(() => null);
-
-      // If both are null/undefined, clipboard is empty
-      if (!currentTextContent && !currentImageContent) {
-        console.log("Empty clipboard detected, will check again later");
-      }
-      // Check if we have new text content
-      else if (
-        currentTextContent &&
-        currentTextContent !== lastClipboardContent
-      ) {
-        // Reset interval when clipboard changes
-        currentIntervalRef.current = baseInterval;
-        noChangeCountRef.current = 0;
-
-        setLastClipboardContent(currentTextContent);
-
-        setClipboardHistory((prev: any) => {
-          // Check if this text already exists in history
-          if (
-            prev.some(
-              (item: any) =>
-                item.type === "text" && item.text === currentTextContent
-            )
-          ) {
-            return prev;
-          }
-
-          const newItem: ClipboardItem = {
-            id: Date.now(),
-            type: "text",
-            text: currentTextContent,
-            timestamp: Date.now(),
-            last_copied: Date.now(),
-            pinned: false,
-            copy_count: 1,
-          };
-
-          const updatedHistory = [newItem, ...prev.slice(0, 99)];
-          saveHistoryToStorage(updatedHistory);
-          return updatedHistory;
-        });
-      }
-      // Check if we have new image content
-      else if (currentImageContent) {
-        // Reset interval when clipboard changes
-        currentIntervalRef.current = baseInterval;
-        noChangeCountRef.current = 0;
-
-        // Use image hash to check if we already have this image
-        setClipboardHistory((prev: any) => {
-          // Check if this image already exists in history by hash
-          if (
-            prev.some(
-              (item: any) =>
-                item.type === "image" &&
-                item.imageData?.hash === currentImageContent.hash
-            )
-          ) {
-            return prev;
-          }
-
-          const newItem: ClipboardItem = {
-            id: Date.now(),
-            type: "image",
-            imageData: {
-              width: currentImageContent.width,
-              height: currentImageContent.height,
-              hash: currentImageContent.hash,
-              filePath: currentImageContent.file_path,
-            },
-            timestamp: Date.now(),
-            last_copied: Date.now(),
-            pinned: false,
-            copy_count: 1,
-          };
-
-          const updatedHistory = [newItem, ...prev.slice(0, 99)];
-          saveHistoryToStorage(updatedHistory);
-          return updatedHistory;
-        });
-      } else {
-        // Increase interval gradually when no changes detected
-        noChangeCountRef.current++;
-
-        // Adjust the interval (exponential backoff with cap)
-        if (noChangeCountRef.current > 5) {
-          // Increase interval after 5 consecutive checks with no changes
-          currentIntervalRef.current = Math.min(
-            currentIntervalRef.current * 1.5,
-            maxInterval
-          );
-        }
+      const highlights: Record<number, ClipboardHighlight[]> = {};
+      for (const hit of results.hits) {
+        highlights[hit.item.id] = hit.highlights;
       }
+      setSearchResults({
+        items: results.hits.map((hit) => hit.item),
+        highlights,
+      });
     } catch (error) {
-      console.error("Clipboard monitoring error:", error);
+      console.error("Failed to search clipboard history:", error);
     }
-
-    // Always schedule next check with dynamic interval, even if there was an error
-    clipboardIntervalRef.current = window.setTimeout(
-      checkClipboard,
-      currentIntervalRef.current
-    );
   };
 
-  // Start monitoring clipboard for changes
-  const startClipboardMonitoring = () => {
-    // Initial check
-    checkClipboard();
-  };
+  const clearSearch = () => setSearchResults(null);
 
-  // Stop clipboard monitoring
-  const stopClipboardMonitoring = () => {
-    if (clipboardIntervalRef.current) {
-      clearTimeout(clipboardIntervalRef.current);
-      clipboardIntervalRef.current = null;
-    }
+  // The backend monitor already stored the item, just show it
+  const handleClipboardChange = (newItem: ClipboardItem) => {
+    setClipboardHistory((prev: any) => [
+      newItem,
+      ...prev.filter((item: any) => item.id !== newItem.id),
+    ]);
   };
 
   // Inside your useClipboardHistory hook
@@ -226,32 +210,30 @@ export const useClipboardHistory = () => {
     }
   };
 
+  // Put a history item back with all of its formats, or only its text
+  const restoreHistoryItem = async (id: number, plainText = false) => {
+    // Also updates copy count and lastCopied timestamp for this item
+    const updatedItem = await invoke<ClipboardItem>("restore_clipboard_item", {
+      itemId: id,
+      plainText,
+    });
+    setClipboardHistory((prev: any) =>
+      prev.map((item: any) => (item.id === updatedItem.id ? updatedItem : item))
+    );
+  };
+
   const copyToClipboard = async (text: string) => {
     if (!text) return false;
     try {
-      await invoke("set_clipboard", { text });
-
-      // Reset interval when user explicitly copies something
-      currentIntervalRef.current = baseInterval;
-      noChangeCountRef.current = 0;
-
-      const now = Date.now();
-
-      // Update copy count and lastCopied timestamp for this item
-      setClipboardHistory((prev: any) => {
-        const updatedHistory = prev.map((item: any) => {
-          if (item.text === text) {
-            return {
-              ...item,
-              copy_count: (item.copy_count || 0) + 1,
-              last_copied: now,
-            };
-          }
-          return item;
-        });
-        saveHistoryToStorage(updatedHistory);
-        return updatedHistory;
-      });
+      const copiedItem = clipboardHistory.find(
+        (item: ClipboardItem) => item.type !== "image" && item.text === text
+      );
+      if (copiedItem) {
+        await restoreHistoryItem(copiedItem.id);
+      } else {
+        // Not from the history, e.g. a calculator result
+        await invoke("set_clipboard", { text });
+      }
 
       return true;
     } catch (error) {
@@ -260,52 +242,40 @@ export const useClipboardHistory = () => {
     }
   };
 
-  const clearHistory = async () => {
-    isInClearing.current = true;
-
+  // Paste a history item into the app that was active before the launcher
+  // opened. The backend also hides the window.
+  const pasteHistoryItem = async (id: number, plainText = false) => {
     try {
-      // Filter out image items from the clipboard history
-      const imageItems = clipboardHistory.filter(
-        (item: ClipboardItem) =>
-          item.type === "image" && item.imageData?.filePath
+      const updatedItem = await invoke<ClipboardItem>("paste_clipboard_item", {
+        itemId: id,
+        plainText,
+      });
+      setClipboardHistory((prev: any) =>
+        prev.map((item: any) =>
+          item.id === updatedItem.id ? updatedItem : item
+        )
       );
+      return true;
+    } catch (error) {
+      console.error("Failed to paste clipboard item:", error);
+      return false;
+    }
+  };
 
-      // Only process image deletion if there are actually images
-      if (imageItems.length > 0) {
-        console.log(`Deleting ${imageItems.length} image files...`);
-
-        // Delete each image file
-        for (const item of imageItems) {
-          if (item.imageData?.filePath) {
-            try {
-              await invoke("delete_clipboard_image_file", {
-                filePath: item.imageData.filePath,
-              });
-            } catch (error) {
-              console.error(
-                `Failed to delete image file: ${item.imageData.filePath}`,
-                error
-              );
-            }
-          }
-        }
-      }
+  // pasteHistoryItem already logs the failure
+  const copyAsPlainText = (id: number) => pasteHistoryItem(id, true);
+
+  const clearHistory = async () => {
+    try {
+      // The backend also removes the image files of cleared items
+      await invoke("clear_clipboard_history");
 
       // Clear system clipboard
       await invoke("clear_system_clipboard");
 
-      // Update state and storage
       setClipboardHistory([]);
-      saveHistoryToStorage([]);
     } catch (error) {
       console.error("Error during history clearing:", error);
-    } finally {
-      // Reset the flag after a delay and restart clipboard monitoring
-      setTimeout(() => {
-        isInClearing.current = false;
-        // Force a clipboard check after clearing completes
-        checkClipboard();
-      }, 2000);
     }
   };
 
@@ -319,88 +289,140 @@ export const useClipboardHistory = () => {
   };
 
   const deleteHistoryItem = async (id: number) => {
-    // Set flag to temporarily pause clipboard monitoring
-    isDeleting.current = true;
+    try {
+      // Removes the item and its image file, and clears the system clipboard
+      // if it still holds the item
+      await invoke<ClipboardItem>("delete_and_clear_clipboard_item", {
+        itemId: id,
+      });
 
-    // Find item to be deleted
-    const itemToDelete = clipboardHistory.find((item: any) => item.id === id);
+      setClipboardHistory((prev: any) =>
+        prev.filter((item: any) => item.id !== id)
+      );
+    } catch (error) {
+      console.error("Failed to manage system clipboard:", error);
+    }
+  };
 
+  // While the paste stack is active every copy is queued and Alt+Shift+V
+  // pastes the next one. Starting an active stack only changes the order.
+  const startPasteStack = async (order?: PasteStackOrder) => {
     try {
-      // If the item is at index 0 and is an image, we need special handling
-      const isFirstItem =
-        clipboardHistory.findIndex((item: any) => item.id === id) === 0;
-
-      // Handle image file deletion first
-      if (
-        itemToDelete &&
-        itemToDelete.type === "image" &&
-        itemToDelete.imageData?.filePath
-      ) {
-        await invoke("delete_clipboard_image_file", {
-          filePath: itemToDelete.imageData.filePath,
-        });
-
-        // If it's the first item, we need to clear the system clipboard
-        if (isFirstItem) {
-          await invoke("clear_system_clipboard");
-        }
-      }
+      setPasteStack(
+        await invoke<PasteStackStatus>("start_paste_stack", { order })
+      );
+    } catch (error) {
+      console.error("Failed to start paste stack:", error);
+    }
+  };
 
-      // Update state after file operations
-      setClipboardHistory((prev: any) => {
-        const filtered = prev.filter((item: any) => item.id !== id);
-        saveHistoryToStorage(filtered);
-        return filtered;
-      });
+  const stopPasteStack = async () => {
+    try {
+      setPasteStack(await invoke<PasteStackStatus>("stop_paste_stack"));
+    } catch (error) {
+      console.error("Failed to stop paste stack:", error);
+    }
+  };
 
-      // Text clipboard management (if needed)
-      if (itemToDelete && itemToDelete.type === "text" && itemToDelete.text) {
-        const currentClipboardText = await invoke("get_clipboard");
+  const handleRecoveryNotice = (notice: ClipboardRecoveryNotice) => {
+    console.warn("Clipboard history was recovered:", notice);
+    setRecoveryNotice(notice);
+    loadHistoryFromStorage();
+  };
 
-        if (currentClipboardText === itemToDelete.text) {
-          // Your existing replacement code...
-        }
-      }
+  // Move the unreadable history aside and continue with a new key
+  const resetClipboardKey = async () => {
+    try {
+      await invoke("reset_clipboard_key");
+      setRecoveryNotice(null);
+      setKeyError(null);
+      loadHistoryFromStorage();
     } catch (error) {
-      console.error("Failed to manage system clipboard:", error);
-    } finally {
-      // Reset the flag after a delay and manually trigger a clipboard check
-      setTimeout(() => {
-        isDeleting.current = false;
-        // Force a clipboard check after deletion completes
-        checkClipboard();
-      }, 1000);
+      console.error("Failed to reset clipboard key:", error);
+      setKeyError(String(error));
     }
   };
 
   // Initialize and cleanup
   useEffect(() => {
     loadHistoryFromStorage();
-    startClipboardMonitoring();
-
-    // Reset interval when window regains focus
-    const handleFocus = () => {
-      currentIntervalRef.current = baseInterval;
-      noChangeCountRef.current = 0;
-      // Force a clipboard check when window gets focus
-      checkClipboard();
-    };
 
-    window.addEventListener("focus", handleFocus);
+    // Recovery happens at startup, usually before we start listening
+    invoke<ClipboardRecoveryNotice | null>("take_clipboard_recovery_notice")
+      .then((notice) => notice && handleRecoveryNotice(notice))
+      .catch((error) =>
+        console.error("Failed to check clipboard recovery:", error)
+      );
+    invoke<string | null>("get_clipboard_key_error")
+      .then(setKeyError)
+      .catch((error) =>
+        console.error("Failed to check clipboard key:", error)
+      );
+    const unlistenKeyError = listen<string>(
+      "clipboard-key-unavailable",
+      (event) => setKeyError(event.payload)
+    );
+    invoke<PasteStackStatus>("get_paste_stack")
+      .then(setPasteStack)
+      .catch((error) => console.error("Failed to load paste stack:", error));
+    const unlistenPasteStack = listen<PasteStackStatus>(
+      "clipboard-paste-stack",
+      (event) => setPasteStack(event.payload)
+    );
+
+    const unlistenRecovery = listen<ClipboardRecoveryNotice>(
+      "clipboard-history-recovered",
+      (event) => handleRecoveryNotice(event.payload)
+    );
+
+    // The backend monitor reports every new history item exactly once
+    const unlisten = listen<ClipboardItem>("clipboard-changed", (event) =>
+      handleClipboardChange(event.payload)
+    );
+
+    // Items received from paired devices
+    const unlistenSynced = listen("clipboard-history-synced", () =>
+      loadHistoryFromStorage()
+    );
+
+    // Sensitive items whose time ran out and items removed by retention limits
+    const unlistenExpired = listen<number[]>(
+      "clipboard-items-expired",
+      (event) =>
+        setClipboardHistory((prev: any) =>
+          prev.filter((item: any) => !event.payload.includes(item.id))
+        )
+    );
 
     return () => {
-      stopClipboardMonitoring();
-      window.removeEventListener("focus", handleFocus);
+      unlisten.then((unlistenFn) => unlistenFn());
+      unlistenExpired.then((unlistenFn) => unlistenFn());
+      unlistenRecovery.then((unlistenFn) => unlistenFn());
+      unlistenKeyError.then((unlistenFn) => unlistenFn());
+      unlistenPasteStack.then((unlistenFn) => unlistenFn());
+      unlistenSynced.then((unlistenFn) => unlistenFn());
     };
   }, []);
 
   return {
     clipboardHistory,
-    getFilteredHistory,
+    searchResults,
+    searchHistory,
+    clearSearch,
     copyImageAndHide,
     copyToClipboard,
+    copyAsPlainText,
+    pasteHistoryItem,
     clearHistory,
     deleteHistoryItem,
     refreshClipboardHistory,
+    recoveryNotice,
+    dismissRecoveryNotice: () => setRecoveryNotice(null),
+    keyError,
+    dismissKeyError: () => setKeyError(null),
+    resetClipboardKey,
+    pasteStack,
+    startPasteStack,
+    stopPasteStack,
   };
 };
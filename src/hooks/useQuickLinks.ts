@@ -1,19 +1,21 @@
 import { useState } from "react";
 import { invoke } from "@tauri-apps/api/tauri";
+import { QuickLinkArgument } from "../types";
 
 export function useQuickLinks(setQuery?: (query: string) => void) {
   const [quickLinkQueryData, setQuickLinkQueryData] = useState<{
     id: string;
     name: string;
     command: string;
+    arguments: QuickLinkArgument[];
   } | null>(null);
 
-  const executeQuickLinkWithQuery = async (finalCommand: string) => {
+  const executeQuickLinkWithQuery = async (args: Record<string, string>) => {
     if (!quickLinkQueryData) return;
 
     await invoke("execute_quick_link_with_command", {
       quickLinkId: quickLinkQueryData.id,
-      command: finalCommand,
+      args,
     });
 
     setQuickLinkQueryData(null);
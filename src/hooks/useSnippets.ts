@@ -0,0 +1,32 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/tauri";
+
+export function useSnippets(setQuery?: (query: string) => void) {
+  // Snippet waiting for its {input:Name} values
+  const [snippetInputData, setSnippetInputData] = useState<{
+    id: string;
+    name: string;
+    inputs: string[];
+  } | null>(null);
+
+  const pasteSnippetWithInputs = async (inputs: Record<string, string>) => {
+    if (!snippetInputData) return;
+
+    await invoke("paste_snippet", {
+      snippetId: snippetInputData.id,
+      inputs,
+    });
+
+    setSnippetInputData(null);
+
+    if (setQuery) {
+      setQuery("");
+    }
+  };
+
+  return {
+    snippetInputData,
+    setSnippetInputData,
+    pasteSnippetWithInputs,
+  };
+}
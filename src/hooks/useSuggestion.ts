@@ -1,10 +1,112 @@
-import { useEffect, useState } from "react";
+import { useEffect, useRef, useState } from "react";
 import { invoke } from "@tauri-apps/api/tauri";
+import { listen } from "@tauri-apps/api/event";
 import { shell } from "@tauri-apps/api";
-import { Suggestion, AppInfo, ActionType } from "../types";
+import {
+  Suggestion,
+  AppSearchResult,
+  ActionType,
+  SearchResultsPayload,
+  SnippetMatch,
+} from "../types";
+
+const appResultToSuggestion = (app: AppSearchResult): Suggestion => ({
+  id: app.id,
+  title: app.name,
+  subtitle: `Open ${app.name}`,
+  category: "Applications",
+  icon: app.icon,
+  action: async () => {
+    try {
+      await invoke("open_app", { appId: app.id });
+    } catch (error) {
+      console.error(`Failed to open ${app.name}:`, error);
+    }
+  },
+});
+
+const quickLinkResultToSuggestion = (link: any): Suggestion => ({
+  id: link.id,
+  title: link.name,
+  subtitle: link.description || link.command,
+  category: "Quick Links",
+  icon: link.icon || "🔗",
+  action: async () => {
+    try {
+      await invoke("execute_quick_link", { quickLinkId: link.id });
+    } catch (error) {
+      console.error(`Failed to execute quick link ${link.name}:`, error);
+    }
+  },
+});
+
+const snippetResultToSuggestion = (snippet: SnippetMatch): Suggestion => ({
+  id: snippet.id,
+  title: snippet.name,
+  subtitle: snippet.keyword
+    ? `${snippet.keyword} · ${snippet.text.split("\n")[0]}`
+    : snippet.text.split("\n")[0],
+  category: "Snippets",
+  icon: "📝",
+  snippetInputs: snippet.inputs,
+  action: async () => {
+    try {
+      await invoke("paste_snippet", { snippetId: snippet.id });
+    } catch (error) {
+      console.error(`Failed to paste snippet ${snippet.name}:`, error);
+    }
+  },
+});
 
 export function useSuggestions(query: string): Suggestion[] {
   const [suggestions, setSuggestions] = useState<Suggestion[]>([]);
+  const generationRef = useRef(0);
+
+  // Provider results for the current generation, streamed in by the backend
+  const [providerResults, setProviderResults] = useState<{
+    generation: number;
+    apps: Suggestion[];
+    quickLinks: Suggestion[];
+    snippets: Suggestion[];
+  }>({ generation: 0, apps: [], quickLinks: [], snippets: [] });
+
+  useEffect(() => {
+    const unlisten = listen<SearchResultsPayload>("search-results", (event) => {
+      const payload = event.payload;
+      // Ignore results of outdated queries
+      if (payload.generation !== generationRef.current) return;
+
+      setProviderResults((prev) => {
+        const base =
+          prev.generation === payload.generation
+            ? prev
+            : {
+                generation: payload.generation,
+                apps: [],
+                quickLinks: [],
+                snippets: [],
+              };
+
+        if (payload.provider === "apps") {
+          return { ...base, apps: payload.results.map(appResultToSuggestion) };
+        }
+        if (payload.provider === "snippets") {
+          return {
+            ...base,
+            snippets: payload.results.map(snippetResultToSuggestion),
+          };
+        }
+        return {
+          ...base,
+          quickLinks: payload.results.map(quickLinkResultToSuggestion),
+        };
+      });
+    });
+
+    return () => {
+      unlisten.then((unlistenFn) => unlistenFn());
+    };
+  }, []);
 
   useEffect(() => {
     const fetchSuggestions = async () => {
@@ -12,11 +114,21 @@ export function useSuggestions(query: string): Suggestion[] {
       const trimmedQuery = query.trim();
       const searchQuery = query.startsWith("?") ? query.slice(1) : query;
 
+      // Every query gets a new generation, older ones are cancelled by the backend
+      const generation = ++generationRef.current;
+
       if (!trimmedQuery) {
+        invoke("cancel_search", { generation }).catch(() => {});
         setSuggestions([]);
         return;
       }
 
+      try {
+        await invoke("start_search", { query: trimmedQuery, generation });
+      } catch (error) {
+        console.error("Failed to start search:", error);
+      }
+
       const queryLower = trimmedQuery.toLowerCase();
 
       // Define action patterns with their corresponding suggestion creator functions
@@ -33,6 +145,17 @@ export function useSuggestions(query: string): Suggestion[] {
             icon: "🔗",
           }),
         },
+        {
+          // Matches: snippet, snip, text snippet, etc.
+          match: (q: string) => /^(sni|snip|snippet|text snippet)/i.test(q),
+          createSuggestion: () => ({
+            id: ActionType.CREATE_SNIPPET,
+            title: "Create Snippet",
+            subtitle: "Save text you type often, with placeholders and a keyword",
+            category: "Actions",
+            icon: "📝",
+          }),
+        },
         {
           // Matches: refresh, reload, re, ref, etc.
           match: (q: string) => /^(re|ref|refr|refresh|reload)/i.test(q),
@@ -74,60 +197,6 @@ export function useSuggestions(query: string): Suggestion[] {
         }
       }
 
-      // Fetch app results
-      try {
-        const appResults: AppInfo[] = await invoke("search_apps", {
-          query: trimmedQuery,
-        });
-        appResults.forEach((app) => {
-          results.push({
-            id: app.id,
-            title: app.name,
-            subtitle: `Open ${app.name}`,
-            category: "Applications",
-            icon: app.icon,
-            action: async () => {
-              try {
-                await invoke("open_app", { appId: app.id });
-              } catch (error) {
-                console.error(`Failed to open ${app.name}:`, error);
-              }
-            },
-          });
-        });
-      } catch (error) {
-        console.error("Failed to fetch app suggestions:", error);
-      }
-
-      // Fetch saved quick links
-      try {
-        const quickLinks: any[] = await invoke("search_quick_links", {
-          query: trimmedQuery,
-        });
-
-        quickLinks.forEach((link) => {
-          results.push({
-            id: link.id,
-            title: link.name,
-            subtitle: link.description || link.command,
-            category: "Quick Links",
-            icon: link.icon || "🔗",
-            action: async () => {
-              try {
-                await invoke("execute_quick_link", { quickLinkId: link.id });
-              } catch (error) {
-                console.error(
-                  `Failed to execute quick link ${link.name}:`,
-                  error
-                );
-              }
-            },
-          });
-        });
-      } catch (error) {
-        console.error("Failed to fetch quick links:", error);
-      }
-
       // Always add search actions at the bottom
       const searchActions: Suggestion[] = [
         {
@@ -179,5 +248,26 @@ export function useSuggestions(query: string): Suggestion[] {
     return () => clearTimeout(timeoutId);
   }, [query]);
 
-  return suggestions;
+  // Actions stay first, provider results are inserted as they arrive
+  if (
+    suggestions.length === 0 ||
+    providerResults.generation !== generationRef.current
+  ) {
+    return suggestions;
+  }
+
+  const actionCount = suggestions.findIndex(
+    (suggestion) =>
+      suggestion.category === "Web Search" ||
+      suggestion.category === "File Search"
+  );
+  const splitAt = actionCount === -1 ? suggestions.length : actionCount;
+
+  return [
+    ...suggestions.slice(0, splitAt),
+    ...providerResults.apps,
+    ...providerResults.quickLinks,
+    ...providerResults.snippets,
+    ...suggestions.slice(splitAt),
+  ];
 }
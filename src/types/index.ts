@@ -6,6 +6,8 @@ export interface Suggestion {
   iconColor?: string;
   path?: string;
   category: string;
+  // {input:Name} values a snippet asks for before it is pasted
+  snippetInputs?: string[];
   action?: () => void | Promise<void>;
 }
 
@@ -19,6 +21,36 @@ export interface AppInfo {
   access_count: number;
 }
 
+// Lightweight app match returned by the backend search index
+export interface AppSearchResult {
+  id: string;
+  name: string;
+  icon: string;
+  category: string;
+  score: number;
+}
+
+// Streamed by the backend for every provider of a search generation
+export type SearchResultsPayload =
+  | {
+      generation: number;
+      query: string;
+      provider: "apps";
+      results: AppSearchResult[];
+    }
+  | {
+      generation: number;
+      query: string;
+      provider: "quick_links";
+      results: any[];
+    }
+  | {
+      generation: number;
+      query: string;
+      provider: "snippets";
+      results: SnippetMatch[];
+    };
+
 export enum ActionType {
   APP = "app",
   SEARCH_GOOGLE = "search_google",
@@ -27,6 +59,7 @@ export enum ActionType {
   CREATE_QUICK_LINK = "create_quick_link",
   EXECUTE_QUICK_LINK = "execute_quick_link",
   REFRESH_APP_INDEX = "refresh_app_index",
+  CREATE_SNIPPET = "create_snippet",
 }
 
 // New interface for Quick Links
@@ -41,6 +74,27 @@ export interface QuickLink {
   useCount?: number;
 }
 
+// A {name} or {name:default} placeholder in a quick link command
+export interface QuickLinkArgument {
+  name: string;
+  default: string | null;
+}
+
+export interface Snippet {
+  id: string;
+  name: string;
+  text: string;
+  keyword?: string | null;
+  created_at: number;
+  last_used?: number | null;
+  use_count: number;
+}
+
+// Snippet with the {input:Name} values it asks for
+export interface SnippetMatch extends Snippet {
+  inputs: string[];
+}
+
 // Convert AppInfo from backend to Suggestion for frontend
 export function appToSuggestion(app: AppInfo): Suggestion {
   return {